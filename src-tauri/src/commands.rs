@@ -1,34 +1,63 @@
 use crate::db::{
-    BulkInsertRequest, ColumnInfo, Commit, CommitDetail, CommitStore, ConnectionConfig,
-    ConnectionInfo, ConnectionManager, ConstraintInfo, CredentialStorage, DataOperations,
-    DeleteRequest, DiscoveredDatabase, FilterCondition, IndexInfo, InsertRequest,
-    MigrationOperations, MigrationRequest, MigrationResult, PaginatedResult, QueryResult,
-    SaveCommitChange, SaveCommitRequest, SchemaInfo, SchemaIntrospector, SchemaWithTables,
-    SslMode, TableColumnsInfo, TableInfo, UpdateRequest,
+    ActivityFilter, ActivityInfo, ActivityMonitor, BulkDeleteRequest, BulkInsertRequest,
+    CancellableConn, ColumnInfo, ColumnStats, Commit, CommitDetail, CommitSearchHit, CommitStore,
+    CompositeType, ConnectionConfig, ConnectionInfo, ConnectionManager, ConstraintInfo, CountMode,
+    CredentialStorage, DataOperations, DeleteRequest, DeleteResult, DiscoveredDatabase,
+    DistinctValues, DomainInfo, FilterCondition, FilterGroup, FilterLogic, FilterNode,
+    FunctionInfo, ImportCsvRequest, ImportResult, IndexInfo, IndexUsageStats, InsertRequest,
+    KeysetPage, KeysetRequest, MigrationOperations, MigrationRequest, MigrationResult, OnConflict,
+    OrderSpec, PageCursor, PageRequest, PaginatedResult, PaginationMode, PgColumnStats, PoolStats,
+    QueryHistoryEntry, QueryHistoryStore, QueryResult, ReferencingRows, RlsPolicy,
+    SaveCommitChange, SaveCommitRequest, SchemaDiff, SchemaInfo, SchemaIntrospector,
+    SchemaWithTables, SequenceInfo, SshTunnelConfig, SslMode, TableColumnsInfo, TableInfo,
+    TableSample, TableSizeInfo, TableStats, TransactionRequest, TransactionResult, TriggerInfo,
+    UpdateByFilterRequest, UpdateRequest, UpdateResult, UpsertRequest, ViewDefinition,
 };
 use crate::db::export::{self, ExportedProject};
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use sqlx::Row;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
 use tokio::sync::RwLock;
 
 /// Application state containing the connection manager
 pub struct AppState {
     pub connection_manager: Arc<RwLock<ConnectionManager>>,
+    /// Queries started by `execute_query` or `fetch_table_data` that are still
+    /// running, by query ID
+    pub running_queries: Arc<RwLock<HashMap<String, RunningQuery>>>,
+    /// Where `execute_query` records queries; `None` without an app data directory
+    pub query_history: Option<QueryHistoryStore>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
+        let query_history = QueryHistoryStore::in_app_data_dir()
+            .inspect_err(|e| log::warn!("Query history is disabled: {}", e))
+            .ok();
         Self {
             connection_manager: Arc::new(RwLock::new(ConnectionManager::new())),
+            running_queries: Arc::new(RwLock::new(HashMap::new())),
+            query_history,
         }
     }
 }
 
+/// Where an in-flight query runs, so it can be cancelled
+#[derive(Debug, Clone)]
+pub struct RunningQuery {
+    pub connection_id: String,
+    /// Server process running the query
+    pub pid: i32,
+    /// Set by `cancel_query`, so a cancelled fetch isn't taken for a timeout
+    pub cancelled: Arc<AtomicBool>,
+}
+
 // ============================================================================
 // Connection Commands
 // ============================================================================
@@ -42,7 +71,9 @@ pub struct ConnectRequest {
     pub username: String,
     pub password: String,
     pub ssl_mode: Option<SslMode>,
+    pub ssh_tunnel: Option<SshTunnelConfig>,
     pub save_connection: Option<bool>,
+    pub read_only: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +99,8 @@ pub async fn connect(
     if let Some(ssl_mode) = request.ssl_mode {
         config.ssl_mode = ssl_mode;
     }
+    config.ssh_tunnel = request.ssh_tunnel;
+    config.read_only = request.read_only.unwrap_or(false);
 
     let connection_manager = state.connection_manager.read().await;
     let connection_id = connection_manager.connect(config.clone(), &request.password).await?;
@@ -121,6 +154,7 @@ pub struct TestConnectionRequest {
     pub username: String,
     pub password: String,
     pub ssl_mode: Option<SslMode>,
+    pub ssh_tunnel: Option<SshTunnelConfig>,
 }
 
 #[tauri::command]
@@ -137,12 +171,46 @@ pub async fn test_connection(request: TestConnectionRequest) -> Result<String> {
     if let Some(ssl_mode) = request.ssl_mode {
         config.ssl_mode = ssl_mode;
     }
+    config.ssh_tunnel = request.ssh_tunnel;
 
     ConnectionManager::test_connection(&config, &request.password).await?;
 
     Ok("Connection successful".to_string())
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct ParsedConnectionUrl {
+    pub config: ConnectionConfig,
+    pub password: String,
+}
+
+#[tauri::command]
+pub fn parse_connection_url(url: String) -> Result<ParsedConnectionUrl> {
+    let (config, password) = ConnectionConfig::from_url(&url)?;
+    Ok(ParsedConnectionUrl { config, password })
+}
+
+/// Connect with a pasted `postgres://` URL, keeping its query parameters
+#[tauri::command]
+pub async fn connect_url(
+    state: State<'_, AppState>,
+    name: Option<String>,
+    url: String,
+) -> Result<ConnectResponse> {
+    let (mut config, password) = ConnectionConfig::from_url(&url)?;
+    if let Some(name) = name {
+        config.name = name;
+    }
+
+    let connection_manager = state.connection_manager.read().await;
+    let connection_id = connection_manager.connect(config, &password).await?;
+
+    Ok(ConnectResponse {
+        connection_id,
+        message: "Connected successfully".to_string(),
+    })
+}
+
 #[tauri::command]
 pub async fn list_active_connections(state: State<'_, AppState>) -> Result<Vec<ConnectionInfo>> {
     let connection_manager = state.connection_manager.read().await;
@@ -155,6 +223,60 @@ pub async fn is_connected(state: State<'_, AppState>, connection_id: String) ->
     Ok(connection_manager.is_connected(&connection_id).await)
 }
 
+/// Reopen a connection's pool, with `password` or else the one it was
+/// opened with
+#[tauri::command]
+pub async fn reconnect(
+    state: State<'_, AppState>,
+    connection_id: String,
+    password: Option<String>,
+) -> Result<()> {
+    let connection_manager = state.connection_manager.read().await;
+    match password {
+        Some(password) => {
+            connection_manager
+                .reconnect(&connection_id, &password)
+                .await
+        }
+        None => {
+            connection_manager
+                .reconnect_with_saved_password(&connection_id)
+                .await
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn set_auto_reconnect(state: State<'_, AppState>, enabled: bool) -> Result<()> {
+    state
+        .connection_manager
+        .write()
+        .await
+        .set_auto_reconnect(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_pool_stats(
+    state: State<'_, AppState>,
+    connection_id: String,
+) -> Result<PoolStats> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.pool_stats(&connection_id).await
+}
+
+#[tauri::command]
+pub async fn set_max_connections(
+    state: State<'_, AppState>,
+    connection_id: String,
+    max_connections: u32,
+) -> Result<()> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager
+        .set_max_connections(&connection_id, max_connections)
+        .await
+}
+
 #[tauri::command]
 pub async fn ping_database(
     state: State<'_, AppState>,
@@ -166,15 +288,34 @@ pub async fn ping_database(
         Err(_) => return Ok(false),
     };
 
-    match tokio::time::timeout(
-        Duration::from_secs(5),
-        sqlx::query("SELECT 1").fetch_one(&pool),
-    )
-    .await
-    {
-        Ok(Ok(row)) => Ok(row.get::<i32, _>(0) == 1),
-        _ => Ok(false),
-    }
+    Ok(ConnectionManager::ping(&pool).await)
+}
+
+/// Ping the connection every `interval_secs` in the background, emitting
+/// `connection-lost` with its id when the server stops answering
+#[tauri::command]
+pub async fn start_keepalive(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    interval_secs: u64,
+) -> Result<()> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager
+        .start_keepalive(
+            &connection_id,
+            Duration::from_secs(interval_secs.max(1)),
+            move |id| {
+                let _ = app.emit("connection-lost", id);
+            },
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn stop_keepalive(state: State<'_, AppState>, connection_id: String) -> Result<()> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.stop_keepalive(&connection_id).await
 }
 
 // ============================================================================
@@ -220,8 +361,11 @@ pub fn delete_password(project_id: String) -> Result<()> {
 #[tauri::command]
 pub async fn get_schemas(state: State<'_, AppState>, connection_id: String) -> Result<Vec<SchemaInfo>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-    SchemaIntrospector::get_schemas(&pool).await
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_schemas(&pool).await
+        })
+        .await
 }
 
 #[tauri::command]
@@ -230,8 +374,11 @@ pub async fn get_schemas_with_tables(
     connection_id: String,
 ) -> Result<Vec<SchemaWithTables>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-    SchemaIntrospector::get_schemas_with_tables(&pool).await
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_schemas_with_tables(&pool).await
+        })
+        .await
 }
 
 #[tauri::command]
@@ -241,8 +388,12 @@ pub async fn get_tables(
     schema: String,
 ) -> Result<Vec<TableInfo>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-    SchemaIntrospector::get_tables(&pool, &schema).await
+    let schema = &schema;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_tables(&pool, schema).await
+        })
+        .await
 }
 
 #[tauri::command]
@@ -253,8 +404,12 @@ pub async fn get_columns(
     table: String,
 ) -> Result<Vec<ColumnInfo>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-    SchemaIntrospector::get_columns(&pool, &schema, &table).await
+    let (schema, table) = (&schema, &table);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_columns(&pool, schema, table).await
+        })
+        .await
 }
 
 #[tauri::command]
@@ -264,8 +419,12 @@ pub async fn get_all_columns(
     schemas: Vec<String>,
 ) -> Result<Vec<TableColumnsInfo>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-    SchemaIntrospector::get_all_columns(&pool, &schemas).await
+    let schemas = &schemas;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_all_columns(&pool, schemas).await
+        })
+        .await
 }
 
 #[tauri::command]
@@ -274,10 +433,22 @@ pub async fn get_row_count(
     connection_id: String,
     schema: String,
     table: String,
+    estimate: Option<bool>,
 ) -> Result<i64> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-    SchemaIntrospector::get_row_count(&pool, &schema, &table).await
+    let (schema, table) = (&schema, &table);
+
+    // The estimate avoids a full scan on large tables; the default stays exact
+    let estimate = estimate.unwrap_or(false);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            if estimate {
+                SchemaIntrospector::get_row_count_estimate(&pool, schema, table).await
+            } else {
+                SchemaIntrospector::get_row_count(&pool, schema, table).await
+            }
+        })
+        .await
 }
 
 #[tauri::command]
@@ -288,8 +459,12 @@ pub async fn get_indexes(
     table: String,
 ) -> Result<Vec<IndexInfo>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-    SchemaIntrospector::get_indexes(&pool, &schema, &table).await
+    let (schema, table) = (&schema, &table);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_indexes(&pool, schema, table).await
+        })
+        .await
 }
 
 #[tauri::command]
@@ -300,209 +475,1058 @@ pub async fn get_constraints(
     table: String,
 ) -> Result<Vec<ConstraintInfo>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-    SchemaIntrospector::get_constraints(&pool, &schema, &table).await
+    let (schema, table) = (&schema, &table);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_constraints(&pool, schema, table).await
+        })
+        .await
 }
 
-// ============================================================================
-// Data Commands
-// ============================================================================
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FetchDataRequest {
-    pub connection_id: String,
-    pub schema: String,
-    pub table: String,
-    pub page: Option<i64>,
-    pub page_size: Option<i64>,
-    pub order_by: Option<Vec<String>>,
-    pub order_direction: Option<Vec<String>>,
-    pub filters: Option<Vec<FilterCondition>>,
+#[tauri::command]
+pub async fn get_row_security_policies(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+) -> Result<Vec<RlsPolicy>> {
+    let connection_manager = state.connection_manager.read().await;
+    let (schema, table) = (&schema, &table);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_row_security_policies(&pool, schema, table).await
+        })
+        .await
 }
 
 #[tauri::command]
-pub async fn fetch_table_data(
+pub async fn get_triggers(
     state: State<'_, AppState>,
-    request: FetchDataRequest,
-) -> Result<PaginatedResult> {
+    connection_id: String,
+    schema: String,
+    table: String,
+) -> Result<Vec<TriggerInfo>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&request.connection_id).await?;
-
-    DataOperations::fetch_paginated(
-        &pool,
-        &request.schema,
-        &request.table,
-        request.page.unwrap_or(1),
-        request.page_size,
-        request.order_by.as_ref(),
-        request.order_direction.as_ref(),
-        request.filters.as_ref(),
-    )
-    .await
+    let (schema, table) = (&schema, &table);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_triggers(&pool, schema, table).await
+        })
+        .await
 }
 
 #[tauri::command]
-pub async fn insert_row(
+pub async fn get_trigger_definition(
     state: State<'_, AppState>,
     connection_id: String,
     schema: String,
     table: String,
-    data: serde_json::Map<String, JsonValue>,
-) -> Result<JsonValue> {
+    name: String,
+) -> Result<String> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
+    let (schema, table, name) = (&schema, &table, &name);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_trigger_definition(&pool, schema, table, name).await
+        })
+        .await
+}
 
-    let request = InsertRequest {
-        schema,
-        table,
-        data,
-    };
+#[tauri::command]
+pub async fn get_view_definition(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    name: String,
+) -> Result<ViewDefinition> {
+    let connection_manager = state.connection_manager.read().await;
+    let (schema, name) = (&schema, &name);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_view_definition(&pool, schema, name).await
+        })
+        .await
+}
 
-    DataOperations::insert_row(&pool, request).await
+#[tauri::command]
+pub async fn get_views_with_definitions(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+) -> Result<Vec<ViewDefinition>> {
+    let connection_manager = state.connection_manager.read().await;
+    let schema = &schema;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_views_with_definitions(&pool, schema).await
+        })
+        .await
 }
 
 #[tauri::command]
-pub async fn bulk_insert(
+pub async fn toggle_trigger(
     state: State<'_, AppState>,
     connection_id: String,
     schema: String,
     table: String,
-    rows: Vec<serde_json::Map<String, JsonValue>>,
-) -> Result<u64> {
+    name: String,
+    enabled: bool,
+) -> Result<()> {
     let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
     let pool = connection_manager.get_pool(&connection_id).await?;
-
-    let request = BulkInsertRequest {
-        schema,
-        table,
-        rows,
-    };
-
-    DataOperations::bulk_insert(&pool, request).await
+    SchemaIntrospector::set_trigger_enabled(&pool, &schema, &table, &name, enabled).await
 }
 
 #[tauri::command]
-pub async fn update_row(
+pub async fn get_table_stats(
     state: State<'_, AppState>,
     connection_id: String,
     schema: String,
     table: String,
-    data: serde_json::Map<String, JsonValue>,
-    where_clause: serde_json::Map<String, JsonValue>,
-) -> Result<u64> {
+) -> Result<TableStats> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-
-    let request = UpdateRequest {
-        schema,
-        table,
-        data,
-        where_clause,
-    };
-
-    DataOperations::update_row(&pool, request).await
+    let (schema, table) = (&schema, &table);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_table_stats(&pool, schema, table).await
+        })
+        .await
 }
 
 #[tauri::command]
-pub async fn delete_row(
+pub async fn get_table_size(
     state: State<'_, AppState>,
     connection_id: String,
     schema: String,
     table: String,
-    where_clause: serde_json::Map<String, JsonValue>,
-) -> Result<u64> {
+) -> Result<TableSizeInfo> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-
-    let request = DeleteRequest {
-        schema,
-        table,
-        where_clause,
-    };
-
-    DataOperations::delete_row(&pool, request).await
+    let (schema, table) = (&schema, &table);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_table_size(&pool, schema, table).await
+        })
+        .await
 }
 
 #[tauri::command]
-pub async fn execute_query(
+pub async fn get_all_table_sizes(
     state: State<'_, AppState>,
     connection_id: String,
-    sql: String,
-) -> Result<QueryResult> {
+    schema: String,
+) -> Result<Vec<TableSizeInfo>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-
-    DataOperations::execute_raw_query(&pool, &sql).await
+    let schema = &schema;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_all_table_sizes(&pool, schema).await
+        })
+        .await
 }
 
 #[tauri::command]
-pub async fn execute_migration(
+pub async fn get_index_usage_stats(
     state: State<'_, AppState>,
-    request: MigrationRequest,
-) -> Result<MigrationResult> {
+    connection_id: String,
+    schema: String,
+) -> Result<Vec<IndexUsageStats>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&request.connection_id).await?;
-
-    MigrationOperations::execute_migration(
-        &pool,
-        &request.statements,
-        request.dry_run,
-        request.lock_timeout_ms,
-        request.statement_timeout_ms,
-    )
-    .await
+    let schema = &schema;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_index_usage_stats(&pool, schema).await
+        })
+        .await
 }
 
-// ============================================================================
-// Utility Commands
-// ============================================================================
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DatabaseInfo {
-    pub version: String,
-    pub current_database: String,
-    pub current_user: String,
-    pub server_encoding: String,
-    pub client_encoding: String,
+/// `SchemaIntrospector::get_column_stats`: the planner's `pg_stats` entry for
+/// every column of a table, without scanning it. Named apart from
+/// `get_column_stats`, which profiles one column with live queries.
+#[tauri::command]
+pub async fn get_table_column_stats(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+) -> Result<Vec<PgColumnStats>> {
+    let connection_manager = state.connection_manager.read().await;
+    let (schema, table) = (&schema, &table);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_column_stats(&pool, schema, table).await
+        })
+        .await
 }
 
 #[tauri::command]
-pub async fn get_database_info(
+pub async fn get_sequences(
     state: State<'_, AppState>,
     connection_id: String,
-) -> Result<DatabaseInfo> {
+    schema: String,
+) -> Result<Vec<SequenceInfo>> {
     let connection_manager = state.connection_manager.read().await;
-    let pool = connection_manager.get_pool(&connection_id).await?;
-
-    let version: (String,) = sqlx::query_as("SELECT version()").fetch_one(&pool).await?;
-
-    let current_db: (String,) = sqlx::query_as("SELECT current_database()")
-        .fetch_one(&pool)
-        .await?;
+    let schema = &schema;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_sequences(&pool, schema).await
+        })
+        .await
+}
 
-    let current_user: (String,) = sqlx::query_as("SELECT current_user")
-        .fetch_one(&pool)
-        .await?;
+#[tauri::command]
+pub async fn get_composite_types(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+) -> Result<Vec<CompositeType>> {
+    let connection_manager = state.connection_manager.read().await;
+    let schema = &schema;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_composite_types(&pool, schema).await
+        })
+        .await
+}
 
-    let server_encoding: (String,) = sqlx::query_as("SHOW server_encoding")
-        .fetch_one(&pool)
-        .await?;
+#[tauri::command]
+pub async fn get_domains(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+) -> Result<Vec<DomainInfo>> {
+    let connection_manager = state.connection_manager.read().await;
+    let schema = &schema;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_domains(&pool, schema).await
+        })
+        .await
+}
 
-    let client_encoding: (String,) = sqlx::query_as("SHOW client_encoding")
-        .fetch_one(&pool)
-        .await?;
+#[tauri::command]
+pub async fn get_functions(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+) -> Result<Vec<FunctionInfo>> {
+    let connection_manager = state.connection_manager.read().await;
+    let schema = &schema;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            SchemaIntrospector::get_functions(&pool, schema).await
+        })
+        .await
+}
 
-    Ok(DatabaseInfo {
-        version: version.0,
-        current_database: current_db.0,
-        current_user: current_user.0,
-        server_encoding: server_encoding.0,
-        client_encoding: client_encoding.0,
-    })
+#[tauri::command]
+pub async fn compare_schemas(
+    state: State<'_, AppState>,
+    connection_id_a: String,
+    connection_id_b: String,
+    schema: String,
+) -> Result<SchemaDiff> {
+    let connection_manager = state.connection_manager.read().await;
+    let pool_a = connection_manager.get_pool(&connection_id_a).await?;
+    let pool_b = connection_manager.get_pool(&connection_id_b).await?;
+    SchemaIntrospector::compare_schemas(&pool_a, &pool_b, &schema).await
 }
 
 // ============================================================================
-// Commit History Commands
+// Data Commands
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchDataRequest {
+    pub connection_id: String,
+    pub schema: String,
+    pub table: String,
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+    /// Columns, or `{ "expression": ... }` sort expressions like `lower(name)`
+    pub order_by: Option<Vec<OrderSpec>>,
+    pub order_direction: Option<Vec<String>>,
+    pub filters: Option<Vec<FilterCondition>>,
+    /// Grouped filters with AND/OR logic; takes precedence over `filters`
+    pub filter_groups: Option<Vec<FilterGroup>>,
+    /// Keyset position from a previous page; when set, `page` no longer picks the offset
+    pub cursor: Option<PageCursor>,
+    /// How to compute `total_count`; defaults to an exact COUNT(*)
+    pub count_mode: Option<CountMode>,
+    /// `total_count` returned by an earlier page; when set, the count query is skipped
+    pub total_count_hint: Option<i64>,
+    /// Columns to select; all columns when absent or empty
+    pub columns: Option<Vec<String>>,
+    /// Keep rows where any column contains this text, case-insensitively
+    pub search: Option<String>,
+    /// Cut text, json and bytea cells longer than this; no limit when absent or 0
+    pub max_cell_bytes: Option<usize>,
+    /// Page by a cursor column instead; takes precedence over `page` and `page_size`
+    pub pagination: Option<PaginationMode>,
+    /// Read a random sample of the table instead of all of it
+    pub sample: Option<TableSample>,
+    /// Give up on the count and data queries after this long
+    pub timeout_ms: Option<u32>,
+    /// Makes the fetch cancellable with `cancel_query` while it runs
+    pub query_token: Option<String>,
+}
+
+#[tauri::command]
+pub async fn fetch_table_data(
+    state: State<'_, AppState>,
+    request: FetchDataRequest,
+) -> Result<PaginatedResult> {
+    // Flat filters are a single AND group
+    let filter_groups = request.filter_groups.or_else(|| {
+        request.filters.map(|conditions| {
+            vec![FilterGroup {
+                conditions: conditions.into_iter().map(FilterNode::from).collect(),
+                logic: FilterLogic::And,
+            }]
+        })
+    });
+
+    let pagination = request.pagination.unwrap_or(PaginationMode::OffsetBased {
+        page: request.page.unwrap_or(1),
+        page_size: request.page_size,
+    });
+
+    let page_request = &PageRequest {
+        schema: request.schema,
+        table: request.table,
+        mode: pagination,
+        order_by: request.order_by,
+        order_direction: request.order_direction,
+        filters: filter_groups,
+        cursor: request.cursor,
+        count_mode: request.count_mode.unwrap_or_default(),
+        total_count_hint: request.total_count_hint,
+        columns: request.columns,
+        search: request.search,
+        max_cell_bytes: request.max_cell_bytes,
+        sample: request.sample,
+        timeout_ms: request.timeout_ms,
+    };
+
+    let Some(query_token) = request.query_token else {
+        let connection_manager = state.connection_manager.read().await;
+        return connection_manager
+            .with_reconnect(&request.connection_id, |pool| async move {
+                DataOperations::fetch_paginated(&pool, page_request, None).await
+            })
+            .await;
+    };
+
+    // A cancellable fetch runs on a connection of its own, registered so
+    // `cancel_query` knows which backend to cancel. It isn't retried on a
+    // broken connection, as the user may have cancelled it meanwhile.
+    let pool = {
+        let connection_manager = state.connection_manager.read().await;
+        connection_manager.get_pool(&request.connection_id).await?
+    };
+    let mut conn = pool.acquire().await?;
+    let pid = DataOperations::backend_pid(&mut conn).await?;
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state.running_queries.write().await.insert(
+        query_token.clone(),
+        RunningQuery {
+            connection_id: request.connection_id,
+            pid,
+            cancelled: cancelled.clone(),
+        },
+    );
+
+    let conn = CancellableConn {
+        conn: &mut conn,
+        cancelled: &cancelled,
+    };
+    let result = DataOperations::fetch_paginated(&pool, page_request, Some(conn)).await;
+    state.running_queries.write().await.remove(&query_token);
+
+    result
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchKeysetRequest {
+    pub connection_id: String,
+    pub schema: String,
+    pub table: String,
+    pub page_size: Option<i64>,
+    pub order_by: Option<Vec<String>>,
+    pub order_direction: Option<Vec<String>>,
+    pub filter_groups: Option<Vec<FilterGroup>>,
+    /// `next_cursor` from the previous page; omit for the first page
+    pub cursor: Option<PageCursor>,
+}
+
+#[tauri::command]
+pub async fn fetch_keyset(
+    state: State<'_, AppState>,
+    request: FetchKeysetRequest,
+) -> Result<KeysetPage> {
+    let connection_manager = state.connection_manager.read().await;
+
+    let keyset_request = &KeysetRequest {
+        schema: request.schema,
+        table: request.table,
+        page_size: request.page_size,
+        order_by: request.order_by,
+        order_direction: request.order_direction,
+        filters: request.filter_groups,
+        cursor: request.cursor,
+    };
+    connection_manager
+        .with_reconnect(&request.connection_id, |pool| async move {
+            DataOperations::fetch_keyset(&pool, keyset_request).await
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn export_table_csv(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    filters: Option<Vec<FilterGroup>>,
+    file_path: String,
+) -> Result<u64> {
+    let connection_manager = state.connection_manager.read().await;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    DataOperations::stream_to_csv(
+        &pool,
+        &schema,
+        &table,
+        filters.as_ref(),
+        Path::new(&file_path),
+        |rows_written| {
+            let _ = app.emit("csv-export-progress", rows_written);
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn download_cell(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    where_clause: serde_json::Map<String, JsonValue>,
+    column: String,
+    file_path: String,
+) -> Result<u64> {
+    let connection_manager = state.connection_manager.read().await;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    DataOperations::download_cell(
+        &pool,
+        &schema,
+        &table,
+        &where_clause,
+        &column,
+        Path::new(&file_path),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn fetch_full_cell(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    where_clause: serde_json::Map<String, JsonValue>,
+    column: String,
+) -> Result<JsonValue> {
+    let connection_manager = state.connection_manager.read().await;
+    let (schema, table, where_clause, column) = (&schema, &table, &where_clause, &column);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            DataOperations::fetch_full_cell(&pool, schema, table, where_clause, column).await
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn fetch_referenced_row(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    column: String,
+    value: JsonValue,
+) -> Result<Option<serde_json::Map<String, JsonValue>>> {
+    let connection_manager = state.connection_manager.read().await;
+    let (schema, table, column, value) = (&schema, &table, &column, &value);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            DataOperations::fetch_referenced_row(&pool, schema, table, column, value).await
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn get_referencing_rows(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    key: serde_json::Map<String, JsonValue>,
+) -> Result<Vec<ReferencingRows>> {
+    let connection_manager = state.connection_manager.read().await;
+    let (schema, table, key) = (&schema, &table, &key);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            DataOperations::get_referencing_rows(&pool, schema, table, key).await
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn reset_sequence(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    name: String,
+    value: i64,
+) -> Result<()> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    DataOperations::reset_sequence(&pool, &schema, &name, value).await
+}
+
+#[tauri::command]
+pub async fn set_sequence_value(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    sequence: String,
+    value: i64,
+    is_called: bool,
+) -> Result<()> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    DataOperations::set_sequence_value(&pool, &schema, &sequence, value, is_called).await
+}
+
+#[tauri::command]
+pub async fn get_distinct_values(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    column: String,
+    search: Option<String>,
+    limit: i64,
+) -> Result<DistinctValues> {
+    let connection_manager = state.connection_manager.read().await;
+    let (schema, table, column, search) = (&schema, &table, &column, search.as_deref());
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            DataOperations::distinct_values(&pool, schema, table, column, search, limit).await
+        })
+        .await
+}
+
+/// `DataOperations::column_stats`: one column profiled with live aggregate
+/// queries, falling back to `pg_stats` above `scan_limit` rows.
+/// `get_table_column_stats` reads `pg_stats` for every column instead.
+#[tauri::command]
+pub async fn get_column_stats(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    column: String,
+    scan_limit: Option<i64>,
+) -> Result<ColumnStats> {
+    let connection_manager = state.connection_manager.read().await;
+    let (schema, table, column) = (&schema, &table, &column);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            DataOperations::column_stats(&pool, schema, table, column, scan_limit).await
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn insert_row(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    data: serde_json::Map<String, JsonValue>,
+    on_conflict: Option<OnConflict>,
+) -> Result<JsonValue> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    let request = InsertRequest {
+        schema,
+        table,
+        data,
+        on_conflict,
+    };
+
+    DataOperations::insert_row(&pool, request).await
+}
+
+#[tauri::command]
+pub async fn upsert_row(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    data: serde_json::Map<String, JsonValue>,
+    conflict_columns: Vec<String>,
+    update_columns: Option<Vec<String>>,
+) -> Result<JsonValue> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    let request = UpsertRequest {
+        schema,
+        table,
+        data,
+        conflict_columns,
+        update_columns,
+    };
+
+    DataOperations::upsert_row(&pool, request).await
+}
+
+#[tauri::command]
+pub async fn bulk_insert(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: BulkInsertRequest,
+) -> Result<u64> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    DataOperations::bulk_insert(&pool, request, |progress| {
+        let _ = app.emit("bulk-insert-progress", progress);
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn import_csv(
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: ImportCsvRequest,
+) -> Result<ImportResult> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    DataOperations::import_csv(&pool, request).await
+}
+
+/// Insert a copy of a row with fresh keys, returning the copy
+#[tauri::command]
+pub async fn duplicate_row(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    where_clause: serde_json::Map<String, JsonValue>,
+) -> Result<JsonValue> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    DataOperations::duplicate_row(&pool, &schema, &table, &where_clause).await
+}
+
+/// What a row edit command returns: the bare affected count, as before rows
+/// could be returned, or the full result when `returning` is set
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum RowChange<T> {
+    Count(u64),
+    Rows(T),
+}
+
+#[tauri::command]
+pub async fn update_row(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    data: serde_json::Map<String, JsonValue>,
+    where_clause: serde_json::Map<String, JsonValue>,
+    returning: Option<bool>,
+) -> Result<RowChange<UpdateResult>> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    let returning = returning.unwrap_or_default();
+    let request = UpdateRequest {
+        schema,
+        table,
+        data,
+        where_clause,
+        returning,
+    };
+
+    let result = DataOperations::update_row(&pool, request).await?;
+    Ok(if returning {
+        RowChange::Rows(result)
+    } else {
+        RowChange::Count(result.rows_affected)
+    })
+}
+
+#[tauri::command]
+pub async fn delete_row(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    where_clause: serde_json::Map<String, JsonValue>,
+    returning: Option<bool>,
+) -> Result<RowChange<DeleteResult>> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    let returning = returning.unwrap_or_default();
+    let request = DeleteRequest {
+        schema,
+        table,
+        where_clause,
+        returning,
+    };
+
+    let result = DataOperations::delete_row(&pool, request).await?;
+    Ok(if returning {
+        RowChange::Rows(result)
+    } else {
+        RowChange::Count(result.rows_affected)
+    })
+}
+
+#[tauri::command]
+pub async fn bulk_delete(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    filters: Vec<FilterCondition>,
+    limit: Option<i64>,
+) -> Result<u64> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    let request = BulkDeleteRequest {
+        schema,
+        table,
+        filters,
+        limit,
+    };
+
+    DataOperations::bulk_delete(&pool, request).await
+}
+
+#[tauri::command]
+pub async fn update_by_filter(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    data: serde_json::Map<String, JsonValue>,
+    filters: Vec<FilterCondition>,
+) -> Result<u64> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    let request = UpdateByFilterRequest {
+        schema,
+        table,
+        data,
+        filters,
+    };
+
+    DataOperations::update_by_filter(&pool, request).await
+}
+
+/// How many rows `update_by_filter` or `bulk_delete` would change
+#[tauri::command]
+pub async fn count_matching_rows(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    filters: Vec<FilterCondition>,
+) -> Result<u64> {
+    let connection_manager = state.connection_manager.read().await;
+    let (schema, table, filters) = (&schema, &table, &filters);
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            DataOperations::count_matching_rows(&pool, schema, table, filters).await
+        })
+        .await
+}
+
+/// Empty a table. `confirmation` must be `schema.table`, typed by the user.
+#[tauri::command]
+pub async fn truncate_table(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: String,
+    table: String,
+    cascade: bool,
+    confirmation: String,
+) -> Result<u64> {
+    DataOperations::check_truncate_confirmation(&schema, &table, &confirmation)?;
+
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&connection_id).await?;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    DataOperations::truncate_table(&pool, &schema, &table, cascade).await
+}
+
+/// Run ad-hoc SQL. Passing a `query_id` makes the query cancellable with
+/// `cancel_query` while it runs.
+#[tauri::command]
+pub async fn execute_query(
+    state: State<'_, AppState>,
+    connection_id: String,
+    sql: String,
+    query_id: Option<String>,
+) -> Result<QueryResult> {
+    let (pool, read_only) = {
+        let connection_manager = state.connection_manager.read().await;
+        (
+            connection_manager.get_pool(&connection_id).await?,
+            connection_manager.is_read_only(&connection_id).await?,
+        )
+    };
+    let mut conn = pool.acquire().await?;
+    let history = state.query_history.as_ref();
+
+    let Some(query_id) = query_id else {
+        return DataOperations::execute_raw_query(
+            &mut conn,
+            history,
+            &connection_id,
+            &sql,
+            read_only,
+        )
+        .await;
+    };
+
+    let pid = DataOperations::backend_pid(&mut conn).await?;
+    state.running_queries.write().await.insert(
+        query_id.clone(),
+        RunningQuery {
+            connection_id: connection_id.clone(),
+            pid,
+            cancelled: Arc::default(),
+        },
+    );
+    let result =
+        DataOperations::execute_raw_query(&mut conn, history, &connection_id, &sql, read_only)
+            .await;
+    state.running_queries.write().await.remove(&query_id);
+
+    result
+}
+
+/// Cancel a query started with `execute_query`, or a `fetch_table_data` call,
+/// by its query ID or token. Returns false when the query already finished.
+#[tauri::command]
+pub async fn cancel_query(state: State<'_, AppState>, query_id: String) -> Result<bool> {
+    let Some(running) = state.running_queries.read().await.get(&query_id).cloned() else {
+        return Ok(false);
+    };
+    let connection_manager = state.connection_manager.read().await;
+    let pool = connection_manager.get_pool(&running.connection_id).await?;
+
+    running.cancelled.store(true, Ordering::SeqCst);
+    DataOperations::cancel_backend(&pool, running.pid).await
+}
+
+#[tauri::command]
+pub async fn explain_query(
+    state: State<'_, AppState>,
+    connection_id: String,
+    sql: String,
+    analyze: bool,
+    verbose: bool,
+    buffers: bool,
+) -> Result<JsonValue> {
+    let connection_manager = state.connection_manager.read().await;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    DataOperations::explain_query(&pool, &sql, analyze, verbose, buffers).await
+}
+
+#[tauri::command]
+pub async fn execute_migration(
+    state: State<'_, AppState>,
+    request: MigrationRequest,
+) -> Result<MigrationResult> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&request.connection_id).await?;
+    let pool = connection_manager.get_pool(&request.connection_id).await?;
+
+    MigrationOperations::execute_migration(
+        &pool,
+        &request.statements,
+        request.dry_run,
+        request.lock_timeout_ms,
+        request.statement_timeout_ms,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn execute_transaction(
+    state: State<'_, AppState>,
+    request: TransactionRequest,
+) -> Result<TransactionResult> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager.ensure_writable(&request.connection_id).await?;
+    let pool = connection_manager.get_pool(&request.connection_id).await?;
+
+    DataOperations::execute_transaction(&pool, &request.statements, request.timeout_ms).await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerateRollbackRequest {
+    pub connection_id: String,
+    /// Commit store the rollback is saved to
+    pub project_id: String,
+    pub statements: Vec<String>,
+    /// Result of running `statements`, saved alongside the rollback. When it
+    /// was committed, its `rollback` is used instead of inferring one.
+    pub result: Option<MigrationResult>,
+}
+
+#[tauri::command]
+pub async fn generate_rollback_sql(
+    state: State<'_, AppState>,
+    request: GenerateRollbackRequest,
+) -> Result<Vec<String>> {
+    let connection_manager = state.connection_manager.read().await;
+    let pool = connection_manager.get_pool(&request.connection_id).await?;
+
+    // A committed migration already captured its rollback before anything it
+    // dropped was gone
+    let rollback = match request.result.as_ref().filter(|r| r.committed) {
+        Some(result) => result.rollback.clone(),
+        None => MigrationOperations::generate_rollback(&pool, &request.statements).await?,
+    };
+    CommitStore::save_rollback(
+        &request.project_id,
+        &request.statements,
+        &rollback,
+        request.result.as_ref(),
+    )
+    .map_err(crate::error::DbViewerError::Configuration)?;
+
+    Ok(rollback)
+}
+
+// ============================================================================
+// Utility Commands
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseInfo {
+    pub version: String,
+    pub current_database: String,
+    pub current_user: String,
+    pub server_encoding: String,
+    pub client_encoding: String,
+}
+
+#[tauri::command]
+pub async fn get_database_info(
+    state: State<'_, AppState>,
+    connection_id: String,
+) -> Result<DatabaseInfo> {
+    let connection_manager = state.connection_manager.read().await;
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            let version: (String,) = sqlx::query_as("SELECT version()").fetch_one(&pool).await?;
+
+            let current_db: (String,) = sqlx::query_as("SELECT current_database()")
+                .fetch_one(&pool)
+                .await?;
+
+            let current_user: (String,) = sqlx::query_as("SELECT current_user")
+                .fetch_one(&pool)
+                .await?;
+
+            let server_encoding: (String,) = sqlx::query_as("SHOW server_encoding")
+                .fetch_one(&pool)
+                .await?;
+
+            let client_encoding: (String,) = sqlx::query_as("SHOW client_encoding")
+                .fetch_one(&pool)
+                .await?;
+
+            Ok(DatabaseInfo {
+                version: version.0,
+                current_database: current_db.0,
+                current_user: current_user.0,
+                server_encoding: server_encoding.0,
+                client_encoding: client_encoding.0,
+            })
+        })
+        .await
+}
+
+/// Backends picked by `filter`, by default those of the current database
+#[tauri::command]
+pub async fn list_activity(
+    state: State<'_, AppState>,
+    connection_id: String,
+    filter: Option<ActivityFilter>,
+) -> Result<Vec<ActivityInfo>> {
+    let connection_manager = state.connection_manager.read().await;
+    let filter = filter.unwrap_or_default();
+    connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            ActivityMonitor::list_backends(&pool, filter).await
+        })
+        .await
+}
+
+/// Cancel a backend's running query, or with `force` terminate its session
+#[tauri::command]
+pub async fn terminate_backend(
+    state: State<'_, AppState>,
+    connection_id: String,
+    pid: i32,
+    force: bool,
+) -> Result<bool> {
+    let connection_manager = state.connection_manager.read().await;
+    let pool = connection_manager.get_pool(&connection_id).await?;
+
+    ActivityMonitor::terminate_backend(&pool, pid, force).await
+}
+
+// ============================================================================
+// Commit History Commands
 // ============================================================================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -535,6 +1559,98 @@ pub fn get_commit_detail(project_id: String, commit_id: String) -> Result<Commit
         .map_err(|e| crate::error::DbViewerError::Configuration(e))
 }
 
+/// Commits matching `query` in their message or changes, newest first, 50 by
+/// default. Each call runs a search, so debounce it when searching as the user types.
+#[tauri::command]
+pub fn search_commits(
+    project_id: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<CommitSearchHit>> {
+    CommitStore::search_commits(&project_id, &query, limit.unwrap_or(50))
+        .map_err(crate::error::DbViewerError::Configuration)
+}
+
+/// SQL undoing a saved commit, to review and run with `execute_migration`.
+/// Column types come from `connection_id`, so array and json values are
+/// written for the columns they go back into.
+#[tauri::command]
+pub async fn revert_commit(
+    state: State<'_, AppState>,
+    connection_id: String,
+    project_id: String,
+    commit_id: String,
+) -> Result<Vec<String>> {
+    let detail = CommitStore::get_commit_detail(&project_id, &commit_id)
+        .map_err(crate::error::DbViewerError::Configuration)?;
+    let mut tables: Vec<(String, String)> = detail
+        .changes
+        .into_iter()
+        .map(|c| (c.schema_name, c.table_name))
+        .collect();
+    tables.sort();
+    tables.dedup();
+
+    let connection_manager = state.connection_manager.read().await;
+    let tables = &tables;
+    let column_types = connection_manager
+        .with_reconnect(&connection_id, |pool| async move {
+            let mut types = HashMap::new();
+            for (schema, table) in tables {
+                let columns = SchemaIntrospector::get_columns(&pool, schema, table).await?;
+                let table_types: HashMap<String, String> =
+                    columns.into_iter().map(|c| (c.name, c.data_type)).collect();
+                types.insert((schema.clone(), table.clone()), table_types);
+            }
+            Ok(types)
+        })
+        .await?;
+
+    CommitStore::revert_commit(&project_id, &commit_id, &column_types)
+        .map_err(crate::error::DbViewerError::Configuration)
+}
+
+// ============================================================================
+// Query History Commands
+// ============================================================================
+
+const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+fn query_history(state: &AppState) -> Result<&QueryHistoryStore> {
+    state.query_history.as_ref().ok_or_else(|| {
+        crate::error::DbViewerError::Configuration("Could not find app data directory".to_string())
+    })
+}
+
+#[tauri::command]
+pub fn get_query_history(
+    state: State<'_, AppState>,
+    connection_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<QueryHistoryEntry>> {
+    query_history(&state)?
+        .get_history(&connection_id, limit.unwrap_or(DEFAULT_HISTORY_LIMIT))
+        .map_err(crate::error::DbViewerError::Configuration)
+}
+
+#[tauri::command]
+pub fn search_query_history(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<QueryHistoryEntry>> {
+    query_history(&state)?
+        .search_history(&query, limit.unwrap_or(DEFAULT_HISTORY_LIMIT))
+        .map_err(crate::error::DbViewerError::Configuration)
+}
+
+#[tauri::command]
+pub fn clear_query_history(state: State<'_, AppState>, connection_id: String) -> Result<()> {
+    query_history(&state)?
+        .clear_history(&connection_id)
+        .map_err(crate::error::DbViewerError::Configuration)
+}
+
 // ============================================================================
 // Export/Import Commands
 // ============================================================================
@@ -1,6 +1,9 @@
+use crate::db::data::{escape_like_pattern, pg_array_literal, quote_identifier, MigrationResult};
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +36,14 @@ pub struct CommitDetail {
     pub changes: Vec<CommitChange>,
 }
 
+/// A commit found by `search_commits`, with those of its changes whose SQL or
+/// data matched. `changes` is empty when only the message or summary did.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitSearchHit {
+    pub commit: Commit,
+    pub changes: Vec<CommitChange>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SaveCommitRequest {
     pub project_id: String,
@@ -54,6 +65,10 @@ pub struct SaveCommitChange {
 
 pub struct CommitStore;
 
+/// The trigram tokenizer matches substrings, like the LIKE fallback, but needs
+/// at least three characters to match on
+const FTS_MIN_QUERY_CHARS: usize = 3;
+
 impl CommitStore {
     fn db_path(project_id: &str) -> Result<PathBuf, String> {
         let data_dir = dirs::data_dir()
@@ -89,12 +104,63 @@ impl CommitStore {
                 sql TEXT NOT NULL,
                 sort_order INTEGER NOT NULL
             );
-            CREATE INDEX IF NOT EXISTS idx_commit_changes_commit_id ON commit_changes(commit_id);"
+            CREATE INDEX IF NOT EXISTS idx_commit_changes_commit_id ON commit_changes(commit_id);
+            CREATE TABLE IF NOT EXISTS migration_rollbacks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                statements TEXT NOT NULL,
+                rollback_statements TEXT NOT NULL,
+                migration_result TEXT
+            );"
         ).map_err(|e| format!("Failed to initialize commit tables: {}", e))?;
+        Self::create_search_index(&conn);
 
         Ok(conn)
     }
 
+    /// Create full-text indexes over commit messages and change SQL and data,
+    /// filling them from existing rows the first time. Without FTS5 in the
+    /// SQLite build this does nothing, and searches fall back to LIKE.
+    fn create_search_index(conn: &Connection) {
+        if Self::has_search_index(conn) {
+            return;
+        }
+        // Commits and changes are never deleted, so only inserts are mirrored
+        let created = conn.execute_batch(
+            "BEGIN;
+            CREATE VIRTUAL TABLE commits_fts USING fts5(
+                message, summary, content='commits', tokenize='trigram'
+            );
+            CREATE VIRTUAL TABLE commit_changes_fts USING fts5(
+                sql, data, content='commit_changes', content_rowid='id', tokenize='trigram'
+            );
+            CREATE TRIGGER commits_fts_insert AFTER INSERT ON commits BEGIN
+                INSERT INTO commits_fts (rowid, message, summary)
+                VALUES (new.rowid, new.message, new.summary);
+            END;
+            CREATE TRIGGER commit_changes_fts_insert AFTER INSERT ON commit_changes BEGIN
+                INSERT INTO commit_changes_fts (rowid, sql, data)
+                VALUES (new.id, new.sql, new.data);
+            END;
+            INSERT INTO commits_fts (commits_fts) VALUES ('rebuild');
+            INSERT INTO commit_changes_fts (commit_changes_fts) VALUES ('rebuild');
+            COMMIT;",
+        );
+        if let Err(e) = created {
+            let _ = conn.execute_batch("ROLLBACK");
+            log::info!("Commit search falls back to LIKE: {}", e);
+        }
+    }
+
+    fn has_search_index(conn: &Connection) -> bool {
+        conn.query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'commit_changes_fts'",
+            [],
+            |_| Ok(()),
+        )
+        .is_ok()
+    }
+
     fn generate_hash(parent_id: &Option<String>, timestamp: &str, sql_statements: &[String]) -> String {
         let mut hasher = Sha256::new();
         hasher.update(parent_id.as_deref().unwrap_or("root"));
@@ -224,4 +290,511 @@ impl CommitStore {
 
         Ok(CommitDetail { commit, changes })
     }
+
+    /// Commits whose message or summary contains `query`, or with a change
+    /// whose SQL or data does, newest first. Matching ignores ASCII case.
+    pub fn search_commits(
+        project_id: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<CommitSearchHit>, String> {
+        let conn = Self::open(project_id)?;
+        let query = query.trim();
+        let use_fts = query.chars().count() >= FTS_MIN_QUERY_CHARS && Self::has_search_index(&conn);
+        Self::search(&conn, query, limit, use_fts)
+    }
+
+    fn search(
+        conn: &Connection,
+        query: &str,
+        limit: usize,
+        use_fts: bool,
+    ) -> Result<Vec<CommitSearchHit>, String> {
+        if query.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let (pattern, commit_matches, change_matches) = if use_fts {
+            // One quoted phrase, so FTS5 query syntax in `query` is matched literally
+            (
+                format!("\"{}\"", query.replace('"', "\"\"")),
+                "rowid IN (SELECT rowid FROM commits_fts WHERE commits_fts MATCH ?1)",
+                "id IN (SELECT rowid FROM commit_changes_fts WHERE commit_changes_fts MATCH ?1)",
+            )
+        } else {
+            (
+                format!("%{}%", escape_like_pattern(query)),
+                "message LIKE ?1 ESCAPE '\\' OR summary LIKE ?1 ESCAPE '\\'",
+                "sql LIKE ?1 ESCAPE '\\' OR data LIKE ?1 ESCAPE '\\'",
+            )
+        };
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id, parent_id, message, summary, created_at, change_count
+                 FROM commits
+                 WHERE {} OR id IN (SELECT commit_id FROM commit_changes WHERE {})
+                 ORDER BY created_at DESC
+                 LIMIT ?2",
+                commit_matches, change_matches
+            ))
+            .map_err(|e| format!("Failed to search commits: {}", e))?;
+        let commits = stmt
+            .query_map(params![pattern, limit as i64], commit_from_row)
+            .map_err(|e| format!("Failed to read commits: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect commits: {}", e))?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id, commit_id, type, schema_name, table_name, data, original_data, sql, sort_order
+                 FROM commit_changes WHERE commit_id = ?2 AND ({}) ORDER BY sort_order",
+                change_matches
+            ))
+            .map_err(|e| format!("Failed to search commit changes: {}", e))?;
+        commits
+            .into_iter()
+            .map(|commit| {
+                let changes = stmt
+                    .query_map(params![pattern, commit.id], change_from_row)
+                    .map_err(|e| format!("Failed to read commit changes: {}", e))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Failed to collect commit changes: {}", e))?;
+                Ok(CommitSearchHit { commit, changes })
+            })
+            .collect()
+    }
+
+    /// SQL that undoes `commit_id`, last change first, ready to pass to
+    /// `execute_migration`. Rows are matched on the columns the original
+    /// statements' WHERE clauses used; inserts borrow those of another change
+    /// to the same table, else match on every inserted column. Array and JSON
+    /// values are written for their column's SQL type in `column_types`,
+    /// keyed by schema and table, and as jsonb when it isn't known.
+    pub fn revert_commit(
+        project_id: &str,
+        commit_id: &str,
+        column_types: &HashMap<(String, String), HashMap<String, String>>,
+    ) -> Result<Vec<String>, String> {
+        let detail = Self::get_commit_detail(project_id, commit_id)?;
+
+        let mut key_columns: HashMap<(&str, &str), Vec<String>> = HashMap::new();
+        for change in &detail.changes {
+            let columns = where_columns(&change.sql);
+            if !columns.is_empty() {
+                key_columns
+                    .entry((&change.schema_name, &change.table_name))
+                    .or_insert(columns);
+            }
+        }
+
+        let mut statements = Vec::new();
+        for change in detail.changes.iter().rev() {
+            let keys = key_columns
+                .get(&(change.schema_name.as_str(), change.table_name.as_str()))
+                .map(Vec::as_slice);
+            let types = column_types.get(&(change.schema_name.clone(), change.table_name.clone()));
+            if let Some(sql) = Self::revert_change(change, keys, types)? {
+                statements.push(sql);
+            }
+        }
+        Ok(statements)
+    }
+
+    /// The inverse of one change, or `None` for an update that changed nothing
+    fn revert_change(
+        change: &CommitChange,
+        table_keys: Option<&[String]>,
+        types: Option<&HashMap<String, String>>,
+    ) -> Result<Option<String>, String> {
+        let literal = |column: &str, value: &JsonValue| {
+            sql_literal(value, types.and_then(|t| t.get(column)).map(String::as_str))
+        };
+        let table = format!(
+            "{}.{}",
+            quote_identifier(&change.schema_name),
+            quote_identifier(&change.table_name)
+        );
+        let data = parse_row(&change.data)?;
+        let original = change.original_data.as_deref().map(parse_row).transpose()?;
+
+        let sql = match change.change_type.as_str() {
+            "insert" => {
+                let keys = match table_keys {
+                    Some(keys) if keys.iter().all(|k| data.contains_key(k)) => keys.to_vec(),
+                    _ => data.keys().cloned().collect(),
+                };
+                format!(
+                    "DELETE FROM {} WHERE {}",
+                    table,
+                    match_row(&keys, &data, None, &literal)
+                )
+            }
+            "delete" => {
+                let row = original.as_ref().unwrap_or(&data);
+                let columns: Vec<String> = row.keys().map(|k| quote_identifier(k)).collect();
+                let values: Vec<String> = row.iter().map(|(k, v)| literal(k, v)).collect();
+                format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    table,
+                    columns.join(", "),
+                    values.join(", ")
+                )
+            }
+            "update" => {
+                let original = original.ok_or_else(|| {
+                    format!("Change {} has no original data to restore", change.id)
+                })?;
+                let set: Vec<String> = data
+                    .iter()
+                    .filter_map(|(k, v)| {
+                        let old = original.get(k)?;
+                        (old != v).then(|| format!("{} = {}", quote_identifier(k), literal(k, old)))
+                    })
+                    .collect();
+                if set.is_empty() {
+                    return Ok(None);
+                }
+                let mut keys = where_columns(&change.sql);
+                if keys.is_empty() {
+                    keys = original.keys().cloned().collect();
+                }
+                // The row now holds the new values, key columns included
+                format!(
+                    "UPDATE {} SET {} WHERE {}",
+                    table,
+                    set.join(", "),
+                    match_row(&keys, &data, Some(&original), &literal)
+                )
+            }
+            other => return Err(format!("Unknown change type: {}", other)),
+        };
+        Ok(Some(sql))
+    }
+
+    /// Store the rollback generated for a migration, with the migration's
+    /// statements and result, as JSON. Returns the new row id.
+    pub fn save_rollback(
+        project_id: &str,
+        statements: &[String],
+        rollback: &[String],
+        result: Option<&MigrationResult>,
+    ) -> Result<i64, String> {
+        let conn = Self::open(project_id)?;
+        let json_err = |e: serde_json::Error| format!("Failed to serialize migration: {}", e);
+        let statements = serde_json::to_string(statements).map_err(json_err)?;
+        let rollback = serde_json::to_string(rollback).map_err(json_err)?;
+        let result = result
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(json_err)?;
+
+        conn.execute(
+            "INSERT INTO migration_rollbacks (created_at, statements, rollback_statements, migration_result)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![chrono::Utc::now().to_rfc3339(), statements, rollback, result],
+        )
+        .map_err(|e| format!("Failed to insert migration rollback: {}", e))?;
+
+        Ok(conn.last_insert_rowid())
+    }
+}
+
+fn commit_from_row(row: &rusqlite::Row) -> rusqlite::Result<Commit> {
+    Ok(Commit {
+        id: row.get(0)?,
+        parent_id: row.get(1)?,
+        message: row.get(2)?,
+        summary: row.get(3)?,
+        created_at: row.get(4)?,
+        change_count: row.get(5)?,
+    })
+}
+
+fn change_from_row(row: &rusqlite::Row) -> rusqlite::Result<CommitChange> {
+    Ok(CommitChange {
+        id: row.get(0)?,
+        commit_id: row.get(1)?,
+        change_type: row.get(2)?,
+        schema_name: row.get(3)?,
+        table_name: row.get(4)?,
+        data: row.get(5)?,
+        original_data: row.get(6)?,
+        sql: row.get(7)?,
+        sort_order: row.get(8)?,
+    })
+}
+
+fn parse_row(json: &str) -> Result<Map<String, JsonValue>, String> {
+    serde_json::from_str(json).map_err(|e| format!("Failed to parse change data: {}", e))
+}
+
+/// `"a" = 1 AND "b" IS NULL` over `keys`, taking values from `row` and then
+/// `fallback`, each written by `literal`
+fn match_row(
+    keys: &[String],
+    row: &Map<String, JsonValue>,
+    fallback: Option<&Map<String, JsonValue>>,
+    literal: &dyn Fn(&str, &JsonValue) -> String,
+) -> String {
+    keys.iter()
+        .map(|key| {
+            let value = row
+                .get(key)
+                .or_else(|| fallback.and_then(|f| f.get(key)))
+                .unwrap_or(&JsonValue::Null);
+            match value {
+                JsonValue::Null => format!("{} IS NULL", quote_identifier(key)),
+                value => format!("{} = {}", quote_identifier(key), literal(key, value)),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// A literal for `value`, formatted the way the frontend writes staged
+/// changes. Arrays and objects are cast to `column_type`, with arrays as
+/// Postgres array literals for array columns, and to jsonb when the type is
+/// unknown.
+fn sql_literal(value: &JsonValue, column_type: Option<&str>) -> String {
+    match (value, column_type) {
+        (JsonValue::Null, _) => "NULL".to_string(),
+        (JsonValue::Bool(b), _) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        (JsonValue::Number(n), _) => n.to_string(),
+        (JsonValue::String(s), _) => format!("'{}'", s.replace('\'', "''")),
+        (JsonValue::Array(items), Some(ty)) if ty.ends_with("[]") => {
+            format!("'{}'::{}", pg_array_literal(items).replace('\'', "''"), ty)
+        }
+        (JsonValue::Array(_) | JsonValue::Object(_), ty) => format!(
+            "'{}'::{}",
+            value.to_string().replace('\'', "''"),
+            ty.unwrap_or("jsonb")
+        ),
+    }
+}
+
+/// Columns named in the top-level WHERE clause of `sql`, skipping string
+/// literals, in order of first appearance
+fn where_columns(sql: &str) -> Vec<String> {
+    let bytes = sql.as_bytes();
+    let mut columns: Vec<String> = Vec::new();
+    let mut in_where = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                let start = i + 1;
+                let mut end = start;
+                // A doubled quote is an escaped one
+                while end < bytes.len() {
+                    if bytes[end] == quote {
+                        if bytes.get(end + 1) == Some(&quote) {
+                            end += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    end += 1;
+                }
+                if quote == b'"' && in_where {
+                    let column = sql[start..end.min(bytes.len())].replace("\"\"", "\"");
+                    if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+                i = end + 1;
+            }
+            _ => {
+                let boundary = |j: usize| bytes.get(j).is_none_or(|b| b.is_ascii_whitespace());
+                if !in_where
+                    && bytes.len() >= i + 5
+                    && bytes[i..i + 5].eq_ignore_ascii_case(b"WHERE")
+                    && (i == 0 || boundary(i - 1))
+                    && boundary(i + 5)
+                {
+                    in_where = true;
+                    i += 5;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(
+        change_type: &str,
+        data: &str,
+        original_data: Option<&str>,
+        sql: &str,
+    ) -> CommitChange {
+        CommitChange {
+            id: 1,
+            commit_id: "abc".to_string(),
+            change_type: change_type.to_string(),
+            schema_name: "public".to_string(),
+            table_name: "users".to_string(),
+            data: data.to_string(),
+            original_data: original_data.map(str::to_string),
+            sql: sql.to_string(),
+            sort_order: 0,
+        }
+    }
+
+    #[test]
+    fn test_where_columns() {
+        assert_eq!(
+            where_columns(
+                r#"UPDATE "public"."users" SET "note" = 'a WHERE "x" = 1' WHERE "id" = 1 AND "org""id" IS NULL"#
+            ),
+            vec!["id".to_string(), "org\"id".to_string()]
+        );
+        assert!(where_columns(r#"INSERT INTO "public"."users" ("id") VALUES (1)"#).is_empty());
+    }
+
+    #[test]
+    fn test_revert_change() {
+        let update = change(
+            "update",
+            r#"{"id": 1, "name": "Bob", "tags": ["a"]}"#,
+            Some(r#"{"id": 1, "name": "O'Neil", "tags": ["a"]}"#),
+            r#"UPDATE "public"."users" SET "name" = 'Bob' WHERE "id" = 1"#,
+        );
+        assert_eq!(
+            CommitStore::revert_change(&update, None, None)
+                .unwrap()
+                .unwrap(),
+            r#"UPDATE "public"."users" SET "name" = 'O''Neil' WHERE "id" = 1"#
+        );
+
+        let delete = change(
+            "delete",
+            r#"{"id": 2, "active": true, "meta": {"k": "v"}}"#,
+            None,
+            r#"DELETE FROM "public"."users" WHERE "id" = 2"#,
+        );
+        assert_eq!(
+            CommitStore::revert_change(&delete, None, None)
+                .unwrap()
+                .unwrap(),
+            r#"INSERT INTO "public"."users" ("active", "id", "meta") VALUES (TRUE, 2, '{"k":"v"}'::jsonb)"#
+        );
+
+        let insert = change(
+            "insert",
+            r#"{"id": 3, "name": null}"#,
+            None,
+            r#"INSERT INTO "public"."users" ("id", "name") VALUES (3, NULL)"#,
+        );
+        assert_eq!(
+            CommitStore::revert_change(&insert, None, None)
+                .unwrap()
+                .unwrap(),
+            r#"DELETE FROM "public"."users" WHERE "id" = 3 AND "name" IS NULL"#
+        );
+        let keys = ["id".to_string()];
+        assert_eq!(
+            CommitStore::revert_change(&insert, Some(&keys), None)
+                .unwrap()
+                .unwrap(),
+            r#"DELETE FROM "public"."users" WHERE "id" = 3"#
+        );
+
+        // With column types, arrays and json are written for their columns
+        let types = HashMap::from([
+            ("tags".to_string(), "text[]".to_string()),
+            ("meta".to_string(), "json".to_string()),
+        ]);
+        let retag = change(
+            "update",
+            r#"{"id": 1, "tags": ["b"], "meta": {}}"#,
+            Some(r#"{"id": 1, "tags": ["it's", null], "meta": {"k": "v"}}"#),
+            r#"UPDATE "public"."users" SET "tags" = '{"b"}'::text[] WHERE "id" = 1"#,
+        );
+        assert_eq!(
+            CommitStore::revert_change(&retag, None, Some(&types))
+                .unwrap()
+                .unwrap(),
+            r#"UPDATE "public"."users" SET "meta" = '{"k":"v"}'::json, "tags" = '{"it''s",NULL}'::text[] WHERE "id" = 1"#
+        );
+
+        let unchanged = change("update", r#"{"id": 1}"#, Some(r#"{"id": 1}"#), "");
+        assert!(CommitStore::revert_change(&unchanged, None, None)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_search() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE commits (
+                id TEXT PRIMARY KEY, parent_id TEXT, message TEXT NOT NULL,
+                summary TEXT NOT NULL, created_at TEXT NOT NULL, change_count INTEGER NOT NULL
+            );
+            CREATE TABLE commit_changes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, commit_id TEXT NOT NULL, type TEXT NOT NULL,
+                schema_name TEXT NOT NULL, table_name TEXT NOT NULL, data TEXT NOT NULL,
+                original_data TEXT, sql TEXT NOT NULL, sort_order INTEGER NOT NULL
+            );
+            INSERT INTO commits VALUES ('a', NULL, 'Fix user emails', '', '2024-01-01', 2);
+            INSERT INTO commit_changes (commit_id, type, schema_name, table_name, data, sql, sort_order)
+            VALUES
+                ('a', 'update', 'public', 'users', '{\"email\": \"a@x.io\"}',
+                 'UPDATE \"public\".\"users\" SET \"email\" = ''a@x.io'' WHERE \"id\" = 1', 0),
+                ('a', 'update', 'public', 'users', '{\"name\": \"100%_off\"}',
+                 'UPDATE \"public\".\"users\" SET \"name\" = ''100%_off'' WHERE \"id\" = 1', 1);",
+        )
+        .unwrap();
+        // Rows saved before the index existed are indexed when it is created
+        CommitStore::create_search_index(&conn);
+        assert!(CommitStore::has_search_index(&conn));
+        conn.execute_batch(
+            "INSERT INTO commits VALUES ('b', 'a', 'Archive orders', '', '2024-02-01', 1);
+            INSERT INTO commit_changes (commit_id, type, schema_name, table_name, data, sql, sort_order)
+            VALUES ('b', 'delete', 'shop', 'orders', '{\"id\": 7}',
+                    'DELETE FROM \"shop\".\"orders\" WHERE \"id\" = 7', 0);",
+        )
+        .unwrap();
+
+        for use_fts in [true, false] {
+            let search = |query: &str| {
+                let hits = CommitStore::search(&conn, query, 10, use_fts).unwrap();
+                hits.into_iter()
+                    .map(|hit| {
+                        let changes = hit.changes.iter().map(|c| c.sort_order).collect();
+                        (hit.commit.id, changes)
+                    })
+                    .collect::<Vec<(String, Vec<i64>)>>()
+            };
+            let hit = |id: &str, changes: &[i64]| (id.to_string(), changes.to_vec());
+
+            assert_eq!(search("EMAIL"), [hit("a", &[0])], "fts: {}", use_fts);
+            assert_eq!(search("users"), [hit("a", &[0, 1])], "fts: {}", use_fts);
+            assert_eq!(search("archive"), [hit("b", &[])], "fts: {}", use_fts);
+            assert_eq!(search("UPDATE"), [hit("a", &[0, 1])], "fts: {}", use_fts);
+            assert_eq!(
+                search("\"public\""),
+                [hit("a", &[0, 1])],
+                "fts: {}",
+                use_fts
+            );
+            assert_eq!(search("%_o"), [hit("a", &[1])], "fts: {}", use_fts);
+            assert!(search("missing").is_empty());
+        }
+        let both = CommitStore::search(&conn, "\"id\"", 10, true).unwrap();
+        let ids: Vec<&str> = both.iter().map(|hit| hit.commit.id.as_str()).collect();
+        assert_eq!(ids, ["b", "a"]);
+        assert!(CommitStore::search(&conn, "e", 0, false)
+            .unwrap()
+            .is_empty());
+        assert!(CommitStore::search(&conn, "", 10, false)
+            .unwrap()
+            .is_empty());
+    }
 }
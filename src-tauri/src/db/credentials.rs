@@ -0,0 +1,349 @@
+use crate::error::{DbViewerError, Result};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use keyring::Entry;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+const KEYRING_SERVICE: &str = "db-viewer-app";
+const NONCE_LEN: usize = 12;
+
+/// Where passwords and other secrets are kept
+pub trait CredentialBackend: Send + Sync {
+    fn save_password(&self, key: &str, password: &str) -> Result<()>;
+    fn get_password(&self, key: &str) -> Result<String>;
+    /// Deleting a key that was never saved is not an error
+    fn delete_password(&self, key: &str) -> Result<()>;
+    fn save_blob(&self, key: &str, data: &[u8]) -> Result<()>;
+    /// `None` when nothing has been saved under `key`
+    fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>>;
+}
+
+/// The system keyring (Keychain, Credential Manager, Secret Service)
+pub struct KeyringBackend;
+
+impl KeyringBackend {
+    fn entry(key: &str) -> Result<Entry> {
+        Ok(Entry::new(KEYRING_SERVICE, key)?)
+    }
+
+    /// Whether the keyring can be read at all. A missing entry still counts
+    /// as available; a missing or locked platform store does not.
+    fn is_available() -> bool {
+        let probe =
+            Self::entry("availability-check").and_then(|entry| match entry.get_password() {
+                Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(e.into()),
+            });
+        if let Err(e) = probe {
+            log::warn!("System keyring unavailable: {}", e);
+            return false;
+        }
+        true
+    }
+}
+
+impl CredentialBackend for KeyringBackend {
+    fn save_password(&self, key: &str, password: &str) -> Result<()> {
+        Self::entry(key)?.set_password(password)?;
+        Ok(())
+    }
+
+    fn get_password(&self, key: &str) -> Result<String> {
+        Ok(Self::entry(key)?.get_password()?)
+    }
+
+    fn delete_password(&self, key: &str) -> Result<()> {
+        // Ignore error if password doesn't exist
+        let _ = Self::entry(key)?.delete_credential();
+        Ok(())
+    }
+
+    // Blobs are stored as passwords, as connection lists always were. On
+    // Windows `set_password` writes UTF-16, which `get_secret` would return
+    // undecoded, so existing entries must be read with `get_password`.
+    fn save_blob(&self, key: &str, data: &[u8]) -> Result<()> {
+        let text = std::str::from_utf8(data).map_err(|e| {
+            DbViewerError::Keyring(format!("Credential {} is not UTF-8: {}", key, e))
+        })?;
+        Self::entry(key)?.set_password(text)?;
+        Ok(())
+    }
+
+    fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match Self::entry(key)?.get_password() {
+            Ok(text) => Ok(Some(text.into_bytes())),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CredentialFile {
+    passwords: BTreeMap<String, String>,
+    /// Hex-encoded
+    blobs: BTreeMap<String, String>,
+}
+
+/// Secrets in an AES-256-GCM encrypted JSON file, for systems without a
+/// keyring. The key is derived from the machine ID, so the file is tied to
+/// this machine but readable by anyone who can read both.
+pub struct EncryptedFileBackend {
+    path: PathBuf,
+    key: [u8; 32],
+    // Serializes read-modify-write cycles on the file
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileBackend {
+    pub fn new(path: PathBuf, key: [u8; 32]) -> Self {
+        Self {
+            path,
+            key,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The credentials file in the app data directory, keyed to this machine
+    pub fn open_default() -> Result<Self> {
+        let machine_id = machine_id().ok_or_else(|| {
+            DbViewerError::Keyring("Could not determine a machine ID".to_string())
+        })?;
+        let data_dir = dirs::data_dir().ok_or_else(|| {
+            DbViewerError::Keyring("Could not find app data directory".to_string())
+        })?;
+        let app_dir = data_dir.join("com.tusker.app");
+        std::fs::create_dir_all(&app_dir).map_err(|e| {
+            DbViewerError::Keyring(format!("Failed to create app data directory: {}", e))
+        })?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"tusker-credentials:");
+        hasher.update(machine_id.as_bytes());
+        Ok(Self::new(
+            app_dir.join("credentials.enc"),
+            hasher.finalize().into(),
+        ))
+    }
+
+    fn cipher(&self) -> Result<Aes256Gcm> {
+        Aes256Gcm::new_from_slice(&self.key)
+            .map_err(|e| DbViewerError::Keyring(format!("Cipher init failed: {}", e)))
+    }
+
+    fn read(&self) -> Result<CredentialFile> {
+        let data = match std::fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(CredentialFile::default())
+            }
+            Err(e) => {
+                return Err(DbViewerError::Keyring(format!(
+                    "Failed to read credentials file: {}",
+                    e
+                )))
+            }
+        };
+        if data.len() < NONCE_LEN {
+            return Err(DbViewerError::Keyring(
+                "Credentials file is corrupted".to_string(),
+            ));
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let json = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                DbViewerError::Keyring("Failed to decrypt credentials file".to_string())
+            })?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    fn write(&self, file: &CredentialFile) -> Result<()> {
+        let json = serde_json::to_vec(file)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(Nonce::from_slice(&nonce), json.as_ref())
+            .map_err(|e| DbViewerError::Keyring(format!("Encryption failed: {}", e)))?;
+
+        let mut data = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+
+        // Write then rename so a crash never leaves a half-written file
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, &data)
+            .and_then(|_| restrict_permissions(&tmp))
+            .and_then(|_| std::fs::rename(&tmp, &self.path))
+            .map_err(|e| DbViewerError::Keyring(format!("Failed to write credentials file: {}", e)))
+    }
+
+    fn update(&self, change: impl FnOnce(&mut CredentialFile)) -> Result<()> {
+        let _guard = self
+            .lock
+            .lock()
+            .map_err(|e| DbViewerError::Lock(e.to_string()))?;
+        let mut file = self.read()?;
+        change(&mut file);
+        self.write(&file)
+    }
+}
+
+impl CredentialBackend for EncryptedFileBackend {
+    fn save_password(&self, key: &str, password: &str) -> Result<()> {
+        self.update(|file| {
+            file.passwords.insert(key.to_string(), password.to_string());
+        })
+    }
+
+    fn get_password(&self, key: &str) -> Result<String> {
+        self.read()?
+            .passwords
+            .remove(key)
+            .ok_or_else(|| DbViewerError::Keyring(format!("No password saved for {}", key)))
+    }
+
+    fn delete_password(&self, key: &str) -> Result<()> {
+        self.update(|file| {
+            file.passwords.remove(key);
+        })
+    }
+
+    fn save_blob(&self, key: &str, data: &[u8]) -> Result<()> {
+        self.update(|file| {
+            file.blobs.insert(key.to_string(), hex::encode(data));
+        })
+    }
+
+    fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.read()?
+            .blobs
+            .remove(key)
+            .map(|data| {
+                hex::decode(data).map_err(|e| {
+                    DbViewerError::Keyring(format!("Corrupted credential {}: {}", key, e))
+                })
+            })
+            .transpose()
+    }
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// A stable identifier for this machine: `/etc/machine-id` on Linux, the
+/// hardware UUID on macOS and the `MachineGuid` on Windows
+fn machine_id() -> Option<String> {
+    let id = if cfg!(target_os = "macos") {
+        let output = std::process::Command::new("ioreg")
+            .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find(|line| line.contains("IOPlatformUUID"))
+            .and_then(|line| line.split('"').nth(3))
+            .map(str::to_string)
+    } else if cfg!(windows) {
+        let output = std::process::Command::new("reg")
+            .args([
+                "query",
+                r"HKLM\SOFTWARE\Microsoft\Cryptography",
+                "/v",
+                "MachineGuid",
+            ])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find(|line| line.contains("MachineGuid"))
+            .and_then(|line| line.split_whitespace().last())
+            .map(str::to_string)
+    } else {
+        ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+            .iter()
+            .find_map(|path| std::fs::read_to_string(path).ok())
+    };
+
+    id.map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+/// The system keyring when it works, else the encrypted file. Decided once
+/// per run, so secrets never split between the two stores.
+pub fn detect_backend() -> Box<dyn CredentialBackend> {
+    if KeyringBackend::is_available() {
+        return Box::new(KeyringBackend);
+    }
+    match EncryptedFileBackend::open_default() {
+        Ok(backend) => {
+            log::info!("Storing credentials in {}", backend.path.display());
+            Box::new(backend)
+        }
+        Err(e) => {
+            log::warn!("Encrypted credentials file unavailable: {}", e);
+            Box::new(KeyringBackend)
+        }
+    }
+}
+
+/// The backend chosen by `detect_backend`
+pub fn backend() -> &'static dyn CredentialBackend {
+    static BACKEND: OnceLock<Box<dyn CredentialBackend>> = OnceLock::new();
+    BACKEND.get_or_init(detect_backend).as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_file_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let backend = EncryptedFileBackend::new(path.clone(), [7; 32]);
+
+        assert!(backend.get_password("db").is_err());
+        assert_eq!(backend.get_blob("connections").unwrap(), None);
+
+        backend.save_password("db", "hunter2").unwrap();
+        backend.save_blob("connections", b"[{\"id\":1}]").unwrap();
+        assert_eq!(backend.get_password("db").unwrap(), "hunter2");
+        assert_eq!(
+            backend.get_blob("connections").unwrap().as_deref(),
+            Some(&b"[{\"id\":1}]"[..])
+        );
+
+        // Nothing readable on disk
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("hunter2"));
+
+        // Another machine's key can't open the file
+        let other = EncryptedFileBackend::new(path, [8; 32]);
+        assert!(matches!(
+            other.get_password("db"),
+            Err(DbViewerError::Keyring(_))
+        ));
+
+        backend.delete_password("db").unwrap();
+        backend.delete_password("never-saved").unwrap();
+        assert!(backend.get_password("db").is_err());
+        assert!(backend.get_blob("connections").unwrap().is_some());
+    }
+}
@@ -1,11 +1,44 @@
+use crate::db::query_history::QueryHistoryStore;
+use crate::db::schema::{ColumnInfo, SchemaIntrospector};
 use crate::error::{DbViewerError, Result};
+use futures_util::TryStreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use sqlx::postgres::PgRow;
-use sqlx::{Column, Executor, PgPool, Row, TypeInfo};
+use sqlx::postgres::types::{PgInterval, PgMoney};
+use sqlx::postgres::{PgArguments, PgHasArrayType, PgRow};
+use sqlx::query::Query;
+use sqlx::types::{BigDecimal, Json};
+use sqlx::{
+    Arguments, Column, Connection, Executor, PgConnection, PgPool, Postgres, Row, TypeInfo,
+};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
+use tokio::io::{AsyncWriteExt, BufWriter};
 
 const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 1000;
+const CSV_PROGRESS_INTERVAL: u64 = 1000;
+const CSV_IMPORT_BATCH_SIZE: usize = 500;
+const BULK_INSERT_CHUNK_SIZE: usize = 1000;
+/// Bulk inserts of at least this many rows stream through COPY
+const BULK_INSERT_COPY_THRESHOLD: usize = 5000;
+/// COPY data is sent to the server in pieces of about this size
+const COPY_SEND_BYTES: usize = 1 << 20;
+/// bytea values longer than this are cut to a preview in the data grid
+const BYTEA_PREVIEW_BYTES: usize = 64;
+const REFERENCING_SAMPLE_LIMIT: i64 = 10;
+/// Savepoint around the catalog lookups that work out a migration's rollback
+const ROLLBACK_SAVEPOINT: &str = "tusker_rollback_lookup";
+/// Column alias carrying the match count alongside the sampled rows
+const REFERENCING_COUNT_COLUMN: &str = "__tusker_referencing_count";
+/// Tables estimated to be larger than this get column stats from pg_stats only
+const COLUMN_STATS_SCAN_LIMIT: i64 = 1_000_000;
+const COLUMN_STATS_TOP_VALUES: i64 = 10;
+/// Column types whose values `fetch_paginated` truncates
+const TRUNCATABLE_TYPES: &[&str] = &["text", "character varying", "json", "jsonb", "xml", "bytea"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaginatedResult {
@@ -14,7 +47,228 @@ pub struct PaginatedResult {
     pub page: i64,
     pub page_size: i64,
     pub total_pages: i64,
+    /// False when `total_count` is an estimate or a lower bound
+    pub total_count_exact: bool,
     pub columns: Vec<ColumnMeta>,
+    /// Cursor for the page after this one, when rows are ordered by primary key
+    /// or by the cursor column of `PaginationMode::CursorBased`
+    pub next_cursor: Option<PageCursor>,
+    /// Cursor for the page before this one, likewise
+    pub prev_cursor: Option<PageCursor>,
+    /// Cells cut short by `max_cell_bytes`; their full values are available
+    /// through `DataOperations::fetch_full_cell`
+    pub truncated_cells: Vec<TruncatedCell>,
+}
+
+/// A cell whose value in `PaginatedResult::rows` is a text prefix of the real value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TruncatedCell {
+    /// Index into `rows`
+    pub row: usize,
+    pub column: String,
+}
+
+/// How `fetch_paginated` computes `total_count`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountMode {
+    /// Run `COUNT(*)` with the page's filters
+    #[default]
+    Exact,
+    /// Use planner statistics instead of scanning the table
+    Estimated,
+    /// Skip counting; `total_count` only covers rows seen so far, plus one
+    /// when the page is full so there is always a next page to ask for
+    None,
+}
+
+/// What `DataOperations::fetch_keyset` reads
+#[derive(Debug, Clone, Default)]
+pub struct KeysetRequest {
+    pub schema: String,
+    pub table: String,
+    pub page_size: Option<i64>,
+    pub order_by: Option<Vec<String>>,
+    pub order_direction: Option<Vec<String>>,
+    pub filters: Option<Vec<FilterGroup>>,
+    /// `next_cursor` of the previous page; `None` for the first page
+    pub cursor: Option<PageCursor>,
+}
+
+/// A page read by `DataOperations::fetch_keyset`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeysetPage {
+    pub rows: Vec<serde_json::Map<String, JsonValue>>,
+    pub page_size: i64,
+    pub columns: Vec<ColumnMeta>,
+    /// Cursor for the page after this one; `None` on the last page
+    pub next_cursor: Option<PageCursor>,
+}
+
+/// Which side of the cursor row a keyset page is read from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorDirection {
+    #[default]
+    Next,
+    Prev,
+}
+
+/// How `TableSample` picks rows
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleMethod {
+    /// Each row independently; slower but evenly spread
+    Bernoulli,
+    /// Whole pages at a time; fast but clustered
+    System,
+}
+
+/// Read a random `percentage` of a table with `TABLESAMPLE`. The same `seed`
+/// picks the same rows again, so pages of a sample stay consistent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableSample {
+    pub percentage: f64,
+    pub method: SampleMethod,
+    pub seed: Option<i64>,
+}
+
+impl TableSample {
+    /// The `TABLESAMPLE` clause to follow the table name
+    fn clause(&self) -> Result<String> {
+        if !(0.0..=100.0).contains(&self.percentage) {
+            return Err(DbViewerError::InvalidQuery(format!(
+                "Sample percentage must be between 0 and 100, got {}",
+                self.percentage
+            )));
+        }
+        let method = match self.method {
+            SampleMethod::Bernoulli => "BERNOULLI",
+            SampleMethod::System => "SYSTEM",
+        };
+        let mut clause = format!("TABLESAMPLE {} ({})", method, self.percentage);
+        if let Some(seed) = self.seed {
+            clause.push_str(&format!(" REPEATABLE ({})", seed));
+        }
+        Ok(clause)
+    }
+}
+
+/// A sort key for `fetch_paginated`: a column, or an expression such as
+/// `lower(name)` or `data->>'city'`. Expressions may only use the table's
+/// columns, literals, casts, operators and the functions in `ORDER_FUNCTIONS`.
+/// In JSON a column is a plain string and an expression is
+/// `{ "expression": "..." }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "OrderSpecRepr", into = "OrderSpecRepr")]
+pub enum OrderSpec {
+    Column(String),
+    Expression(String),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum OrderSpecRepr {
+    Column(String),
+    Expression { expression: String },
+}
+
+impl From<OrderSpecRepr> for OrderSpec {
+    fn from(repr: OrderSpecRepr) -> Self {
+        match repr {
+            OrderSpecRepr::Column(column) => OrderSpec::Column(column),
+            OrderSpecRepr::Expression { expression } => OrderSpec::Expression(expression),
+        }
+    }
+}
+
+impl From<OrderSpec> for OrderSpecRepr {
+    fn from(spec: OrderSpec) -> Self {
+        match spec {
+            OrderSpec::Column(column) => OrderSpecRepr::Column(column),
+            OrderSpec::Expression(expression) => OrderSpecRepr::Expression { expression },
+        }
+    }
+}
+
+/// How `fetch_paginated` picks the rows of a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaginationMode {
+    /// Numbered pages read with OFFSET
+    OffsetBased { page: i64, page_size: Option<i64> },
+    /// Up to `limit` rows past `cursor_value` in `cursor_column` order, so deep
+    /// pages cost the same as the first. Without a cursor value, the first
+    /// page (`Next`) or the last one (`Prev`). The column should be unique;
+    /// rows where it is NULL are only reached without a cursor value.
+    CursorBased {
+        cursor_column: String,
+        cursor_value: Option<JsonValue>,
+        limit: i64,
+        direction: CursorDirection,
+    },
+}
+
+impl Default for PaginationMode {
+    fn default() -> Self {
+        PaginationMode::OffsetBased {
+            page: 1,
+            page_size: None,
+        }
+    }
+}
+
+/// What `DataOperations::fetch_paginated` reads. The default is the first
+/// page of the whole table, with an exact count.
+#[derive(Debug, Clone, Default)]
+pub struct PageRequest {
+    pub schema: String,
+    pub table: String,
+    pub mode: PaginationMode,
+    pub order_by: Option<Vec<OrderSpec>>,
+    pub order_direction: Option<Vec<String>>,
+    pub filters: Option<Vec<FilterGroup>>,
+    pub cursor: Option<PageCursor>,
+    pub count_mode: CountMode,
+    pub total_count_hint: Option<i64>,
+    pub columns: Option<Vec<String>>,
+    pub search: Option<String>,
+    pub max_cell_bytes: Option<usize>,
+    pub sample: Option<TableSample>,
+    pub timeout_ms: Option<u32>,
+}
+
+/// A connection for `DataOperations::fetch_paginated` to run on, so the caller
+/// knows which backend to cancel. Whoever cancels it sets `cancelled` first,
+/// which is how the fetch tells a cancellation from its own timeout.
+pub struct CancellableConn<'a> {
+    pub conn: &'a mut PgConnection,
+    pub cancelled: &'a AtomicBool,
+}
+
+/// Keyset pagination position: the primary key values of the row at the edge
+/// of the previously fetched page, in key column order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageCursor {
+    pub values: Vec<JsonValue>,
+    #[serde(default)]
+    pub direction: CursorDirection,
+}
+
+impl PageCursor {
+    fn from_row(
+        row: &serde_json::Map<String, JsonValue>,
+        pk_columns: &[(String, String)],
+        direction: CursorDirection,
+    ) -> Self {
+        Self {
+            values: pk_columns
+                .iter()
+                .map(|(col, _)| row.get(col).cloned().unwrap_or(JsonValue::Null))
+                .collect(),
+            direction,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,11 +285,171 @@ pub struct QueryResult {
     pub execution_time_ms: u128,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkDeleteRequest {
+    pub schema: String,
+    pub table: String,
+    pub filters: Vec<FilterCondition>,
+    /// Maximum number of rows to delete
+    pub limit: Option<i64>,
+}
+
+/// Set the same values on every row matching all of `filters`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateByFilterRequest {
+    pub schema: String,
+    pub table: String,
+    pub data: serde_json::Map<String, JsonValue>,
+    pub filters: Vec<FilterCondition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistinctValues {
+    pub values: Vec<JsonValue>,
+    /// True when more distinct values exist than were returned
+    pub limit_reached: bool,
+}
+
+/// Rows in another table that point at a given row through one foreign key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferencingRows {
+    pub schema: String,
+    pub table: String,
+    pub constraint: String,
+    pub count: i64,
+    pub sample_rows: Vec<serde_json::Map<String, JsonValue>>,
+    /// Set when the table could not be queried, e.g. for lack of privileges
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommonValue {
+    pub value: JsonValue,
+    pub count: i64,
+}
+
+/// Summary of one column from `DataOperations::column_stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnStats {
+    pub row_count: i64,
+    pub null_count: Option<i64>,
+    pub distinct_count: Option<i64>,
+    /// Null when the type has no ordering or the value is unknown
+    pub min: JsonValue,
+    pub max: JsonValue,
+    /// Most frequent non-null values, most frequent first
+    pub most_common: Vec<CommonValue>,
+    /// False when the numbers are planner estimates from pg_stats; estimated
+    /// values are strings and min/max come from the histogram bounds
+    pub exact: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InsertRequest {
+    pub schema: String,
+    pub table: String,
+    /// Column values; `{"$default": true}` inserts the column's default
+    /// rather than NULL, and a row of only defaults is `DEFAULT VALUES`
+    pub data: serde_json::Map<String, JsonValue>,
+    /// Skip or update the existing row instead of failing on a unique violation
+    #[serde(default)]
+    pub on_conflict: Option<OnConflict>,
+}
+
+/// `ON CONFLICT` handling for `insert_row`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnConflict {
+    /// Columns of the unique constraint to resolve conflicts on; may be empty
+    /// with `DoNothing` to skip on any conflict
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub action: ConflictAction,
+    /// Columns to overwrite with `DoUpdate`; defaults to every non-conflict column in the data
+    pub update_columns: Option<Vec<String>>,
+}
+
+impl OnConflict {
+    /// The ` ON CONFLICT ...` clause for inserting `data` into a table with
+    /// `table_columns`
+    fn clause(
+        &self,
+        data: &serde_json::Map<String, JsonValue>,
+        table_columns: &HashMap<&str, &ColumnInfo>,
+    ) -> Result<String> {
+        if let Some(missing) = self
+            .columns
+            .iter()
+            .find(|c| !data.contains_key(*c) && !table_columns.contains_key(c.as_str()))
+        {
+            return Err(DbViewerError::InvalidQuery(format!(
+                "Conflict column {} does not exist",
+                missing
+            )));
+        }
+        let target = self
+            .columns
+            .iter()
+            .map(|c| quote_identifier(c))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if self.action == ConflictAction::DoNothing {
+            if target.is_empty() {
+                return Ok(" ON CONFLICT DO NOTHING".to_string());
+            }
+            return Ok(format!(" ON CONFLICT ({}) DO NOTHING", target));
+        }
+        if self.columns.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "Updating on conflict needs conflict columns".to_string(),
+            ));
+        }
+
+        let update_columns: Vec<&str> = match &self.update_columns {
+            Some(cols) => {
+                if let Some(missing) = cols.iter().find(|c| !data.contains_key(*c)) {
+                    return Err(DbViewerError::InvalidQuery(format!(
+                        "Update column {} is not in the inserted data",
+                        missing
+                    )));
+                }
+                cols.iter().map(|s| s.as_str()).collect()
+            }
+            None => data
+                .keys()
+                .filter(|k| !self.columns.contains(k))
+                .map(|s| s.as_str())
+                .collect(),
+        };
+        // With nothing to update, reassign the conflict columns so RETURNING
+        // still yields the existing row (DO NOTHING would return no row)
+        let set_columns: Vec<&str> = if update_columns.is_empty() {
+            self.columns.iter().map(|s| s.as_str()).collect()
+        } else {
+            update_columns
+        };
+
+        Ok(format!(
+            " ON CONFLICT ({}) DO UPDATE SET {}",
+            target,
+            set_columns
+                .iter()
+                .map(|c| format!("{} = EXCLUDED.{}", quote_identifier(c), quote_identifier(c)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpsertRequest {
     pub schema: String,
     pub table: String,
     pub data: serde_json::Map<String, JsonValue>,
+    /// Columns forming the conflict target; empty means a plain insert
+    pub conflict_columns: Vec<String>,
+    /// Columns to overwrite on conflict; defaults to every non-conflict column in `data`
+    pub update_columns: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,14 +457,114 @@ pub struct BulkInsertRequest {
     pub schema: String,
     pub table: String,
     pub rows: Vec<serde_json::Map<String, JsonValue>>,
+    /// Rows per INSERT statement; defaults to `BULK_INSERT_CHUNK_SIZE`
+    pub chunk_size: Option<usize>,
+    /// Columns of the unique constraint to resolve conflicts on; when empty,
+    /// a conflicting row fails the batch
+    #[serde(default)]
+    pub conflict_target: Vec<String>,
+    #[serde(default)]
+    pub conflict_action: ConflictAction,
+    /// Row count from which rows are streamed with COPY instead of INSERT;
+    /// defaults to `BULK_INSERT_COPY_THRESHOLD`. Batches with a
+    /// `conflict_target` always use INSERT.
+    pub copy_threshold: Option<usize>,
+    /// What to write for a column that some rows have and others leave out
+    #[serde(default)]
+    pub missing_values: MissingValue,
+}
+
+/// The value `bulk_insert` writes for a column missing from a row
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingValue {
+    #[default]
+    Null,
+    /// The column's default, e.g. the next serial value
+    Default,
+}
+
+/// Rows of a `bulk_insert` sent to the server so far. They only become
+/// visible once the whole batch commits.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BulkInsertProgress {
+    pub inserted: u64,
+    pub total: u64,
+}
+
+/// What `bulk_insert` does with a row that conflicts on `conflict_target`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictAction {
+    /// Keep the existing row
+    #[default]
+    DoNothing,
+    /// Overwrite the existing row's other columns with the inserted values
+    DoUpdate,
+}
+
+/// How `import_csv` handles rows that collide with an existing key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Abort the import
+    #[default]
+    Fail,
+    /// Keep the existing row
+    Skip,
+    /// Overwrite the existing row, matched on the primary key
+    Replace,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportCsvRequest {
+    pub schema: String,
+    pub table: String,
+    pub file_path: String,
+    pub has_header: bool,
+    pub delimiter: char,
+    /// Fields equal to this are imported as NULL
+    pub null_string: String,
+    pub on_conflict: ConflictStrategy,
+    /// Target columns in file order; defaults to the header row
+    pub columns: Option<Vec<String>>,
+}
+
+/// A CSV record that could not be imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowError {
+    /// Line in the file where the record starts
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportResult {
+    /// Rows written, including rows replaced on conflict
+    pub rows_inserted: u64,
+    /// Rows skipped on conflict or because they could not be parsed
+    pub rows_skipped: u64,
+    pub errors: Vec<RowError>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateRequest {
     pub schema: String,
     pub table: String,
+    /// New column values; `{"$default": true}` resets a column to its default
     pub data: serde_json::Map<String, JsonValue>,
     pub where_clause: serde_json::Map<String, JsonValue>,
+    /// Return the updated rows, not just how many there were
+    #[serde(default)]
+    pub returning: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateResult {
+    pub rows_affected: u64,
+    /// With `returning`, the updated rows as stored, including values set by
+    /// defaults or triggers; empty otherwise
+    pub rows: Vec<serde_json::Map<String, JsonValue>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +572,17 @@ pub struct DeleteRequest {
     pub schema: String,
     pub table: String,
     pub where_clause: serde_json::Map<String, JsonValue>,
+    /// Return the deleted rows, not just how many there were
+    #[serde(default)]
+    pub returning: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteResult {
+    pub rows_affected: u64,
+    /// With `returning`, the deleted rows as they were before the delete;
+    /// empty otherwise
+    pub rows: Vec<serde_json::Map<String, JsonValue>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +604,23 @@ pub enum FilterOperator {
     IsFalse,
     Between,
     In,
+    NotIn,
+    /// Case-sensitive POSIX match (`~`)
+    #[serde(alias = "matches")]
+    Regex,
+    NotRegex,
+    /// Case-insensitive POSIX match (`~*`)
+    #[serde(alias = "matches_insensitive")]
+    RegexInsensitive,
+    /// The text at `path` in a jsonb column equals `value`
+    JsonPathEquals,
+    /// `path` exists in a jsonb column
+    JsonPathExists,
+    /// An array column has an element equal to `value`, or contains every
+    /// element of `values` (`@>`)
+    ArrayContains,
+    /// An array column shares at least one element with `values` (`&&`)
+    ArrayOverlaps,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,825 +630,9165 @@ pub struct FilterCondition {
     pub value: Option<String>,
     pub value2: Option<String>,
     pub values: Option<Vec<String>>,
+    /// Dot-separated key path into a jsonb column, e.g. `plan.tier`
+    pub path: Option<String>,
+    /// Match `Contains`/`NotContains`/`StartsWith`/`EndsWith` with LIKE instead
+    /// of ILIKE. Defaults to case-insensitive.
+    pub case_sensitive: Option<bool>,
+}
+
+impl FilterCondition {
+    fn like_operator(&self) -> &'static str {
+        if self.case_sensitive.unwrap_or(false) {
+            "LIKE"
+        } else {
+            "ILIKE"
+        }
+    }
 }
 
-/// Escape LIKE wildcards in a string
-fn escape_like_pattern(s: &str) -> String {
+/// Escape LIKE wildcards, for patterns used with `ESCAPE '\'`
+pub(crate) fn escape_like_pattern(s: &str) -> String {
     s.replace('\\', "\\\\")
         .replace('%', "\\%")
         .replace('_', "\\_")
 }
 
-/// Build a WHERE clause from filter conditions
-fn build_where_clause(filters: &[FilterCondition]) -> String {
-    let conditions: Vec<String> = filters
-        .iter()
-        .filter_map(|f| {
-            let col = quote_identifier(&f.column);
-            match f.operator {
-                FilterOperator::Equals => {
-                    let v = f.value.as_ref()?;
-                    Some(format!("{} = '{}'", col, escape_sql_string(v)))
-                }
-                FilterOperator::NotEquals => {
-                    let v = f.value.as_ref()?;
-                    Some(format!("{} != '{}'", col, escape_sql_string(v)))
-                }
-                FilterOperator::GreaterThan => {
-                    let v = f.value.as_ref()?;
-                    Some(format!("{} > '{}'", col, escape_sql_string(v)))
-                }
-                FilterOperator::LessThan => {
-                    let v = f.value.as_ref()?;
-                    Some(format!("{} < '{}'", col, escape_sql_string(v)))
-                }
-                FilterOperator::GreaterThanOrEqual => {
-                    let v = f.value.as_ref()?;
-                    Some(format!("{} >= '{}'", col, escape_sql_string(v)))
-                }
-                FilterOperator::LessThanOrEqual => {
-                    let v = f.value.as_ref()?;
-                    Some(format!("{} <= '{}'", col, escape_sql_string(v)))
-                }
-                FilterOperator::Contains => {
-                    let v = f.value.as_ref()?;
-                    Some(format!(
-                        "{}::text ILIKE '{}' ESCAPE '\\'",
-                        col,
-                        escape_sql_string(&format!("%{}%", escape_like_pattern(v)))
-                    ))
-                }
-                FilterOperator::NotContains => {
-                    let v = f.value.as_ref()?;
-                    Some(format!(
-                        "{}::text NOT ILIKE '{}' ESCAPE '\\'",
-                        col,
-                        escape_sql_string(&format!("%{}%", escape_like_pattern(v)))
-                    ))
-                }
-                FilterOperator::StartsWith => {
-                    let v = f.value.as_ref()?;
-                    Some(format!(
-                        "{}::text ILIKE '{}' ESCAPE '\\'",
-                        col,
-                        escape_sql_string(&format!("{}%", escape_like_pattern(v)))
-                    ))
-                }
-                FilterOperator::EndsWith => {
-                    let v = f.value.as_ref()?;
-                    Some(format!(
-                        "{}::text ILIKE '{}' ESCAPE '\\'",
-                        col,
-                        escape_sql_string(&format!("%{}", escape_like_pattern(v)))
-                    ))
-                }
-                FilterOperator::IsNull => Some(format!("{} IS NULL", col)),
-                FilterOperator::IsNotNull => Some(format!("{} IS NOT NULL", col)),
-                FilterOperator::IsTrue => Some(format!("{} = TRUE", col)),
-                FilterOperator::IsFalse => Some(format!("{} = FALSE", col)),
-                FilterOperator::Between => {
-                    let v1 = f.value.as_ref()?;
-                    let v2 = f.value2.as_ref()?;
-                    Some(format!(
-                        "{} BETWEEN '{}' AND '{}'",
-                        col,
-                        escape_sql_string(v1),
-                        escape_sql_string(v2)
-                    ))
-                }
-                FilterOperator::In => {
-                    let vals = f.values.as_ref()?;
-                    if vals.is_empty() {
-                        return None;
-                    }
-                    let escaped: Vec<String> = vals
-                        .iter()
-                        .map(|v| format!("'{}'", escape_sql_string(v)))
-                        .collect();
-                    Some(format!("{} IN ({})", col, escaped.join(", ")))
-                }
-            }
-        })
-        .collect();
+/// How the conditions inside a `FilterGroup` are combined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterLogic {
+    #[default]
+    And,
+    Or,
+}
 
-    if conditions.is_empty() {
-        String::new()
-    } else {
-        format!("WHERE {}", conditions.join(" AND "))
+impl FilterLogic {
+    fn keyword(self) -> &'static str {
+        match self {
+            FilterLogic::And => " AND ",
+            FilterLogic::Or => " OR ",
+        }
     }
 }
 
-pub struct DataOperations;
-
-impl DataOperations {
-    /// Fetch paginated data from a table
-    pub async fn fetch_paginated(
-        pool: &PgPool,
-        schema: &str,
-        table: &str,
-        page: i64,
-        page_size: Option<i64>,
-        order_by: Option<&Vec<String>>,
-        order_direction: Option<&Vec<String>>,
-        filters: Option<&Vec<FilterCondition>>,
-    ) -> Result<PaginatedResult> {
-        let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE);
-        let offset = (page - 1) * page_size;
+/// A set of filter conditions and nested groups combined with a single logical
+/// operator. Multiple top-level groups are always combined with AND,
+/// e.g. `(a OR b) AND (c OR (d AND e))`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterGroup {
+    pub conditions: Vec<FilterNode>,
+    #[serde(default)]
+    pub logic: FilterLogic,
+}
 
-        let has_explicit_order = matches!(order_by, Some(columns) if !columns.is_empty());
+/// A child of a `FilterGroup`: either a single condition or a nested group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FilterNode {
+    Condition(FilterCondition),
+    Group(FilterGroup),
+}
 
-        let where_clause = filters
-            .filter(|f| !f.is_empty())
-            .map(|f| build_where_clause(f))
-            .unwrap_or_default();
+impl From<FilterCondition> for FilterNode {
+    fn from(condition: FilterCondition) -> Self {
+        FilterNode::Condition(condition)
+    }
+}
 
-        let qualified_table = format!(
-            "{}.{}",
-            quote_identifier(schema),
-            quote_identifier(table)
-        );
+impl From<FilterGroup> for FilterNode {
+    fn from(group: FilterGroup) -> Self {
+        FilterNode::Group(group)
+    }
+}
 
-        let count_query = format!(
-            "SELECT COUNT(*) FROM {} {}",
-            qualified_table, where_clause
-        );
+/// A filter value bound to a `$n` placeholder
+#[derive(Debug, Clone, PartialEq)]
+enum BoundValue {
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    TextArray(Vec<String>),
+    Null,
+}
 
-        if has_explicit_order {
-            // Explicit sort provided — build order clause and run COUNT + SELECT concurrently
-            let columns = order_by.unwrap();
-            let directions = order_direction.cloned().unwrap_or_default();
-            let parts: Vec<String> = columns
-                .iter()
-                .enumerate()
-                .map(|(i, col)| {
-                    let dir = directions
-                        .get(i)
-                        .map(|d| if d.to_uppercase() == "DESC" { "DESC" } else { "ASC" })
-                        .unwrap_or("ASC");
-                    format!("{} {}", quote_identifier(col), dir)
-                })
-                .collect();
-            let order_clause = format!("ORDER BY {}", parts.join(", "));
+/// Collect bound values into query arguments, in placeholder order
+fn bind_values(values: &[BoundValue]) -> Result<PgArguments> {
+    let mut args = PgArguments::default();
+    for value in values {
+        let added = match value {
+            BoundValue::Text(v) => args.add(v.clone()),
+            BoundValue::Int(v) => args.add(*v),
+            BoundValue::Float(v) => args.add(*v),
+            BoundValue::Bool(v) => args.add(*v),
+            BoundValue::TextArray(v) => args.add(v.clone()),
+            BoundValue::Null => args.add(None::<String>),
+        };
+        added.map_err(|e| DbViewerError::InvalidQuery(e.to_string()))?;
+    }
+    Ok(args)
+}
 
-            let data_query = format!(
-                "SELECT * FROM {} {} {} LIMIT {} OFFSET {}",
-                qualified_table, where_clause, order_clause, page_size, offset
-            );
+/// Bind a cell value for a column of SQL type `column_type`, returning its
+/// placeholder cast to that type, less any length limit. The value goes as
+/// text for the server to parse, so decimals keep their precision and text
+/// forms of intervals, network addresses and the like work. JSON arrays become Postgres array
+/// literals for array columns; json and jsonb columns get the JSON text.
+fn bind_cell(
+    binds: &mut Vec<BoundValue>,
+    value: &JsonValue,
+    column_type: Option<&String>,
+) -> String {
+    let ty = column_type.map(String::as_str);
+    binds.push(match (value, ty) {
+        (JsonValue::Null, _) => BoundValue::Null,
+        (_, Some("json" | "jsonb")) => BoundValue::Text(value.to_string()),
+        (JsonValue::String(s), _) => BoundValue::Text(s.clone()),
+        (JsonValue::Array(items), Some(ty)) if ty.ends_with("[]") => {
+            BoundValue::Text(pg_array_literal(items))
+        }
+        _ => BoundValue::Text(value.to_string()),
+    });
+    match ty {
+        Some(ty) => format!("${}::{}", binds.len(), cast_type(ty)),
+        None => format!("${}", binds.len()),
+    }
+}
 
-            let (count_result, data_result) = tokio::join!(
-                sqlx::query_as::<_, (i64,)>(&count_query).fetch_one(pool),
-                sqlx::query(&data_query).fetch_all(pool),
-            );
+/// Collects bind parameters while a WHERE clause is assembled. Values compared
+/// against integer, double precision and boolean columns are bound natively;
+/// anything else is bound as text and cast to the column's declared type, so
+/// it is coerced exactly like an untyped literal would be.
+struct WhereBuilder<'a> {
+    column_types: &'a HashMap<String, String>,
+    binds: Vec<BoundValue>,
+    /// First filter that can't be applied, reported once the clause is built
+    invalid: Option<String>,
+}
 
-            let total_count = count_result?.0;
-            let rows = data_result?;
+impl<'a> WhereBuilder<'a> {
+    fn new(column_types: &'a HashMap<String, String>) -> Self {
+        Self {
+            column_types,
+            binds: Vec::new(),
+            invalid: None,
+        }
+    }
 
-            let (rows, columns) = rows_to_json(&rows);
-            let total_pages = (total_count as f64 / page_size as f64).ceil() as i64;
+    fn push(&mut self, value: BoundValue) -> String {
+        self.binds.push(value);
+        format!("${}", self.binds.len())
+    }
 
-            return Ok(PaginatedResult {
-                rows, total_count, page, page_size, total_pages, columns,
-            });
+    /// Bind a value compared against `column`, returning its placeholder
+    fn bind_typed(&mut self, column: &str, value: &str) -> String {
+        match self.column_types.get(column) {
+            Some(ty) => self.bind_as(ty, value),
+            None => self.bind_text(value),
         }
+    }
 
-        // No explicit sort — run PK detection + COUNT concurrently, then SELECT
-        let (pk_result, count_result) = tokio::join!(
-            sqlx::query_scalar::<_, String>(
-                r#"
-                SELECT a.attname
-                FROM pg_index i
-                JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = i.indkey[0]
-                WHERE i.indrelid = (quote_ident($1) || '.' || quote_ident($2))::regclass
-                  AND i.indisprimary
-                LIMIT 1
-                "#,
-            )
-            .bind(schema)
-            .bind(table)
-            .fetch_optional(pool),
-            sqlx::query_as::<_, (i64,)>(&count_query).fetch_one(pool),
-        );
-
-        let total_count = count_result?.0;
-        let order_clause = match pk_result.ok().flatten() {
-            Some(col) => format!("ORDER BY {} ASC", quote_identifier(&col)),
-            None => String::new(),
+    /// Bind a value of SQL type `ty`, returning its placeholder
+    fn bind_as(&mut self, ty: &str, value: &str) -> String {
+        let native = match ty {
+            "smallint" | "integer" | "bigint" => value.trim().parse().ok().map(BoundValue::Int),
+            "double precision" => value.trim().parse().ok().map(BoundValue::Float),
+            "boolean" => match value.trim().to_lowercase().as_str() {
+                "true" => Some(BoundValue::Bool(true)),
+                "false" => Some(BoundValue::Bool(false)),
+                _ => None,
+            },
+            _ => None,
         };
+        match native {
+            Some(v) => self.push(v),
+            None => format!("{}::{}", self.bind_text(value), cast_type(ty)),
+        }
+    }
 
-        let data_query = format!(
-            "SELECT * FROM {} {} {} LIMIT {} OFFSET {}",
-            qualified_table, where_clause, order_clause, page_size, offset
-        );
-        let rows = sqlx::query(&data_query).fetch_all(pool).await?;
-
-        let (rows, columns) = rows_to_json(&rows);
+    /// Bind a value used as plain text (e.g. a LIKE pattern), returning its placeholder
+    fn bind_text(&mut self, value: &str) -> String {
+        self.push(BoundValue::Text(value.to_string()))
+    }
 
-        let total_pages = (total_count as f64 / page_size as f64).ceil() as i64;
-
-        Ok(PaginatedResult {
-            rows,
-            total_count,
-            page,
-            page_size,
-            total_pages,
-            columns,
-        })
+    /// Build the SQL for a single filter condition, or `None` if it has no usable value
+    fn condition(&mut self, f: &FilterCondition) -> Option<String> {
+        let col = quote_identifier(&f.column);
+        match f.operator {
+            FilterOperator::Equals => {
+                let v = f.value.as_ref()?;
+                Some(format!("{} = {}", col, self.bind_typed(&f.column, v)))
+            }
+            FilterOperator::NotEquals => {
+                let v = f.value.as_ref()?;
+                Some(format!("{} != {}", col, self.bind_typed(&f.column, v)))
+            }
+            FilterOperator::GreaterThan => {
+                let v = f.value.as_ref()?;
+                Some(format!("{} > {}", col, self.bind_typed(&f.column, v)))
+            }
+            FilterOperator::LessThan => {
+                let v = f.value.as_ref()?;
+                Some(format!("{} < {}", col, self.bind_typed(&f.column, v)))
+            }
+            FilterOperator::GreaterThanOrEqual => {
+                let v = f.value.as_ref()?;
+                Some(format!("{} >= {}", col, self.bind_typed(&f.column, v)))
+            }
+            FilterOperator::LessThanOrEqual => {
+                let v = f.value.as_ref()?;
+                Some(format!("{} <= {}", col, self.bind_typed(&f.column, v)))
+            }
+            FilterOperator::Contains => {
+                let v = f.value.as_ref()?;
+                let pattern = format!("%{}%", escape_like_pattern(v));
+                Some(format!(
+                    "{}::text {} {} ESCAPE '\\'",
+                    col,
+                    f.like_operator(),
+                    self.bind_text(&pattern)
+                ))
+            }
+            FilterOperator::NotContains => {
+                let v = f.value.as_ref()?;
+                let pattern = format!("%{}%", escape_like_pattern(v));
+                Some(format!(
+                    "{}::text NOT {} {} ESCAPE '\\'",
+                    col,
+                    f.like_operator(),
+                    self.bind_text(&pattern)
+                ))
+            }
+            FilterOperator::StartsWith => {
+                let v = f.value.as_ref()?;
+                let pattern = format!("{}%", escape_like_pattern(v));
+                Some(format!(
+                    "{}::text {} {} ESCAPE '\\'",
+                    col,
+                    f.like_operator(),
+                    self.bind_text(&pattern)
+                ))
+            }
+            FilterOperator::EndsWith => {
+                let v = f.value.as_ref()?;
+                let pattern = format!("%{}", escape_like_pattern(v));
+                Some(format!(
+                    "{}::text {} {} ESCAPE '\\'",
+                    col,
+                    f.like_operator(),
+                    self.bind_text(&pattern)
+                ))
+            }
+            FilterOperator::Regex => {
+                let v = f.value.as_ref().filter(|v| !v.is_empty())?;
+                Some(format!("{}::text ~ {}", col, self.bind_text(v)))
+            }
+            FilterOperator::RegexInsensitive => {
+                let v = f.value.as_ref().filter(|v| !v.is_empty())?;
+                Some(format!("{}::text ~* {}", col, self.bind_text(v)))
+            }
+            FilterOperator::NotRegex => {
+                let v = f.value.as_ref().filter(|v| !v.is_empty())?;
+                Some(format!("{}::text !~ {}", col, self.bind_text(v)))
+            }
+            FilterOperator::JsonPathEquals => {
+                let path = self.json_path(f)?;
+                let v = f.value.as_ref()?;
+                let path = self.push(BoundValue::TextArray(path));
+                Some(format!("{} #>> {} = {}", col, path, self.bind_text(v)))
+            }
+            FilterOperator::JsonPathExists => {
+                let mut path = self.json_path(f)?;
+                let key = path.pop()?;
+                if path.is_empty() {
+                    Some(format!("{} ? {}", col, self.bind_text(&key)))
+                } else {
+                    let parent = self.push(BoundValue::TextArray(path));
+                    Some(format!("{} #> {} ? {}", col, parent, self.bind_text(&key)))
+                }
+            }
+            FilterOperator::ArrayContains if f.values.is_some() => {
+                let array = self.bind_array(f)?;
+                Some(format!("{} @> {}", col, array))
+            }
+            FilterOperator::ArrayContains => {
+                let array_type = self.array_type(f)?;
+                let v = f.value.as_ref()?;
+                let element = match array_type {
+                    Some(ty) => self.bind_as(ty.trim_end_matches("[]"), v),
+                    None => self.bind_text(v),
+                };
+                Some(format!("{} = ANY({})", element, col))
+            }
+            FilterOperator::ArrayOverlaps => {
+                let array = self.bind_array(f)?;
+                Some(format!("{} && {}", col, array))
+            }
+            FilterOperator::IsNull => Some(format!("{} IS NULL", col)),
+            FilterOperator::IsNotNull => Some(format!("{} IS NOT NULL", col)),
+            FilterOperator::IsTrue => Some(format!("{} = TRUE", col)),
+            FilterOperator::IsFalse => Some(format!("{} = FALSE", col)),
+            FilterOperator::Between => {
+                let v1 = f.value.as_ref()?;
+                let v2 = f.value2.as_ref()?;
+                Some(format!(
+                    "{} BETWEEN {} AND {}",
+                    col,
+                    self.bind_typed(&f.column, v1),
+                    self.bind_typed(&f.column, v2)
+                ))
+            }
+            FilterOperator::In => {
+                let placeholders = self.bind_list(f)?;
+                Some(format!("{} IN ({})", col, placeholders))
+            }
+            FilterOperator::NotIn => {
+                let placeholders = self.bind_list(f)?;
+                Some(format!("{} NOT IN ({})", col, placeholders))
+            }
+        }
     }
 
-    /// Insert a row into a table
-    pub async fn insert_row(pool: &PgPool, request: InsertRequest) -> Result<JsonValue> {
-        if request.data.is_empty() {
-            return Err(DbViewerError::InvalidQuery(
-                "No data provided for insert".to_string(),
-            ));
+    /// Split a condition's jsonb path into its keys. The keys are bound as a
+    /// `text[]`, so they never appear in the SQL text. Flags the filter as
+    /// invalid when the column is known not to be jsonb.
+    fn json_path(&mut self, f: &FilterCondition) -> Option<Vec<String>> {
+        if let Some(ty) = self.column_types.get(&f.column) {
+            if ty != "jsonb" {
+                self.invalid.get_or_insert_with(|| {
+                    format!(
+                        "JSON path filters need a jsonb column, but {} is {}",
+                        f.column, ty
+                    )
+                });
+                return None;
+            }
         }
+        let path = f.path.as_ref()?;
+        let keys: Vec<String> = path.split('.').map(|k| k.to_string()).collect();
+        if keys.iter().any(|k| k.is_empty()) {
+            self.invalid
+                .get_or_insert_with(|| format!("Invalid JSON path: {:?}", path));
+            return None;
+        }
+        Some(keys)
+    }
 
-        let columns: Vec<&str> = request.data.keys().map(|s| s.as_str()).collect();
-        let values: Vec<String> = request
-            .data
-            .values()
-            .map(json_value_to_sql)
-            .collect();
-
-        let query = format!(
-            "INSERT INTO {}.{} ({}) VALUES ({}) RETURNING *",
-            quote_identifier(&request.schema),
-            quote_identifier(&request.table),
-            columns
-                .iter()
-                .map(|c| quote_identifier(c))
-                .collect::<Vec<_>>()
-                .join(", "),
-            values.join(", ")
-        );
-
-        let row = pool.fetch_one(query.as_str()).await?;
-        let (rows, _) = rows_to_json(&[row]);
+    /// The declared type of an array filter's column, `None` when unknown.
+    /// Flags the filter as invalid when the column is known not to be an array.
+    fn array_type(&mut self, f: &FilterCondition) -> Option<Option<String>> {
+        match self.column_types.get(&f.column) {
+            Some(ty) if ty.ends_with("[]") => Some(Some(ty.clone())),
+            Some(ty) => {
+                self.invalid.get_or_insert_with(|| {
+                    format!(
+                        "Array filters need an array column, but {} is {}",
+                        f.column, ty
+                    )
+                });
+                None
+            }
+            None => Some(None),
+        }
+    }
 
-        Ok(JsonValue::Object(
-            rows.into_iter().next().unwrap_or_default(),
+    /// Bind every entry of `values` as text into an `ARRAY[...]` cast to the
+    /// column's array type (`text[]` when unknown), or `None` when the list is
+    /// missing or empty
+    fn bind_array(&mut self, f: &FilterCondition) -> Option<String> {
+        let array_type = self
+            .array_type(f)?
+            .map_or_else(|| "text[]".to_string(), |ty| cast_type(&ty));
+        let vals = f.values.as_ref().filter(|vals| !vals.is_empty())?;
+        let placeholders: Vec<String> = vals.iter().map(|v| self.bind_text(v)).collect();
+        Some(format!(
+            "ARRAY[{}]::{}",
+            placeholders.join(", "),
+            array_type
         ))
     }
 
-    /// Bulk insert multiple rows into a table
-    pub async fn bulk_insert(pool: &PgPool, request: BulkInsertRequest) -> Result<u64> {
-        if request.rows.is_empty() {
-            return Ok(0);
-        }
-
-        // Get columns from the first row
-        let first_row = &request.rows[0];
-        if first_row.is_empty() {
-            return Err(DbViewerError::InvalidQuery(
-                "No data provided for insert".to_string(),
-            ));
+    /// Bind every entry of `values`, returning the comma-separated placeholders,
+    /// or `None` when the list is missing or empty
+    fn bind_list(&mut self, f: &FilterCondition) -> Option<String> {
+        let vals = f.values.as_ref()?;
+        if vals.is_empty() {
+            return None;
         }
+        let placeholders: Vec<String> =
+            vals.iter().map(|v| self.bind_typed(&f.column, v)).collect();
+        Some(placeholders.join(", "))
+    }
 
-        let columns: Vec<&str> = first_row.keys().map(|s| s.as_str()).collect();
-        let column_list = columns
-            .iter()
-            .map(|c| quote_identifier(c))
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        // Build VALUES clause for all rows
-        let values_list: Vec<String> = request
-            .rows
+    /// Build the SQL for a group, recursing into nested groups. Returns `None`
+    /// when nothing in the group produces a condition, so empty groups vanish
+    /// instead of rendering as `()`.
+    fn group(&mut self, group: &FilterGroup) -> Option<String> {
+        let parts: Vec<String> = group
+            .conditions
             .iter()
-            .map(|row| {
-                let values: Vec<String> = columns
-                    .iter()
-                    .map(|col| {
-                        row.get(*col)
-                            .map(json_value_to_sql)
-                            .unwrap_or_else(|| "NULL".to_string())
-                    })
-                    .collect();
-                format!("({})", values.join(", "))
+            .filter_map(|node| match node {
+                FilterNode::Condition(f) => self.condition(f),
+                FilterNode::Group(g) => self.group(g),
             })
             .collect();
+        match parts.len() {
+            0 => None,
+            1 => parts.into_iter().next(),
+            _ => Some(format!("({})", parts.join(group.logic.keyword()))),
+        }
+    }
+}
 
-        let query = format!(
-            "INSERT INTO {}.{} ({}) VALUES {}",
-            quote_identifier(&request.schema),
-            quote_identifier(&request.table),
-            column_list,
-            values_list.join(", ")
-        );
+/// Build a WHERE clause from filter groups, returning the SQL and the values
+/// to bind to its `$n` placeholders in order.
+/// `column_types` maps column names to their SQL type for casting bound values.
+fn build_where_clause(
+    groups: &[FilterGroup],
+    column_types: &HashMap<String, String>,
+) -> Result<(String, Vec<BoundValue>)> {
+    let mut builder = WhereBuilder::new(column_types);
+    let parts: Vec<String> = groups.iter().filter_map(|g| builder.group(g)).collect();
 
-        let result = pool.execute(query.as_str()).await?;
-        Ok(result.rows_affected())
+    if let Some(reason) = builder.invalid {
+        return Err(DbViewerError::InvalidQuery(reason));
+    }
+    if parts.is_empty() {
+        Ok((String::new(), Vec::new()))
+    } else {
+        Ok((format!("WHERE {}", parts.join(" AND ")), builder.binds))
     }
+}
 
-    /// Update a row in a table
-    pub async fn update_row(pool: &PgPool, request: UpdateRequest) -> Result<u64> {
-        if request.data.is_empty() {
-            return Err(DbViewerError::InvalidQuery(
-                "No data provided for update".to_string(),
-            ));
-        }
+/// A WHERE clause matching rows that meet all of `filters`, for changing or
+/// counting rows in bulk. `action` names the operation in errors. An empty
+/// filter list, or one without a usable condition, is refused rather than
+/// matching the whole table.
+fn filter_where_clause(
+    filters: &[FilterCondition],
+    column_types: &HashMap<String, String>,
+    action: &str,
+) -> Result<(String, Vec<BoundValue>)> {
+    if filters.is_empty() {
+        return Err(DbViewerError::InvalidQuery(format!(
+            "No filters provided for {}",
+            action
+        )));
+    }
 
-        if request.where_clause.is_empty() {
-            return Err(DbViewerError::InvalidQuery(
-                "No where clause provided for update".to_string(),
-            ));
-        }
+    let group = FilterGroup {
+        conditions: filters.iter().cloned().map(FilterNode::from).collect(),
+        logic: FilterLogic::And,
+    };
+    let (where_clause, binds) = build_where_clause(&[group], column_types)?;
+    // Conditions without a usable value are dropped, which could leave no WHERE at all
+    if where_clause.is_empty() {
+        return Err(DbViewerError::InvalidQuery(format!(
+            "No usable filters provided for {}",
+            action
+        )));
+    }
+    Ok((where_clause, binds))
+}
 
-        let set_clause: Vec<String> = request
-            .data
-            .iter()
-            .map(|(col, val)| format!("{} = {}", quote_identifier(col), json_value_to_sql(val)))
-            .collect();
+/// A filter whose value can't be compared with its column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterValidationError {
+    pub column: String,
+    pub operator: FilterOperator,
+    pub message: String,
+}
 
-        let where_clause: Vec<String> = request
-            .where_clause
-            .iter()
-            .map(|(col, val)| format!("{} = {}", quote_identifier(col), json_value_to_sql(val)))
-            .collect();
+/// The kinds of column whose filter values are checked before querying
+enum FilterValueKind {
+    Integer,
+    Float,
+    Date,
+    Timestamp,
+    Boolean,
+}
 
-        let query = format!(
-            "UPDATE {}.{} SET {} WHERE {}",
-            quote_identifier(&request.schema),
-            quote_identifier(&request.table),
-            set_clause.join(", "),
-            where_clause.join(" AND ")
-        );
+impl FilterValueKind {
+    fn of(column: &ColumnInfo) -> Option<Self> {
+        match column.udt_name.as_str() {
+            "int2" | "int4" | "int8" => Some(Self::Integer),
+            "float4" | "float8" | "numeric" => Some(Self::Float),
+            "date" => Some(Self::Date),
+            "timestamp" | "timestamptz" => Some(Self::Timestamp),
+            "bool" => Some(Self::Boolean),
+            _ => None,
+        }
+    }
+
+    /// Why `value` isn't accepted for this kind of column, if it isn't
+    fn check(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        let valid = match self {
+            Self::Integer => value.parse::<i64>().is_ok(),
+            Self::Float => value.parse::<f64>().is_ok(),
+            Self::Date => is_special_date(value) || parse_date(value).is_some(),
+            Self::Timestamp => is_special_date(value) || parse_timestamp(value).is_some(),
+            Self::Boolean => matches!(value.to_lowercase().as_str(), "true" | "false" | "1" | "0"),
+        };
+        if valid {
+            return None;
+        }
+        let expected = match self {
+            Self::Integer => "an integer",
+            Self::Float => "a number",
+            Self::Date => "a date (YYYY-MM-DD)",
+            Self::Timestamp => "a timestamp (YYYY-MM-DD HH:MM:SS)",
+            Self::Boolean => "true, false, 1 or 0",
+        };
+        Some(format!("{:?} is not {}", value, expected))
+    }
+}
 
-        let result = pool.execute(query.as_str()).await?;
+/// Date and time inputs PostgreSQL accepts by name
+fn is_special_date(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "now" | "today" | "tomorrow" | "yesterday" | "epoch" | "infinity" | "-infinity"
+    )
+}
 
-        Ok(result.rows_affected())
+fn parse_date(value: &str) -> Option<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+/// Accepts a bare date, a date and time separated by a space or `T`, and
+/// either of those with a UTC offset
+fn parse_timestamp(value: &str) -> Option<chrono::NaiveDateTime> {
+    if let Some(date) = parse_date(value) {
+        return date.and_hms_opt(0, 0, 0);
     }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| {
+            chrono::NaiveDateTime::parse_from_str(value, format)
+                .ok()
+                .or_else(|| {
+                    chrono::DateTime::parse_from_str(value, &format!("{}%#z", format))
+                        .ok()
+                        .map(|dt| dt.naive_utc())
+                })
+        })
+}
 
-    /// Delete a row from a table
-    pub async fn delete_row(pool: &PgPool, request: DeleteRequest) -> Result<u64> {
-        if request.where_clause.is_empty() {
-            return Err(DbViewerError::InvalidQuery(
-                "No where clause provided for delete".to_string(),
-            ));
+/// Check filter values against the types of the columns they compare with,
+/// returning every problem found. Only operators that compare the column
+/// itself are checked; text matches, JSON and array filters, and columns
+/// missing from `columns` are left to the database.
+pub fn validate_filters(
+    filters: &[FilterCondition],
+    columns: &[ColumnInfo],
+) -> Result<Vec<FilterValidationError>> {
+    let mut errors = Vec::new();
+    for f in filters {
+        let mut invalid = |message: String| {
+            errors.push(FilterValidationError {
+                column: f.column.clone(),
+                operator: f.operator.clone(),
+                message,
+            })
+        };
+
+        if matches!(f.operator, FilterOperator::Between) {
+            if f.value.is_none() {
+                invalid("Between needs a lower bound".to_string());
+            }
+            if f.value2.is_none() {
+                invalid("Between needs an upper bound".to_string());
+            }
         }
 
-        let where_clause: Vec<String> = request
-            .where_clause
+        let values: Vec<&String> = match f.operator {
+            FilterOperator::Equals
+            | FilterOperator::NotEquals
+            | FilterOperator::GreaterThan
+            | FilterOperator::LessThan
+            | FilterOperator::GreaterThanOrEqual
+            | FilterOperator::LessThanOrEqual => f.value.iter().collect(),
+            FilterOperator::Between => f.value.iter().chain(&f.value2).collect(),
+            FilterOperator::In | FilterOperator::NotIn => f.values.iter().flatten().collect(),
+            _ => continue,
+        };
+        let Some(kind) = columns
             .iter()
-            .map(|(col, val)| format!("{} = {}", quote_identifier(col), json_value_to_sql(val)))
-            .collect();
-
-        let query = format!(
-            "DELETE FROM {}.{} WHERE {}",
-            quote_identifier(&request.schema),
-            quote_identifier(&request.table),
-            where_clause.join(" AND ")
-        );
+            .find(|c| c.name == f.column)
+            .and_then(FilterValueKind::of)
+        else {
+            continue;
+        };
+        for value in values {
+            if let Some(message) = kind.check(value) {
+                invalid(message);
+            }
+        }
+    }
+    Ok(errors)
+}
 
-        let result = pool.execute(query.as_str()).await?;
+/// Every condition in `groups`, including those in nested groups
+fn flatten_conditions(groups: &[FilterGroup]) -> Vec<FilterCondition> {
+    let mut conditions = Vec::new();
+    let mut pending: Vec<&FilterGroup> = groups.iter().collect();
+    while let Some(group) = pending.pop() {
+        for node in &group.conditions {
+            match node {
+                FilterNode::Condition(f) => conditions.push(f.clone()),
+                FilterNode::Group(g) => pending.push(g),
+            }
+        }
+    }
+    conditions
+}
 
-        Ok(result.rows_affected())
+/// `(a::text ILIKE $n OR b::text ILIKE $n ...)`, matching rows where any
+/// column contains `search`. bytea columns are skipped, as their text form is
+/// hex; every other type has a cast to text. With no column left to search,
+/// nothing matches.
+fn search_condition(
+    search: &str,
+    column_types: &HashMap<String, String>,
+    binds: &mut Vec<BoundValue>,
+) -> String {
+    let mut columns: Vec<&String> = column_types
+        .iter()
+        .filter(|(_, ty)| !ty.starts_with("bytea"))
+        .map(|(col, _)| col)
+        .collect();
+    if columns.is_empty() {
+        return "false".to_string();
     }
+    columns.sort();
 
-    /// Execute a raw SQL query
-    pub async fn execute_raw_query(pool: &PgPool, sql: &str) -> Result<QueryResult> {
-        let sql_trimmed = sql.trim();
+    binds.push(BoundValue::Text(format!(
+        "%{}%",
+        escape_like_pattern(search)
+    )));
+    let placeholder = format!("${}", binds.len());
+    let parts: Vec<String> = columns
+        .iter()
+        .map(|col| {
+            format!(
+                "{}::text ILIKE {} ESCAPE '\\'",
+                quote_identifier(col),
+                placeholder
+            )
+        })
+        .collect();
+    format!("({})", parts.join(" OR "))
+}
 
-        if sql_trimmed.is_empty() {
-            return Err(DbViewerError::InvalidQuery("Empty query".to_string()));
-        }
+/// Fetch the SQL type of every column in a table, keyed by column name
+async fn fetch_column_types(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+) -> Result<HashMap<String, String>> {
+    Ok(fetch_table_columns(pool, schema, table)
+        .await?
+        .into_iter()
+        .collect())
+}
 
-        let start_time = std::time::Instant::now();
+/// Fetch a table's column names and SQL types in table order
+async fn fetch_table_columns(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<(String, String)>> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        r#"
+        SELECT a.attname, format_type(a.atttypid, a.atttypmod)
+        FROM pg_attribute a
+        WHERE a.attrelid = (quote_ident($1) || '.' || quote_ident($2))::regclass
+          AND a.attnum > 0
+          AND NOT a.attisdropped
+        ORDER BY a.attnum
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
 
-        // Determine if this is a SELECT query or a mutation
-        let sql_upper = sql_trimmed.to_uppercase();
-        let is_select = sql_upper.starts_with("SELECT")
-            || sql_upper.starts_with("WITH")
-            || sql_upper.starts_with("EXPLAIN")
-            || sql_upper.starts_with("SHOW");
-
-        if is_select {
-            let rows = sqlx::query(sql_trimmed).fetch_all(pool).await?;
-            let (rows, columns) = rows_to_json(&rows);
+    Ok(rows)
+}
 
-            Ok(QueryResult {
-                rows,
-                columns,
-                rows_affected: 0,
-                execution_time_ms: start_time.elapsed().as_millis(),
-            })
-        } else {
-            let result = pool.execute(sql_trimmed).await?;
+/// Build the select list for `fetch_paginated`. A column that can hold long
+/// values reads NULL when its text is over `max_cell_bytes`, and a helper
+/// column after it carries the prefix instead. Returns the list with each
+/// (column, helper alias) pair for `apply_truncation`. Selects `*` when there
+/// is nothing to truncate and no column list.
+fn truncating_select_list(
+    selected: Option<&Vec<String>>,
+    table_columns: &[(String, String)],
+    max_cell_bytes: usize,
+) -> (String, Vec<(String, String)>) {
+    let types: HashMap<&str, &str> = table_columns
+        .iter()
+        .map(|(name, ty)| (name.as_str(), ty.as_str()))
+        .collect();
+    let names: Vec<&str> = match selected {
+        Some(cols) => cols.iter().map(String::as_str).collect(),
+        None if max_cell_bytes == 0 || table_columns.is_empty() => {
+            return ("*".to_string(), Vec::new())
+        }
+        None => table_columns
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect(),
+    };
 
-            Ok(QueryResult {
-                rows: Vec::new(),
-                columns: Vec::new(),
-                rows_affected: result.rows_affected(),
-                execution_time_ms: start_time.elapsed().as_millis(),
-            })
+    let mut items = Vec::with_capacity(names.len());
+    let mut truncatable = Vec::new();
+    for name in names {
+        let col = quote_identifier(name);
+        let long = max_cell_bytes > 0
+            && types
+                .get(name)
+                .is_some_and(|ty| TRUNCATABLE_TYPES.contains(&base_type(ty).as_str()));
+        if !long {
+            items.push(col);
+            continue;
         }
+        let alias = format!("__tusker_truncated_{}", truncatable.len());
+        items.push(format!(
+            "CASE WHEN octet_length({c}::text) > {n} THEN NULL ELSE {c} END AS {c}",
+            c = col,
+            n = max_cell_bytes
+        ));
+        items.push(format!(
+            "CASE WHEN octet_length({c}::text) > {n} THEN left({c}::text, {n}) END AS {}",
+            quote_identifier(&alias),
+            c = col,
+            n = max_cell_bytes
+        ));
+        truncatable.push((name.to_string(), alias));
     }
+    (items.join(", "), truncatable)
 }
 
-// ============================================================================
-// Migration Operations
-// ============================================================================
+/// Fold the helper columns from `truncating_select_list` back into their
+/// columns, returning which cells hold a truncated prefix
+fn apply_truncation(
+    rows: &mut [serde_json::Map<String, JsonValue>],
+    columns: &mut Vec<ColumnMeta>,
+    truncatable: &[(String, String)],
+) -> Vec<TruncatedCell> {
+    if truncatable.is_empty() {
+        return Vec::new();
+    }
+    columns.retain(|c| !truncatable.iter().any(|(_, alias)| *alias == c.name));
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MigrationRequest {
-    pub connection_id: String,
-    pub statements: Vec<String>,
-    pub dry_run: bool,
-    pub lock_timeout_ms: Option<u32>,
-    pub statement_timeout_ms: Option<u32>,
+    let mut truncated = Vec::new();
+    for (i, row) in rows.iter_mut().enumerate() {
+        for (column, alias) in truncatable {
+            if let Some(prefix @ JsonValue::String(_)) = row.remove(alias) {
+                row.insert(column.clone(), prefix);
+                truncated.push(TruncatedCell {
+                    row: i,
+                    column: column.clone(),
+                });
+            }
+        }
+    }
+    truncated
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StatementError {
-    pub code: Option<String>,
-    pub message: String,
-    pub detail: Option<String>,
-    pub hint: Option<String>,
-}
+/// Fetch the primary key columns of a table with their SQL types, in key order.
+/// Returns an empty list when the table has no primary key.
+async fn fetch_primary_key(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<(String, String)>> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        r#"
+        SELECT a.attname, format_type(a.atttypid, a.atttypmod)
+        FROM pg_index i
+        JOIN LATERAL unnest(i.indkey) WITH ORDINALITY AS k(attnum, ord) ON true
+        JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = k.attnum
+        WHERE i.indrelid = (quote_ident($1) || '.' || quote_ident($2))::regclass
+          AND i.indisprimary
+        ORDER BY k.ord
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StatementResult {
-    pub sql: String,
-    pub ok: bool,
-    pub duration_ms: f64,
-    pub rows_affected: Option<u64>,
-    pub error: Option<StatementError>,
+    Ok(rows)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MigrationResult {
-    pub ok: bool,
-    pub dry_run: bool,
-    pub committed: bool,
-    pub duration_ms: f64,
-    pub statements: Vec<StatementResult>,
-    pub lock_timeout_ms: u32,
-    pub statement_timeout_ms: u32,
+/// The query behind a page's `total_count`
+struct CountQuery<'a> {
+    qualified_table: &'a str,
+    where_clause: &'a str,
+    binds: &'a [BoundValue],
+    count_mode: CountMode,
+    total_count_hint: Option<i64>,
+    sample: Option<&'a TableSample>,
 }
 
-pub struct MigrationOperations;
+impl CountQuery<'_> {
+    /// Count the rows matching `where_clause` according to `count_mode`,
+    /// returning the count and whether it is exact, or `None` when counting is
+    /// skipped. A `total_count_hint` from an earlier page is returned as-is
+    /// without querying.
+    async fn run(&self, conn: &mut PgConnection) -> Result<Option<(i64, bool)>> {
+        if let Some(hint) = self.total_count_hint {
+            return Ok(Some((
+                hint,
+                matches!(self.count_mode, CountMode::Exact) && self.sample.is_none(),
+            )));
+        }
 
-impl MigrationOperations {
-    pub async fn execute_migration(
-        pool: &PgPool,
-        statements: &[String],
-        dry_run: bool,
-        lock_timeout_ms: Option<u32>,
-        statement_timeout_ms: Option<u32>,
-    ) -> Result<MigrationResult> {
-        let lock_timeout = lock_timeout_ms.unwrap_or(5000);
-        let stmt_timeout = statement_timeout_ms.unwrap_or(30000);
-        let total_start = Instant::now();
+        // A sample's size is estimated from the table's, never counted
+        let count_mode = if self.sample.is_some() {
+            CountMode::Estimated
+        } else {
+            self.count_mode
+        };
+        let scale = |count: i64| match self.sample {
+            Some(sample) => (count as f64 * sample.percentage / 100.0).round() as i64,
+            None => count,
+        };
 
-        // Acquire a connection and begin transaction
-        let mut tx = pool.begin().await?;
+        match count_mode {
+            CountMode::None => return Ok(None),
+            CountMode::Estimated if self.where_clause.is_empty() => {
+                let (estimate,) = sqlx::query_as::<_, (f32,)>(
+                    "SELECT reltuples FROM pg_class WHERE oid = $1::regclass",
+                )
+                .bind(self.qualified_table)
+                .fetch_one(&mut *conn)
+                .await?;
+                // reltuples is -1 until the table is first vacuumed or analyzed
+                if estimate >= 0.0 {
+                    return Ok(Some((scale(estimate as i64), false)));
+                }
+            }
+            CountMode::Estimated => {
+                let explain = format!(
+                    "EXPLAIN (FORMAT JSON) SELECT 1 FROM {} {}",
+                    self.qualified_table, self.where_clause
+                );
+                let (plan,) =
+                    sqlx::query_as_with::<_, (JsonValue,), _>(&explain, bind_values(self.binds)?)
+                        .fetch_one(&mut *conn)
+                        .await?;
+                if let Some(rows) = plan[0]["Plan"]["Plan Rows"].as_f64() {
+                    return Ok(Some((scale(rows as i64), false)));
+                }
+            }
+            CountMode::Exact => {}
+        }
 
-        // Set session-local timeouts
-        let setup_sqls = [
-            format!("SET LOCAL lock_timeout = '{lock_timeout}ms'"),
-            format!("SET LOCAL statement_timeout = '{stmt_timeout}ms'"),
-            format!("SET LOCAL idle_in_transaction_session_timeout = '60s'"),
-            "SET LOCAL application_name = 'tusker-migration'".to_string(),
-        ];
+        let count_query = format!(
+            "SELECT COUNT(*) FROM {} {}",
+            self.qualified_table, self.where_clause
+        );
+        let (count,) = sqlx::query_as_with::<_, (i64,), _>(&count_query, bind_values(self.binds)?)
+            .fetch_one(&mut *conn)
+            .await?;
+        Ok(Some((scale(count), self.sample.is_none())))
+    }
 
-        for sql in &setup_sqls {
-            if let Err(e) = sqlx::query(sql).execute(&mut *tx).await {
-                return Ok(MigrationResult {
-                    ok: false,
-                    dry_run,
-                    committed: false,
-                    duration_ms: total_start.elapsed().as_secs_f64() * 1000.0,
-                    statements: vec![StatementResult {
-                        sql: sql.clone(),
-                        ok: false,
-                        duration_ms: 0.0,
-                        rows_affected: None,
-                        error: Some(extract_pg_error(&e)),
-                    }],
-                    lock_timeout_ms: lock_timeout,
-                    statement_timeout_ms: stmt_timeout,
-                });
+    /// Run on `conn` when there is one, else on a connection from the pool
+    async fn run_on(
+        &self,
+        pool: &PgPool,
+        conn: Option<&mut PgConnection>,
+    ) -> Result<Option<(i64, bool)>> {
+        match conn {
+            Some(conn) => self.run(conn).await,
+            None => self.run(&mut *pool.acquire().await?).await,
+        }
+    }
+}
+
+/// Read a page's rows on `conn` when there is one, else from the pool
+async fn fetch_page_rows(
+    pool: &PgPool,
+    conn: Option<&mut PgConnection>,
+    query: &str,
+    binds: &[BoundValue],
+) -> Result<Vec<PgRow>> {
+    let query = sqlx::query_with(query, bind_values(binds)?);
+    Ok(match conn {
+        Some(conn) => query.fetch_all(conn).await?,
+        None => query.fetch_all(pool).await?,
+    })
+}
+
+/// Run a page's count and data queries: one after the other on `conn` when
+/// the page has its own connection, else concurrently on the pool
+async fn count_and_fetch(
+    pool: &PgPool,
+    conn: Option<&mut PgConnection>,
+    count: &CountQuery<'_>,
+    query: &str,
+    binds: &[BoundValue],
+) -> Result<(Option<(i64, bool)>, Vec<PgRow>)> {
+    match conn {
+        Some(conn) => {
+            let counted = count.run(conn).await?;
+            let rows = fetch_page_rows(pool, Some(conn), query, binds).await?;
+            Ok((counted, rows))
+        }
+        None => {
+            let (counted, rows) = tokio::join!(
+                count.run_on(pool, None),
+                fetch_page_rows(pool, None, query, binds)
+            );
+            Ok((counted?, rows?))
+        }
+    }
+}
+
+/// Text form of a cursor value, bound as a parameter cast to the key column type
+fn cursor_value_text(column: &str, value: &JsonValue) -> Result<String> {
+    match value {
+        JsonValue::String(s) => Ok(s.clone()),
+        JsonValue::Null => Err(DbViewerError::InvalidQuery(format!(
+            "Cursor value for {} is null",
+            column
+        ))),
+        other => Ok(other.to_string()),
+    }
+}
+
+/// Stand-in total when counting is skipped: the rows up to the end of this page,
+/// plus one when the page is full
+fn uncounted_total(offset: i64, rows_on_page: usize, page_size: i64) -> i64 {
+    let seen = offset + rows_on_page as i64;
+    if rows_on_page as i64 == page_size {
+        seen + 1
+    } else {
+        seen
+    }
+}
+
+pub struct DataOperations;
+
+impl DataOperations {
+    /// Fetch a page of `request.table`. `page` is clamped to at least 1 and
+    /// `page_size` to `1..=MAX_PAGE_SIZE`; the returned result reports the
+    /// clamped values. `columns` limits the selected columns, all of them when
+    /// empty; page cursors are only returned when the primary key is selected.
+    /// `search` keeps rows where any column's text contains it, on top of `filters`.
+    /// Text, json and bytea values longer than `max_cell_bytes` come back as a
+    /// text prefix and are listed in `truncated_cells`, and shorter bytea values
+    /// are cut to a preview; without it (or with 0) values are returned whole.
+    ///
+    /// With `timeout_ms`, the count and data queries run one after the other
+    /// in a transaction with that statement timeout, and fail with
+    /// `QueryTimeout` when it runs out. They run the same way on `conn` when
+    /// given, so the caller knows which backend to cancel; a cancelled fetch
+    /// fails with `Cancelled`.
+    /// With `sample`, rows come from a random sample of the table and
+    /// `total_count` is the estimated size of that sample. Views can't be
+    /// sampled.
+    ///
+    /// In `PaginationMode::CursorBased`, `order_by` and `cursor` are ignored,
+    /// `page` is reported as 1 and the returned cursors hold the single
+    /// cursor column value to pass back as `cursor_value`.
+    pub async fn fetch_paginated(
+        pool: &PgPool,
+        request: &PageRequest,
+        conn: Option<CancellableConn<'_>>,
+    ) -> Result<PaginatedResult> {
+        let schema = request.schema.as_str();
+        let table = request.table.as_str();
+        let mode = &request.mode;
+        let order_by = request.order_by.as_ref();
+        let order_direction = request.order_direction.as_ref();
+        let filters = request.filters.as_ref();
+        let cursor = request.cursor.as_ref();
+        let columns = request.columns.as_ref();
+        let search = request.search.as_deref();
+        let sample = request.sample.as_ref();
+        let timeout_ms = request.timeout_ms;
+
+        let (page, page_size) = match mode {
+            PaginationMode::OffsetBased { page, page_size } => (*page, *page_size),
+            PaginationMode::CursorBased { limit, .. } => (1, Some(*limit)),
+        };
+        let page = page.max(1);
+        let page_size = page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let offset = (page - 1) * page_size;
+        let max_cell_bytes = request.max_cell_bytes.unwrap_or(0);
+
+        let has_explicit_order = matches!(order_by, Some(specs) if !specs.is_empty());
+        let has_expression = order_by
+            .into_iter()
+            .flatten()
+            .any(|spec| matches!(spec, OrderSpec::Expression(_)));
+        let selected = columns.filter(|c| !c.is_empty());
+
+        // Filter values are bound as parameters typed by each column, and
+        // truncation needs to know which columns hold long values
+        let filters = filters.filter(|f| !f.is_empty());
+        let search = search.map(str::trim).filter(|s| !s.is_empty());
+        let cursor_mode = matches!(mode, PaginationMode::CursorBased { .. });
+        // Filters are validated against the full column info, which has the
+        // column types as well
+        let table_columns = if let Some(groups) = filters {
+            let columns = SchemaIntrospector::get_columns(pool, schema, table).await?;
+            let errors = validate_filters(&flatten_conditions(groups), &columns)?;
+            if !errors.is_empty() {
+                let messages: Vec<String> = errors
+                    .iter()
+                    .map(|e| format!("{}: {}", e.column, e.message))
+                    .collect();
+                return Err(DbViewerError::InvalidQuery(format!(
+                    "Invalid filter values: {}",
+                    messages.join("; ")
+                )));
+            }
+            // A column in several foreign keys is listed once per key
+            let mut table_columns: Vec<(String, String)> =
+                columns.into_iter().map(|c| (c.name, c.data_type)).collect();
+            table_columns.dedup_by(|a, b| a.0 == b.0);
+            table_columns
+        } else if search.is_some() || max_cell_bytes > 0 || cursor_mode || has_expression {
+            fetch_table_columns(pool, schema, table).await?
+        } else {
+            Vec::new()
+        };
+        let column_types: HashMap<String, String> = table_columns.iter().cloned().collect();
+        let (select_list, truncatable) =
+            truncating_select_list(selected, &table_columns, max_cell_bytes);
+        let (mut where_clause, mut binds) = filters
+            .map(|f| build_where_clause(f, &column_types))
+            .transpose()?
+            .unwrap_or_default();
+        if let Some(search) = search {
+            let condition = search_condition(search, &column_types, &mut binds);
+            where_clause = if where_clause.is_empty() {
+                format!("WHERE {}", condition)
+            } else {
+                format!("{} AND {}", where_clause, condition)
+            };
+        }
+
+        let qualified_table = format!("{}.{}", quote_identifier(schema), quote_identifier(table));
+
+        // Rows are read from `source`; a sample's count is estimated from the table
+        let source = match sample {
+            Some(sample) => {
+                let clause = sample.clause()?;
+                let relkind: Option<String> = sqlx::query_scalar(
+                    "SELECT relkind::text FROM pg_class WHERE oid = $1::regclass",
+                )
+                .bind(&qualified_table)
+                .fetch_optional(pool)
+                .await?;
+                if relkind.as_deref() == Some("v") {
+                    return Err(DbViewerError::InvalidQuery(format!(
+                        "{}.{} is a view; only tables can be sampled",
+                        schema, table
+                    )));
+                }
+                format!("{} {}", qualified_table, clause)
             }
+            None => qualified_table.clone(),
+        };
+
+        // A timeout needs a connection of its own to set it on
+        let (conn, cancelled) = match conn {
+            Some(c) => (Some(c.conn), Some(c.cancelled)),
+            None => (None, None),
+        };
+        let mut tx = match (conn, timeout_ms) {
+            (Some(conn), _) => Some(conn.begin().await?),
+            (None, Some(_)) => Some(pool.begin().await?),
+            (None, None) => None,
+        };
+        if let (Some(tx), Some(timeout_ms)) = (tx.as_mut(), timeout_ms) {
+            sqlx::query(&format!("SET LOCAL statement_timeout = '{timeout_ms}ms'"))
+                .execute(&mut **tx)
+                .await?;
         }
+        let query_error = |e: DbViewerError| {
+            let cancelled = cancelled.is_some_and(|c| c.load(Ordering::SeqCst));
+            page_query_error(e, timeout_ms, cancelled)
+        };
+        let count = CountQuery {
+            qualified_table: &qualified_table,
+            where_clause: &where_clause,
+            binds: &binds,
+            count_mode: request.count_mode,
+            total_count_hint: request.total_count_hint,
+            sample,
+        };
 
-        let mut results: Vec<StatementResult> = Vec::new();
-        let mut all_ok = true;
+        if let PaginationMode::CursorBased {
+            cursor_column,
+            cursor_value,
+            direction,
+            ..
+        } = mode
+        {
+            let ty = column_types.get(cursor_column).ok_or_else(|| {
+                DbViewerError::InvalidQuery(format!("Unknown cursor column: {}", cursor_column))
+            })?;
 
-        for (i, stmt) in statements.iter().enumerate() {
-            let trimmed = stmt.trim();
-            if trimmed.is_empty() {
-                continue;
+            // The seek only narrows the page; the count covers every matching row
+            let cursor_value = cursor_value.as_ref().filter(|v| !v.is_null());
+            let mut data_where = where_clause.clone();
+            let mut data_binds = binds.clone();
+            if let Some(value) = cursor_value {
+                data_binds.push(BoundValue::Text(cursor_value_text(cursor_column, value)?));
+                let seek = format!(
+                    "{} {} ${}::{}",
+                    quote_identifier(cursor_column),
+                    match direction {
+                        CursorDirection::Next => ">",
+                        CursorDirection::Prev => "<",
+                    },
+                    data_binds.len(),
+                    ty
+                );
+                data_where = if data_where.is_empty() {
+                    format!("WHERE {}", seek)
+                } else {
+                    format!("{} AND {}", data_where, seek)
+                };
             }
 
-            let stmt_start = Instant::now();
+            // Prev pages are read backwards from the cursor, then flipped
+            let descending = *direction == CursorDirection::Prev;
+            let data_query = format!(
+                "SELECT {} FROM {} {} ORDER BY {} {} LIMIT {}",
+                select_list,
+                source,
+                data_where,
+                quote_identifier(cursor_column),
+                if descending { "DESC" } else { "ASC" },
+                page_size
+            );
+            let (counted, rows) =
+                count_and_fetch(pool, tx.as_deref_mut(), &count, &data_query, &data_binds)
+                    .await
+                    .map_err(query_error)?;
 
-            if dry_run {
-                // Use savepoints so we can recover from errors and continue
-                // validating subsequent statements. Don't roll back on success —
-                // let effects accumulate so later statements see prior changes
-                // (e.g. RENAME TABLE followed by ALTER on the new name).
-                // The entire transaction is rolled back at the end.
-                let sp_name = format!("s{i}");
-                let _ = sqlx::query(&format!("SAVEPOINT {sp_name}"))
-                    .execute(&mut *tx)
-                    .await;
+            let (mut rows, mut columns) = rows_to_json_with(&rows, max_cell_bytes > 0);
+            if columns.is_empty() {
+                columns = describe_columns(pool, &data_query).await?;
+            }
+            if descending {
+                rows.reverse();
+            }
+            let truncated_cells = apply_truncation(&mut rows, &mut columns, &truncatable);
 
-                match sqlx::query(trimmed).execute(&mut *tx).await {
-                    Ok(r) => {
-                        let duration = stmt_start.elapsed().as_secs_f64() * 1000.0;
-                        results.push(StatementResult {
-                            sql: trimmed.to_string(),
-                            ok: true,
-                            duration_ms: duration,
-                            rows_affected: Some(r.rows_affected()),
-                            error: None,
-                        });
-                    }
-                    Err(e) => {
-                        let duration = stmt_start.elapsed().as_secs_f64() * 1000.0;
-                        all_ok = false;
-                        results.push(StatementResult {
-                            sql: trimmed.to_string(),
-                            ok: false,
-                            duration_ms: duration,
-                            rows_affected: None,
-                            error: Some(extract_pg_error(&e)),
-                        });
-                        // Roll back only on error so the transaction stays usable
-                        let _ = sqlx::query(&format!("ROLLBACK TO SAVEPOINT {sp_name}"))
-                            .execute(&mut *tx)
-                            .await;
-                    }
-                }
+            // Seeking from a cursor means rows were skipped on that side
+            let full_page = rows.len() as i64 == page_size;
+            let (has_prev, has_next) = match direction {
+                CursorDirection::Next => (cursor_value.is_some(), full_page),
+                CursorDirection::Prev => (full_page, cursor_value.is_some()),
+            };
+            let cursor_at = |row: &serde_json::Map<String, JsonValue>, direction| {
+                row.get(cursor_column)
+                    .filter(|v| !v.is_null())
+                    .map(|v| PageCursor {
+                        values: vec![v.clone()],
+                        direction,
+                    })
+            };
+            let prev_cursor = rows
+                .first()
+                .filter(|_| has_prev)
+                .and_then(|r| cursor_at(r, CursorDirection::Prev));
+            let next_cursor = rows
+                .last()
+                .filter(|_| has_next)
+                .and_then(|r| cursor_at(r, CursorDirection::Next));
+
+            let (total_count, total_count_exact) =
+                counted.unwrap_or_else(|| (uncounted_total(0, rows.len(), page_size), false));
+            let total_pages = (total_count as f64 / page_size as f64).ceil() as i64;
+
+            return Ok(PaginatedResult {
+                rows,
+                total_count,
+                page,
+                page_size,
+                total_pages,
+                total_count_exact,
+                columns,
+                next_cursor,
+                prev_cursor,
+                truncated_cells,
+            });
+        }
+
+        if has_explicit_order {
+            // Explicit sort provided — build order clause and run COUNT + SELECT concurrently
+            let specs = order_by.unwrap();
+            let directions = order_direction.cloned().unwrap_or_default();
+            let parts: Vec<String> = specs
+                .iter()
+                .enumerate()
+                .map(|(i, spec)| {
+                    let dir = directions
+                        .get(i)
+                        .map(|d| {
+                            if d.to_uppercase() == "DESC" {
+                                "DESC"
+                            } else {
+                                "ASC"
+                            }
+                        })
+                        .unwrap_or("ASC");
+                    let key = match spec {
+                        OrderSpec::Column(col) => quote_identifier(col),
+                        OrderSpec::Expression(expression) => {
+                            validate_order_expression(expression, &column_types)?;
+                            format!("({})", expression)
+                        }
+                    };
+                    Ok(format!("{} {}", key, dir))
+                })
+                .collect::<Result<_>>()?;
+            let order_clause = format!("ORDER BY {}", parts.join(", "));
+
+            let data_query = format!(
+                "SELECT {} FROM {} {} {} LIMIT {} OFFSET {}",
+                select_list, source, where_clause, order_clause, page_size, offset
+            );
+
+            let (counted, rows) =
+                count_and_fetch(pool, tx.as_deref_mut(), &count, &data_query, &binds)
+                    .await
+                    .map_err(query_error)?;
+
+            let (mut rows, mut columns) = rows_to_json_with(&rows, max_cell_bytes > 0);
+            if columns.is_empty() {
+                columns = describe_columns(pool, &data_query).await?;
+            }
+            let truncated_cells = apply_truncation(&mut rows, &mut columns, &truncatable);
+            let (total_count, total_count_exact) =
+                counted.unwrap_or_else(|| (uncounted_total(offset, rows.len(), page_size), false));
+            let total_pages = (total_count as f64 / page_size as f64).ceil() as i64;
+
+            return Ok(PaginatedResult {
+                rows,
+                total_count,
+                page,
+                page_size,
+                total_pages,
+                total_count_exact,
+                columns,
+                next_cursor: None,
+                prev_cursor: None,
+                truncated_cells,
+            });
+        }
+
+        // No explicit sort — run PK detection + COUNT concurrently, then SELECT
+        let (pk_result, count_result) = tokio::join!(
+            fetch_primary_key(pool, schema, table),
+            count.run_on(pool, tx.as_deref_mut()),
+        );
+
+        let counted = count_result.map_err(query_error)?;
+        let pk_columns = pk_result.unwrap_or_default();
+
+        // Keyset mode seeks past the cursor row by primary key instead of
+        // scanning skipped rows; tables without a primary key fall back to OFFSET
+        let cursor = cursor.filter(|_| !pk_columns.is_empty());
+        if let Some(cursor) = cursor {
+            if cursor.values.len() != pk_columns.len() {
+                return Err(DbViewerError::InvalidQuery(format!(
+                    "Cursor has {} values but the primary key has {} columns",
+                    cursor.values.len(),
+                    pk_columns.len()
+                )));
+            }
+            let mut placeholders = Vec::with_capacity(pk_columns.len());
+            for (value, (col, ty)) in cursor.values.iter().zip(&pk_columns) {
+                binds.push(BoundValue::Text(cursor_value_text(col, value)?));
+                placeholders.push(format!("${}::{}", binds.len(), ty));
+            }
+            let seek = format!(
+                "({}) {} ({})",
+                pk_columns
+                    .iter()
+                    .map(|(col, _)| quote_identifier(col))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                match cursor.direction {
+                    CursorDirection::Next => ">",
+                    CursorDirection::Prev => "<",
+                },
+                placeholders.join(", ")
+            );
+            where_clause = if where_clause.is_empty() {
+                format!("WHERE {}", seek)
             } else {
-                // Apply mode: execute directly, abort on first error
-                match sqlx::query(trimmed).execute(&mut *tx).await {
-                    Ok(r) => {
-                        let duration = stmt_start.elapsed().as_secs_f64() * 1000.0;
-                        results.push(StatementResult {
-                            sql: trimmed.to_string(),
-                            ok: true,
-                            duration_ms: duration,
-                            rows_affected: Some(r.rows_affected()),
-                            error: None,
-                        });
-                    }
-                    Err(e) => {
-                        let duration = stmt_start.elapsed().as_secs_f64() * 1000.0;
-                        results.push(StatementResult {
-                            sql: trimmed.to_string(),
-                            ok: false,
-                            duration_ms: duration,
-                            rows_affected: None,
-                            error: Some(extract_pg_error(&e)),
-                        });
-                        // Transaction is aborted — drop it (auto-rollback)
-                        return Ok(MigrationResult {
-                            ok: false,
-                            dry_run,
-                            committed: false,
-                            duration_ms: total_start.elapsed().as_secs_f64() * 1000.0,
-                            statements: results,
-                            lock_timeout_ms: lock_timeout,
-                            statement_timeout_ms: stmt_timeout,
-                        });
-                    }
-                }
+                format!("{} AND {}", where_clause, seek)
+            };
+        }
+
+        // Prev pages are read backwards from the cursor, then flipped
+        let descending = matches!(cursor, Some(c) if c.direction == CursorDirection::Prev);
+        let order_clause = if pk_columns.is_empty() {
+            String::new()
+        } else {
+            let dir = if descending { "DESC" } else { "ASC" };
+            let parts: Vec<String> = pk_columns
+                .iter()
+                .map(|(col, _)| format!("{} {}", quote_identifier(col), dir))
+                .collect();
+            format!("ORDER BY {}", parts.join(", "))
+        };
+        let limit_clause = match cursor {
+            Some(_) => format!("LIMIT {}", page_size),
+            None => format!("LIMIT {} OFFSET {}", page_size, offset),
+        };
+
+        let data_query = format!(
+            "SELECT {} FROM {} {} {} {}",
+            select_list, source, where_clause, order_clause, limit_clause
+        );
+        let rows = fetch_page_rows(pool, tx.as_deref_mut(), &data_query, &binds)
+            .await
+            .map_err(query_error)?;
+
+        let (mut rows, mut columns) = rows_to_json_with(&rows, max_cell_bytes > 0);
+        if columns.is_empty() {
+            columns = describe_columns(pool, &data_query).await?;
+        }
+        if descending {
+            rows.reverse();
+        }
+        let truncated_cells = apply_truncation(&mut rows, &mut columns, &truncatable);
+
+        // A full page means there may be more rows past it
+        let full_page = rows.len() as i64 == page_size;
+        let (has_prev, has_next) = match cursor.map(|c| c.direction) {
+            None => (offset > 0, full_page),
+            Some(CursorDirection::Next) => (true, full_page),
+            Some(CursorDirection::Prev) => (full_page, true),
+        };
+        let pk_selected =
+            selected.is_none_or(|cols| pk_columns.iter().all(|(pk, _)| cols.contains(pk)));
+        let (prev_cursor, next_cursor) = if pk_columns.is_empty() || !pk_selected {
+            (None, None)
+        } else {
+            (
+                rows.first()
+                    .filter(|_| has_prev)
+                    .map(|r| PageCursor::from_row(r, &pk_columns, CursorDirection::Prev)),
+                rows.last()
+                    .filter(|_| has_next)
+                    .map(|r| PageCursor::from_row(r, &pk_columns, CursorDirection::Next)),
+            )
+        };
+
+        let (total_count, total_count_exact) =
+            counted.unwrap_or_else(|| (uncounted_total(offset, rows.len(), page_size), false));
+        let total_pages = (total_count as f64 / page_size as f64).ceil() as i64;
+
+        Ok(PaginatedResult {
+            rows,
+            total_count,
+            page,
+            page_size,
+            total_pages,
+            total_count_exact,
+            columns,
+            next_cursor,
+            prev_cursor,
+            truncated_cells,
+        })
+    }
+
+    /// Fetch the page after `cursor` by seeking on the sort key instead of using
+    /// OFFSET, so deep pages cost the same as the first and no COUNT(*) is run.
+    ///
+    /// Rows are ordered by `order_by` (falling back to the primary key), with any
+    /// primary key columns not already listed appended as a tie-breaker. The
+    /// cursor values follow that key column order, with JSON null for a NULL
+    /// key value. NULLs sort as PostgreSQL sorts them by default: after every
+    /// value ascending, before them descending. Only forward reads are supported.
+    pub async fn fetch_keyset(pool: &PgPool, request: &KeysetRequest) -> Result<KeysetPage> {
+        let schema = request.schema.as_str();
+        let table = request.table.as_str();
+        let order_by = request.order_by.as_ref();
+        let filters = request.filters.as_ref();
+        let cursor = request.cursor.as_ref();
+
+        let page_size = request
+            .page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        if matches!(cursor, Some(c) if c.direction == CursorDirection::Prev) {
+            return Err(DbViewerError::InvalidQuery(
+                "Keyset pagination only reads forward".to_string(),
+            ));
+        }
+
+        let (types_result, pk_result) = tokio::join!(
+            fetch_column_types(pool, schema, table),
+            fetch_primary_key(pool, schema, table),
+        );
+        let column_types = types_result?;
+        let pk_columns = pk_result?;
+
+        // Key columns with their type and whether they sort descending
+        let directions = request.order_direction.clone().unwrap_or_default();
+        let mut keys: Vec<(String, String, bool)> = Vec::new();
+        for (i, col) in order_by.into_iter().flatten().enumerate() {
+            let ty = column_types.get(col).ok_or_else(|| {
+                DbViewerError::InvalidQuery(format!("Unknown sort column: {}", col))
+            })?;
+            let descending = directions
+                .get(i)
+                .is_some_and(|d| d.eq_ignore_ascii_case("DESC"));
+            keys.push((col.clone(), ty.clone(), descending));
+        }
+        for (col, ty) in pk_columns {
+            if !keys.iter().any(|(k, _, _)| *k == col) {
+                keys.push((col, ty, false));
             }
         }
+        if keys.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "Keyset pagination needs a sort column or a primary key".to_string(),
+            ));
+        }
 
-        // Finalize: rollback for dry-run, commit for apply
-        let committed = if dry_run {
-            tx.rollback().await.ok();
-            false
+        let filters = filters.filter(|f| !f.is_empty());
+        let (where_clause, mut binds) = filters
+            .map(|f| build_where_clause(f, &column_types))
+            .transpose()?
+            .unwrap_or_default();
+
+        // Rows after the cursor: the first key column past its value, or equal on
+        // it and past on the next, and so on. Spelled out term by term because a
+        // row comparison can't mix ASC and DESC columns, nor compare NULLs.
+        let mut conditions = vec![where_clause.trim_start_matches("WHERE ").to_string()];
+        if let Some(cursor) = cursor {
+            if cursor.values.len() != keys.len() {
+                return Err(DbViewerError::InvalidQuery(format!(
+                    "Cursor has {} values but the sort key has {} columns",
+                    cursor.values.len(),
+                    keys.len()
+                )));
+            }
+            // (equal to the cursor value, past it) for each key column
+            let mut predicates = Vec::with_capacity(keys.len());
+            for (value, (col, ty, descending)) in cursor.values.iter().zip(&keys) {
+                let column = quote_identifier(col);
+                let predicate = if value.is_null() {
+                    // NULLs come last ascending, so nothing is past them, and
+                    // first descending, so every value is
+                    let past = if *descending {
+                        format!("{} IS NOT NULL", column)
+                    } else {
+                        "false".to_string()
+                    };
+                    (format!("{} IS NULL", column), past)
+                } else {
+                    binds.push(BoundValue::Text(cursor_value_text(col, value)?));
+                    let placeholder = format!("${}::{}", binds.len(), ty);
+                    let past = if *descending {
+                        format!("{} < {}", column, placeholder)
+                    } else {
+                        format!("({c} > {p} OR {c} IS NULL)", c = column, p = placeholder)
+                    };
+                    (format!("{} = {}", column, placeholder), past)
+                };
+                predicates.push(predicate);
+            }
+            let terms: Vec<String> = (0..keys.len())
+                .map(|i| {
+                    let mut parts: Vec<&str> = predicates[..i]
+                        .iter()
+                        .map(|(equal, _)| equal.as_str())
+                        .collect();
+                    parts.push(&predicates[i].1);
+                    format!("({})", parts.join(" AND "))
+                })
+                .collect();
+            conditions.push(format!("({})", terms.join(" OR ")));
+        }
+        conditions.retain(|c| !c.is_empty());
+        let where_clause = if conditions.is_empty() {
+            String::new()
         } else {
-            match tx.commit().await {
-                Ok(_) => true,
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let order_clause: Vec<String> = keys
+            .iter()
+            .map(|(col, _, descending)| {
+                format!(
+                    "{} {}",
+                    quote_identifier(col),
+                    if *descending { "DESC" } else { "ASC" }
+                )
+            })
+            .collect();
+        let data_query = format!(
+            "SELECT * FROM {}.{} {} ORDER BY {} LIMIT {}",
+            quote_identifier(schema),
+            quote_identifier(table),
+            where_clause,
+            order_clause.join(", "),
+            page_size
+        );
+        let rows = sqlx::query_with(&data_query, bind_values(&binds)?)
+            .fetch_all(pool)
+            .await
+            .map_err(filter_query_error)?;
+
+        let (rows, mut columns) = rows_to_json(&rows);
+        if columns.is_empty() {
+            columns = describe_columns(pool, &data_query).await?;
+        }
+
+        // A full page means there may be more rows past it
+        let key_columns: Vec<(String, String)> =
+            keys.into_iter().map(|(col, ty, _)| (col, ty)).collect();
+        let next_cursor = rows
+            .last()
+            .filter(|_| rows.len() as i64 == page_size)
+            .map(|r| PageCursor::from_row(r, &key_columns, CursorDirection::Next));
+
+        Ok(KeysetPage {
+            rows,
+            page_size,
+            columns,
+            next_cursor,
+        })
+    }
+
+    /// Stream every row matching `filters` into a CSV file at `path` without
+    /// holding the result set in memory. `on_progress` is called with the running
+    /// row count every `CSV_PROGRESS_INTERVAL` rows. Returns the number of rows written.
+    pub async fn stream_to_csv(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        filters: Option<&Vec<FilterGroup>>,
+        path: &Path,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<u64> {
+        let filters = filters.filter(|f| !f.is_empty());
+        let column_types = match filters {
+            Some(_) => fetch_column_types(pool, schema, table).await?,
+            None => HashMap::new(),
+        };
+        let (where_clause, binds) = filters
+            .map(|f| build_where_clause(f, &column_types))
+            .transpose()?
+            .unwrap_or_default();
+
+        let query = format!(
+            "SELECT * FROM {}.{} {}",
+            quote_identifier(schema),
+            quote_identifier(table),
+            where_clause
+        );
+
+        // Describe the query up front so the header is written even when no rows match
+        let columns = describe_columns(pool, &query).await?;
+
+        let file = tokio::fs::File::create(path)
+            .await
+            .map_err(|e| DbViewerError::Export(format!("Failed to create file: {}", e)))?;
+        let mut writer = BufWriter::new(file);
+        let write_err =
+            |e: std::io::Error| DbViewerError::Export(format!("Failed to write file: {}", e));
+
+        let header: Vec<String> = columns.iter().map(|c| csv_field(&c.name)).collect();
+        writer
+            .write_all(format!("{}\n", header.join(",")).as_bytes())
+            .await
+            .map_err(write_err)?;
+
+        let mut rows = sqlx::query_with(&query, bind_values(&binds)?).fetch(pool);
+        let mut written: u64 = 0;
+        while let Some(row) = rows.try_next().await? {
+            let fields: Vec<String> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, c)| match pg_value_to_json(&row, i, &c.data_type) {
+                    JsonValue::Null => String::new(),
+                    JsonValue::String(s) => csv_field(&s),
+                    other => csv_field(&other.to_string()),
+                })
+                .collect();
+            writer
+                .write_all(format!("{}\n", fields.join(",")).as_bytes())
+                .await
+                .map_err(write_err)?;
+
+            written += 1;
+            if written.is_multiple_of(CSV_PROGRESS_INTERVAL) {
+                on_progress(written);
+            }
+        }
+
+        writer.flush().await.map_err(write_err)?;
+        Ok(written)
+    }
+
+    /// Fetch `select` from the one row of a table matched by `where_clause`,
+    /// refusing a clause that matches no rows or several
+    async fn fetch_unique_row(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        where_clause: &serde_json::Map<String, JsonValue>,
+        select: &str,
+    ) -> Result<PgRow> {
+        if where_clause.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "No where clause provided".to_string(),
+            ));
+        }
+
+        let column_types = fetch_column_types(pool, schema, table).await?;
+
+        // Fetch a second row only to detect an ambiguous where clause
+        let query = format!(
+            "SELECT {} FROM {}.{} WHERE {} LIMIT 2",
+            select,
+            quote_identifier(schema),
+            quote_identifier(table),
+            equality_conditions(where_clause, &column_types)
+        );
+        let mut rows = sqlx::query(&query).fetch_all(pool).await?;
+        match rows.len() {
+            1 => Ok(rows.remove(0)),
+            0 => Err(DbViewerError::InvalidQuery(
+                "No row matches the where clause".to_string(),
+            )),
+            _ => Err(DbViewerError::InvalidQuery(
+                "Where clause matches more than one row".to_string(),
+            )),
+        }
+    }
+
+    /// Write the raw bytes of a bytea cell to `path`, returning the number of
+    /// bytes written. The row is picked by `where_clause`, which must match
+    /// exactly one row.
+    pub async fn download_cell(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        where_clause: &serde_json::Map<String, JsonValue>,
+        column: &str,
+        path: &Path,
+    ) -> Result<u64> {
+        let row =
+            Self::fetch_unique_row(pool, schema, table, where_clause, &quote_identifier(column))
+                .await?;
+
+        let bytes = row
+            .try_get::<Option<Vec<u8>>, _>(0)
+            .map_err(|_| DbViewerError::InvalidQuery(format!("Column {} is not bytea", column)))?
+            .ok_or_else(|| DbViewerError::InvalidQuery(format!("Column {} is NULL", column)))?;
+
+        tokio::fs::write(path, &bytes)
+            .await
+            .map_err(|e| DbViewerError::Export(format!("Failed to write file: {}", e)))?;
+
+        Ok(bytes.len() as u64)
+    }
+
+    /// The complete value of a cell that `fetch_paginated` truncated. The row
+    /// is picked by `where_clause`, which must match exactly one row. bytea
+    /// values come back as full hex; `download_cell` saves the raw bytes.
+    pub async fn fetch_full_cell(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        where_clause: &serde_json::Map<String, JsonValue>,
+        column: &str,
+    ) -> Result<JsonValue> {
+        let column_types = fetch_column_types(pool, schema, table).await?;
+        let col = quote_identifier(column);
+        let select = match column_types.get(column).map(String::as_str) {
+            Some("bytea") => format!("{c}::text AS {c}", c = col),
+            Some(_) => col,
+            None => {
+                return Err(DbViewerError::InvalidQuery(format!(
+                    "Column {} not found in {}",
+                    column, table
+                )))
+            }
+        };
+
+        let row = Self::fetch_unique_row(pool, schema, table, where_clause, &select).await?;
+        let (mut rows, _) = rows_to_json(&[row]);
+        Ok(rows
+            .pop()
+            .and_then(|mut row| row.remove(column))
+            .unwrap_or(JsonValue::Null))
+    }
+
+    /// Fetch the row that `value` in a foreign key `column` points at, or
+    /// `None` if nothing matches
+    pub async fn fetch_referenced_row(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        column: &str,
+        value: &JsonValue,
+    ) -> Result<Option<serde_json::Map<String, JsonValue>>> {
+        let columns = SchemaIntrospector::get_columns(pool, schema, table).await?;
+        let fk = columns
+            .into_iter()
+            .find(|c| c.name == column)
+            .ok_or_else(|| {
+                DbViewerError::InvalidQuery(format!("Column {} not found in {}", column, table))
+            })?
+            .foreign_key_info
+            .ok_or_else(|| {
+                DbViewerError::InvalidQuery(format!("Column {} has no foreign key", column))
+            })?;
+
+        if value.is_null() {
+            return Ok(None);
+        }
+
+        let column_types =
+            fetch_column_types(pool, &fk.referenced_schema, &fk.referenced_table).await?;
+        let mut binds = Vec::new();
+        let query = format!(
+            "SELECT * FROM {}.{} WHERE {} = {} LIMIT 1",
+            quote_identifier(&fk.referenced_schema),
+            quote_identifier(&fk.referenced_table),
+            quote_identifier(&fk.referenced_column),
+            bind_cell(&mut binds, value, column_types.get(&fk.referenced_column))
+        );
+        let row = sqlx::query_with(&query, bind_values(&binds)?)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(row.and_then(|row| rows_to_json(&[row]).0.into_iter().next()))
+    }
+
+    /// For every foreign key pointing at `table`, count the rows that
+    /// reference the row identified by `key` and return a few of them
+    pub async fn get_referencing_rows(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        key: &serde_json::Map<String, JsonValue>,
+    ) -> Result<Vec<ReferencingRows>> {
+        if key.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "No key provided for referencing rows".to_string(),
+            ));
+        }
+
+        let foreign_keys = sqlx::query_as::<_, (String, String, String, Vec<String>, Vec<String>)>(
+            r#"
+            SELECT n.nspname::text, c.relname::text, con.conname::text,
+                   array_agg(a.attname::text ORDER BY k.ord),
+                   array_agg(fa.attname::text ORDER BY k.ord)
+            FROM pg_constraint con
+            JOIN pg_class c ON c.oid = con.conrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            CROSS JOIN LATERAL unnest(con.conkey, con.confkey)
+                WITH ORDINALITY AS k(attnum, fattnum, ord)
+            JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+            JOIN pg_attribute fa ON fa.attrelid = con.confrelid AND fa.attnum = k.fattnum
+            WHERE con.contype = 'f'
+              AND con.confrelid = (quote_ident($1) || '.' || quote_ident($2))::regclass
+            GROUP BY n.nspname, c.relname, con.conname
+            ORDER BY 1, 2, 3
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await?;
+
+        let column_types = fetch_column_types(pool, schema, table).await?;
+        let mut results = Vec::with_capacity(foreign_keys.len());
+        for (ref_schema, ref_table, constraint, columns, referenced_columns) in foreign_keys {
+            let mut referencing = ReferencingRows {
+                schema: ref_schema,
+                table: ref_table,
+                constraint,
+                count: 0,
+                sample_rows: Vec::new(),
+                error: None,
+            };
+
+            let mut binds = Vec::new();
+            let conditions: std::result::Result<Vec<String>, String> = columns
+                .iter()
+                .zip(&referenced_columns)
+                .map(|(col, ref_col)| {
+                    let value = key
+                        .get(ref_col)
+                        .ok_or_else(|| format!("Key does not include column {}", ref_col))?;
+                    Ok(format!(
+                        "{} = {}",
+                        quote_identifier(col),
+                        bind_cell(&mut binds, value, column_types.get(ref_col))
+                    ))
+                })
+                .collect();
+            let conditions = match conditions {
+                Ok(conditions) => conditions.join(" AND "),
                 Err(e) => {
-                    results.push(StatementResult {
-                        sql: "COMMIT".to_string(),
-                        ok: false,
-                        duration_ms: 0.0,
-                        rows_affected: None,
-                        error: Some(extract_pg_error(&e)),
+                    referencing.error = Some(e);
+                    results.push(referencing);
+                    continue;
+                }
+            };
+
+            // The window count covers every match, not just the sampled rows
+            let query = format!(
+                "SELECT t.*, count(*) OVER () AS {} FROM {}.{} t WHERE {} LIMIT {}",
+                quote_identifier(REFERENCING_COUNT_COLUMN),
+                quote_identifier(&referencing.schema),
+                quote_identifier(&referencing.table),
+                conditions,
+                REFERENCING_SAMPLE_LIMIT
+            );
+            let sample = sqlx::query_with(&query, bind_values(&binds)?)
+                .fetch_all(pool)
+                .await
+                .and_then(|rows| {
+                    let count = match rows.first() {
+                        Some(row) => row.try_get(REFERENCING_COUNT_COLUMN)?,
+                        None => 0,
+                    };
+                    Ok((count, rows))
+                });
+            match sample {
+                Ok((count, rows)) => {
+                    referencing.count = count;
+                    referencing.sample_rows = rows_to_json(&rows).0;
+                    for row in &mut referencing.sample_rows {
+                        row.remove(REFERENCING_COUNT_COLUMN);
+                    }
+                }
+                Err(e) => referencing.error = Some(e.to_string()),
+            }
+            results.push(referencing);
+        }
+
+        Ok(results)
+    }
+
+    /// Insert a row into a table, returning it. A row skipped by
+    /// `ON CONFLICT DO NOTHING` comes back as null.
+    pub async fn insert_row(pool: &PgPool, request: InsertRequest) -> Result<JsonValue> {
+        Self::insert_returning(
+            pool,
+            &request.schema,
+            &request.table,
+            &request.data,
+            request.on_conflict.as_ref(),
+        )
+        .await
+    }
+
+    /// Insert a row, or update the existing one when it conflicts on `conflict_columns`
+    pub async fn upsert_row(pool: &PgPool, request: UpsertRequest) -> Result<JsonValue> {
+        // No conflict target is a plain insert
+        let on_conflict = (!request.conflict_columns.is_empty()).then_some(OnConflict {
+            columns: request.conflict_columns,
+            action: ConflictAction::DoUpdate,
+            update_columns: request.update_columns,
+        });
+        Self::insert_row(
+            pool,
+            InsertRequest {
+                schema: request.schema,
+                table: request.table,
+                data: request.data,
+                on_conflict,
+            },
+        )
+        .await
+    }
+
+    /// Run a parameterized `INSERT ... VALUES (...)` with an optional
+    /// `ON CONFLICT` clause and `RETURNING *`, returning the resulting row
+    async fn insert_returning(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        data: &serde_json::Map<String, JsonValue>,
+        on_conflict: Option<&OnConflict>,
+    ) -> Result<JsonValue> {
+        if data.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "No data provided for insert".to_string(),
+            ));
+        }
+
+        let table_columns = SchemaIntrospector::get_columns(pool, schema, table).await?;
+        if table_columns.is_empty() {
+            return Err(DbViewerError::TableNotFound(format!(
+                "{}.{}",
+                schema, table
+            )));
+        }
+        let column_info: HashMap<&str, &ColumnInfo> =
+            table_columns.iter().map(|c| (c.name.as_str(), c)).collect();
+        let suffix = match on_conflict {
+            Some(on_conflict) => on_conflict.clause(data, &column_info)?,
+            None => String::new(),
+        };
+
+        let columns: Vec<&str> = data.keys().map(|s| s.as_str()).collect();
+        let mut bound = 0;
+        let placeholders: Vec<String> = data
+            .iter()
+            .map(|(col, value)| {
+                if is_default_marker(value) {
+                    return "DEFAULT".to_string();
+                }
+                bound += 1;
+                match column_info.get(col.as_str()) {
+                    Some(info) => format!("${}::{}", bound, cast_type(&info.data_type)),
+                    None => format!("${}", bound),
+                }
+            })
+            .collect();
+
+        let target = format!("{}.{}", quote_identifier(schema), quote_identifier(table));
+        let query = if bound == 0 {
+            format!(
+                "INSERT INTO {} DEFAULT VALUES{} RETURNING *",
+                target, suffix
+            )
+        } else {
+            format!(
+                "INSERT INTO {} ({}) VALUES ({}){} RETURNING *",
+                target,
+                columns
+                    .iter()
+                    .map(|c| quote_identifier(c))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                placeholders.join(", "),
+                suffix
+            )
+        };
+
+        let mut q = sqlx::query(&query);
+        for (col, value) in data.iter().filter(|(_, v)| !is_default_marker(v)) {
+            let udt_name = column_info
+                .get(col.as_str())
+                .map(|c| c.udt_name.as_str())
+                .unwrap_or_default();
+            q = bind_json_value(q, value, udt_name);
+        }
+
+        let Some(row) = q.fetch_optional(pool).await? else {
+            return Ok(JsonValue::Null);
+        };
+        let (rows, _) = rows_to_json(&[row]);
+
+        Ok(JsonValue::Object(
+            rows.into_iter().next().unwrap_or_default(),
+        ))
+    }
+
+    /// Insert a copy of the one row matching `where_clause`, returning the
+    /// copy. Identity, generated and sequence-backed columns, and primary
+    /// keys with a default, get fresh values instead of copies. A copied
+    /// value that must be unique fails with `InvalidQuery`.
+    pub async fn duplicate_row(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        where_clause: &serde_json::Map<String, JsonValue>,
+    ) -> Result<JsonValue> {
+        let columns = SchemaIntrospector::get_columns(pool, schema, table).await?;
+        if columns.is_empty() {
+            return Err(DbViewerError::TableNotFound(format!(
+                "{}.{}",
+                schema, table
+            )));
+        }
+        let source = Self::fetch_unique_row(pool, schema, table, where_clause, "*").await?;
+        let mut row = rows_to_json(std::slice::from_ref(&source))
+            .0
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        // Identity columns have no pg_attrdef default, so look them up
+        let generated: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT attname FROM pg_attribute
+            WHERE attrelid = (quote_ident($1) || '.' || quote_ident($2))::regclass
+              AND attnum > 0
+              AND NOT attisdropped
+              AND (attidentity <> '' OR attgenerated <> '')
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await?;
+        for column in &columns {
+            let from_sequence = column
+                .default_value
+                .as_deref()
+                .is_some_and(|d| d.starts_with("nextval("));
+            let keyed_default = column.is_primary_key && column.default_value.is_some();
+            if generated.contains(&column.name) || from_sequence || keyed_default {
+                row.insert(column.name.clone(), serde_json::json!({"$default": true}));
+            }
+        }
+
+        Self::insert_returning(pool, schema, table, &row, None)
+            .await
+            .map_err(|err| match err {
+                DbViewerError::Database(e)
+                    if extract_pg_error(&e).code.as_deref() == Some("23505") =>
+                {
+                    let pg_err = extract_pg_error(&e);
+                    DbViewerError::InvalidQuery(format!(
+                        "The copy would duplicate a unique value: {}",
+                        pg_err.detail.unwrap_or(pg_err.message)
+                    ))
+                }
+                other => other,
+            })
+    }
+
+    /// Bulk insert multiple rows into a table, all or nothing. `on_progress`
+    /// is called after each chunk (or each piece of COPY data) is sent. A
+    /// failing chunk is named in the error along with its row offset.
+    pub async fn bulk_insert(
+        pool: &PgPool,
+        request: BulkInsertRequest,
+        mut on_progress: impl FnMut(BulkInsertProgress),
+    ) -> Result<u64> {
+        if request.rows.is_empty() {
+            return Ok(0);
+        }
+
+        let columns = row_columns(&request.rows);
+        let on_conflict = if request.conflict_target.is_empty() {
+            String::new()
+        } else {
+            for (i, row) in request.rows.iter().enumerate() {
+                if let Some(missing) = request
+                    .conflict_target
+                    .iter()
+                    .find(|col| !row.contains_key(*col))
+                {
+                    return Err(DbViewerError::InvalidQuery(format!(
+                        "Conflict column {} is missing from row {}",
+                        missing, i
+                    )));
+                }
+            }
+            let target: Vec<&str> = request.conflict_target.iter().map(|s| s.as_str()).collect();
+            let columns: Vec<&str> = match request.conflict_action {
+                ConflictAction::DoNothing => Vec::new(),
+                ConflictAction::DoUpdate => columns.clone(),
+            };
+            on_conflict_clause(&target, &columns)
+        };
+
+        // COPY has no ON CONFLICT, and can only write NULL for a missing
+        // value, so only plain inserts can take the fast path
+        let copy_threshold = request.copy_threshold.unwrap_or(BULK_INSERT_COPY_THRESHOLD);
+        let copy_fills_missing = request.missing_values == MissingValue::Null
+            || request.rows.iter().all(|row| row.len() == columns.len());
+        if on_conflict.is_empty() && copy_fills_missing && request.rows.len() >= copy_threshold {
+            return Self::copy_insert(
+                pool,
+                &request.schema,
+                &request.table,
+                &columns,
+                &request.rows,
+                on_progress,
+            )
+            .await;
+        }
+
+        // One INSERT per chunk keeps each statement well under the query size
+        // limit; the transaction keeps the batch all-or-nothing
+        let chunk_size = request.chunk_size.unwrap_or(BULK_INSERT_CHUNK_SIZE).max(1);
+        let total = request.rows.len() as u64;
+        let mut tx = pool.begin().await?;
+        let mut rows_affected = 0;
+        let mut inserted = 0;
+        for (i, chunk) in request.rows.chunks(chunk_size).enumerate() {
+            let query = bulk_insert_query(
+                &request.schema,
+                &request.table,
+                &columns,
+                chunk,
+                request.missing_values,
+                &on_conflict,
+            )?;
+            // Dropping the transaction on error rolls back the earlier chunks
+            rows_affected += tx
+                .execute(query.as_str())
+                .await
+                .map_err(|e| match e {
+                    sqlx::Error::Database(_) => DbViewerError::InvalidQuery(format!(
+                        "Chunk {} (rows {} to {}): {}",
+                        i,
+                        inserted,
+                        inserted + chunk.len() as u64 - 1,
+                        extract_pg_error(&e).message
+                    )),
+                    e => e.into(),
+                })?
+                .rows_affected();
+            inserted += chunk.len() as u64;
+            on_progress(BulkInsertProgress { inserted, total });
+        }
+        tx.commit().await?;
+
+        Ok(rows_affected)
+    }
+
+    /// Stream `rows` into a table's `columns` with `COPY ... FROM STDIN`,
+    /// writing NULL for the columns a row lacks. A row the server rejects is
+    /// named by its index in the error.
+    async fn copy_insert(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        columns: &[&str],
+        rows: &[serde_json::Map<String, JsonValue>],
+        mut on_progress: impl FnMut(BulkInsertProgress),
+    ) -> Result<u64> {
+        if columns.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "No data provided for insert".to_string(),
+            ));
+        }
+        let statement = format!(
+            "COPY {}.{} ({}) FROM STDIN WITH (FORMAT csv)",
+            quote_identifier(schema),
+            quote_identifier(table),
+            columns
+                .iter()
+                .map(|c| quote_identifier(c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let mut conn = pool.acquire().await?;
+        let mut copy = conn.copy_in_raw(&statement).await?;
+        let total = rows.len() as u64;
+        let sent = async {
+            let mut buf = Vec::new();
+            for (i, row) in rows.iter().enumerate() {
+                write_csv_row(&mut buf, columns, row);
+                if buf.len() >= COPY_SEND_BYTES || i + 1 == rows.len() {
+                    copy.send(std::mem::take(&mut buf)).await?;
+                    on_progress(BulkInsertProgress {
+                        inserted: i as u64 + 1,
+                        total,
                     });
-                    all_ok = false;
-                    false
                 }
             }
+            Ok::<_, sqlx::Error>(())
+        }
+        .await;
+
+        // COPY is a single statement, so a failure leaves no rows behind
+        if let Err(e) = sent {
+            let _ = copy.abort("bulk insert failed").await;
+            return Err(copy_row_error(e));
+        }
+        copy.finish().await.map_err(copy_row_error)
+    }
+
+    /// Load a CSV file into a table in batches of `CSV_IMPORT_BATCH_SIZE` rows,
+    /// all in one transaction. Records that can't be parsed, such as ones with
+    /// the wrong number of fields, are reported in `errors` and skipped; a
+    /// database error rolls back the whole import.
+    pub async fn import_csv(pool: &PgPool, request: ImportCsvRequest) -> Result<ImportResult> {
+        let import_err = |e: csv_async::Error| DbViewerError::Import(e.to_string());
+
+        let delimiter = u8::try_from(request.delimiter)
+            .ok()
+            .filter(u8::is_ascii)
+            .ok_or_else(|| {
+                DbViewerError::InvalidQuery(format!(
+                    "Delimiter must be a single ASCII character, got {:?}",
+                    request.delimiter
+                ))
+            })?;
+        let file = tokio::fs::File::open(&request.file_path)
+            .await
+            .map_err(|e| DbViewerError::Import(format!("Failed to open file: {}", e)))?;
+        let mut reader = csv_async::AsyncReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(request.has_header)
+            .create_reader(file);
+
+        let columns: Vec<String> = match &request.columns {
+            Some(columns) => columns.clone(),
+            None if request.has_header => reader
+                .headers()
+                .await
+                .map_err(import_err)?
+                .iter()
+                .map(String::from)
+                .collect(),
+            None => {
+                return Err(DbViewerError::InvalidQuery(
+                    "Column names are required when the file has no header row".to_string(),
+                ))
+            }
+        };
+        if columns.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "No columns to import".to_string(),
+            ));
+        }
+
+        let on_conflict = match request.on_conflict {
+            ConflictStrategy::Fail => String::new(),
+            ConflictStrategy::Skip => " ON CONFLICT DO NOTHING".to_string(),
+            ConflictStrategy::Replace => {
+                let pk_columns = fetch_primary_key(pool, &request.schema, &request.table).await?;
+                if pk_columns.is_empty() {
+                    return Err(DbViewerError::InvalidQuery(format!(
+                        "Replacing conflicting rows needs a primary key on {}.{}",
+                        request.schema, request.table
+                    )));
+                }
+                let target: Vec<&str> = pk_columns.iter().map(|(col, _)| col.as_str()).collect();
+                let columns: Vec<&str> = columns.iter().map(|s| s.as_str()).collect();
+                on_conflict_clause(&target, &columns)
+            }
+        };
+
+        let mut tx = pool.begin().await?;
+        let mut result = ImportResult::default();
+        let mut batch = Vec::with_capacity(CSV_IMPORT_BATCH_SIZE);
+        let mut record = csv_async::StringRecord::new();
+        loop {
+            match reader.read_record(&mut record).await {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) if e.is_io_error() => return Err(import_err(e)),
+                Err(e) => {
+                    result.errors.push(RowError {
+                        line: e.position().map_or(0, |p| p.line()),
+                        message: e.to_string(),
+                    });
+                    result.rows_skipped += 1;
+                    continue;
+                }
+            }
+
+            if record.len() != columns.len() {
+                result.errors.push(RowError {
+                    line: record.position().map_or(0, |p| p.line()),
+                    message: format!("Expected {} fields, found {}", columns.len(), record.len()),
+                });
+                result.rows_skipped += 1;
+                continue;
+            }
+
+            let row: serde_json::Map<String, JsonValue> = columns
+                .iter()
+                .zip(record.iter())
+                .map(|(col, field)| {
+                    let value = if field == request.null_string {
+                        JsonValue::Null
+                    } else {
+                        JsonValue::String(field.to_string())
+                    };
+                    (col.clone(), value)
+                })
+                .collect();
+            batch.push(row);
+
+            if batch.len() == CSV_IMPORT_BATCH_SIZE {
+                Self::import_batch(&mut tx, &request, &batch, &on_conflict, &mut result).await?;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            Self::import_batch(&mut tx, &request, &batch, &on_conflict, &mut result).await?;
+        }
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    async fn import_batch(
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        request: &ImportCsvRequest,
+        batch: &[serde_json::Map<String, JsonValue>],
+        on_conflict: &str,
+        result: &mut ImportResult,
+    ) -> Result<()> {
+        let query = bulk_insert_query(
+            &request.schema,
+            &request.table,
+            &row_columns(batch),
+            batch,
+            MissingValue::Null,
+            on_conflict,
+        )?;
+        let inserted = tx.execute(query.as_str()).await?.rows_affected();
+        result.rows_inserted += inserted;
+        result.rows_skipped += batch.len() as u64 - inserted;
+        Ok(())
+    }
+
+    /// Update the rows matching `where_clause`, and with `returning` fetch
+    /// their new values
+    pub async fn update_row(pool: &PgPool, request: UpdateRequest) -> Result<UpdateResult> {
+        if request.data.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "No data provided for update".to_string(),
+            ));
+        }
+
+        if request.where_clause.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "No where clause provided for update".to_string(),
+            ));
+        }
+
+        // Values are cast to their column types, like the placeholders in insert_row
+        let column_types = fetch_column_types(pool, &request.schema, &request.table).await?;
+
+        let query = format!(
+            "UPDATE {}.{} SET {} WHERE {}",
+            quote_identifier(&request.schema),
+            quote_identifier(&request.table),
+            set_clause(&request.data, &column_types),
+            equality_conditions(&request.where_clause, &column_types)
+        );
+
+        let (rows_affected, rows) = execute_returning(pool, query, request.returning).await?;
+        Ok(UpdateResult {
+            rows_affected,
+            rows,
+        })
+    }
+
+    /// Delete the rows matching `where_clause`, and with `returning` fetch
+    /// what was removed
+    pub async fn delete_row(pool: &PgPool, request: DeleteRequest) -> Result<DeleteResult> {
+        if request.where_clause.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "No where clause provided for delete".to_string(),
+            ));
+        }
+
+        // Cast like update_row, so array and other typed keys match their columns
+        let column_types = fetch_column_types(pool, &request.schema, &request.table).await?;
+        let query = format!(
+            "DELETE FROM {}.{} WHERE {}",
+            quote_identifier(&request.schema),
+            quote_identifier(&request.table),
+            equality_conditions(&request.where_clause, &column_types)
+        );
+
+        let (rows_affected, rows) = execute_returning(pool, query, request.returning).await?;
+        Ok(DeleteResult {
+            rows_affected,
+            rows,
+        })
+    }
+
+    /// Delete every row matching all of `filters`, up to `limit` rows if given
+    pub async fn bulk_delete(pool: &PgPool, request: BulkDeleteRequest) -> Result<u64> {
+        let column_types = fetch_column_types(pool, &request.schema, &request.table).await?;
+        let (where_clause, binds) =
+            filter_where_clause(&request.filters, &column_types, "bulk delete")?;
+
+        let qualified_table = format!(
+            "{}.{}",
+            quote_identifier(&request.schema),
+            quote_identifier(&request.table)
+        );
+        // DELETE has no LIMIT, so cap it by selecting the physical row ids first
+        let query = match request.limit {
+            Some(limit) => format!(
+                "DELETE FROM {} WHERE ctid = ANY(SELECT ctid FROM {} {} LIMIT {})",
+                qualified_table,
+                qualified_table,
+                where_clause,
+                limit.max(0)
+            ),
+            None => format!("DELETE FROM {} {}", qualified_table, where_clause),
+        };
+
+        let result = sqlx::query_with(&query, bind_values(&binds)?)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Set `data` on every row matching all of `filters`, returning how many
+    /// rows changed. `count_matching_rows` previews that number.
+    pub async fn update_by_filter(pool: &PgPool, request: UpdateByFilterRequest) -> Result<u64> {
+        if request.data.is_empty() {
+            return Err(DbViewerError::InvalidQuery(
+                "No data provided for update".to_string(),
+            ));
+        }
+
+        let column_types = fetch_column_types(pool, &request.schema, &request.table).await?;
+        let (where_clause, binds) =
+            filter_where_clause(&request.filters, &column_types, "bulk update")?;
+        let set_clause = set_clause(&request.data, &column_types);
+
+        let query = format!(
+            "UPDATE {}.{} SET {} {}",
+            quote_identifier(&request.schema),
+            quote_identifier(&request.table),
+            set_clause,
+            where_clause
+        );
+        let result = sqlx::query_with(&query, bind_values(&binds)?)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Count the rows matching all of `filters`, so a bulk update or delete
+    /// can be confirmed before it runs
+    pub async fn count_matching_rows(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        filters: &[FilterCondition],
+    ) -> Result<u64> {
+        let column_types = fetch_column_types(pool, schema, table).await?;
+        let (where_clause, binds) = filter_where_clause(filters, &column_types, "count")?;
+        let query = format!(
+            "SELECT count(*) FROM {}.{} {}",
+            quote_identifier(schema),
+            quote_identifier(table),
+            where_clause
+        );
+        let (count,): (i64,) = sqlx::query_as_with(&query, bind_values(&binds)?)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(count as u64)
+    }
+
+    /// Reject a truncate unless `confirmation` names the table as
+    /// `schema.table`, so one can't be run by a stray click
+    pub fn check_truncate_confirmation(
+        schema: &str,
+        table: &str,
+        confirmation: &str,
+    ) -> Result<()> {
+        let expected = format!("{}.{}", schema, table);
+        if confirmation != expected {
+            return Err(DbViewerError::InvalidQuery(format!(
+                "Type {} to confirm truncating the table",
+                expected
+            )));
+        }
+        Ok(())
+    }
+
+    /// Remove every row from a table, returning roughly how many there were.
+    /// With `cascade`, tables with foreign keys to it are truncated too.
+    pub async fn truncate_table(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        cascade: bool,
+    ) -> Result<u64> {
+        let rows = Self::get_row_count_before_truncate(pool, schema, table).await?;
+        let query = format!(
+            "TRUNCATE {}.{}{}",
+            quote_identifier(schema),
+            quote_identifier(table),
+            if cascade { " CASCADE" } else { "" }
+        );
+        sqlx::query(&query).execute(pool).await?;
+        Ok(rows)
+    }
+
+    /// The planner's row estimate, counted exactly for a table that has
+    /// never been analyzed
+    async fn get_row_count_before_truncate(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+    ) -> Result<u64> {
+        let estimate = SchemaIntrospector::get_row_count_estimate(pool, schema, table).await?;
+        Ok(estimate.max(0) as u64)
+    }
+
+    /// Set a sequence's current value, so the next `nextval` returns `value + increment`
+    pub async fn reset_sequence(pool: &PgPool, schema: &str, name: &str, value: i64) -> Result<()> {
+        Self::set_sequence_value(pool, schema, name, value, true).await
+    }
+
+    /// `setval` a sequence. With `is_called` the next `nextval` returns
+    /// `value + increment`, without it `value` itself.
+    pub async fn set_sequence_value(
+        pool: &PgPool,
+        schema: &str,
+        name: &str,
+        value: i64,
+        is_called: bool,
+    ) -> Result<()> {
+        let sequence = format!("{}.{}", quote_identifier(schema), quote_identifier(name));
+        sqlx::query("SELECT setval($1::regclass, $2, $3)")
+            .bind(sequence)
+            .bind(value)
+            .bind(is_called)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Fetch up to `limit` distinct values of a column, optionally restricted to
+    /// values whose text starts with `search`
+    pub async fn distinct_values(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        column: &str,
+        search: Option<&str>,
+        limit: i64,
+    ) -> Result<DistinctValues> {
+        let limit = limit.max(1);
+        let col = quote_identifier(column);
+        let search = search.filter(|s| !s.is_empty());
+        let where_clause = match search {
+            Some(_) => format!("WHERE {}::text ILIKE $2 ESCAPE '\\'", col),
+            None => String::new(),
+        };
+
+        // Ask for one extra row to tell whether the limit cut the list short
+        let query = format!(
+            "SELECT DISTINCT {} FROM {}.{} {} ORDER BY 1 LIMIT $1",
+            col,
+            quote_identifier(schema),
+            quote_identifier(table),
+            where_clause
+        );
+        let mut q = sqlx::query(&query).bind(limit + 1);
+        if let Some(prefix) = search {
+            q = q.bind(format!("{}%", escape_like_pattern(prefix)));
+        }
+        let rows = q.fetch_all(pool).await?;
+
+        let limit_reached = rows.len() as i64 > limit;
+        let (rows, _) = rows_to_json(&rows[..rows.len().min(limit as usize)]);
+        let values = rows
+            .into_iter()
+            .map(|mut row| row.remove(column).unwrap_or(JsonValue::Null))
+            .collect();
+
+        Ok(DistinctValues {
+            values,
+            limit_reached,
+        })
+    }
+
+    /// Null and distinct counts, min/max and most common values of `column`.
+    /// Tables estimated at more than `scan_limit` rows are not scanned; their
+    /// stats come from pg_stats, as of the last ANALYZE.
+    pub async fn column_stats(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        column: &str,
+        scan_limit: Option<i64>,
+    ) -> Result<ColumnStats> {
+        let column_types = fetch_column_types(pool, schema, table).await?;
+        if !column_types.contains_key(column) {
+            return Err(DbViewerError::InvalidQuery(format!(
+                "Column {} not found in {}",
+                column, table
+            )));
+        }
+
+        let qualified_table = format!("{}.{}", quote_identifier(schema), quote_identifier(table));
+        let (estimate,) =
+            sqlx::query_as::<_, (f32,)>("SELECT reltuples FROM pg_class WHERE oid = $1::regclass")
+                .bind(&qualified_table)
+                .fetch_one(pool)
+                .await?;
+        // reltuples is -1 until the table is first vacuumed or analyzed, so
+        // such tables are scanned
+        if estimate > scan_limit.unwrap_or(COLUMN_STATS_SCAN_LIMIT) as f32 {
+            return Self::estimated_column_stats(pool, schema, table, column, estimate as i64)
+                .await;
+        }
+
+        let col = quote_identifier(column);
+        let (row_count, non_null): (i64, i64) = sqlx::query_as(&format!(
+            "SELECT count(*), count({}) FROM {}",
+            col, qualified_table
+        ))
+        .fetch_one(pool)
+        .await?;
+
+        // Types without equality (json) or ordering (point) can't be counted
+        // distinct, ranked or grouped; those stats stay empty
+        let distinct_count = sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT count(DISTINCT {}) FROM {}",
+            col, qualified_table
+        ))
+        .fetch_one(pool)
+        .await
+        .ok();
+        let (min, max) = match sqlx::query(&format!(
+            "SELECT min({c}) AS min, max({c}) AS max FROM {}",
+            qualified_table,
+            c = col
+        ))
+        .fetch_all(pool)
+        .await
+        {
+            Ok(rows) => {
+                let mut row = rows_to_json(&rows).0.pop().unwrap_or_default();
+                (
+                    row.remove("min").unwrap_or(JsonValue::Null),
+                    row.remove("max").unwrap_or(JsonValue::Null),
+                )
+            }
+            Err(_) => (JsonValue::Null, JsonValue::Null),
+        };
+        let most_common = match sqlx::query(&format!(
+            "SELECT {c} AS value, count(*) AS count FROM {} WHERE {c} IS NOT NULL
+             GROUP BY {c} ORDER BY 2 DESC LIMIT {}",
+            qualified_table,
+            COLUMN_STATS_TOP_VALUES,
+            c = col
+        ))
+        .fetch_all(pool)
+        .await
+        {
+            Ok(rows) => rows_to_json(&rows)
+                .0
+                .into_iter()
+                .map(|mut row| CommonValue {
+                    value: row.remove("value").unwrap_or(JsonValue::Null),
+                    count: row.remove("count").and_then(|c| c.as_i64()).unwrap_or(0),
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        Ok(ColumnStats {
+            row_count,
+            null_count: Some(row_count - non_null),
+            distinct_count,
+            min,
+            max,
+            most_common,
+            exact: true,
+        })
+    }
+
+    async fn estimated_column_stats(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        column: &str,
+        row_count: i64,
+    ) -> Result<ColumnStats> {
+        let stats = sqlx::query_as::<
+            _,
+            (
+                f32,
+                f32,
+                Option<Vec<String>>,
+                Option<Vec<f32>>,
+                Option<Vec<String>>,
+            ),
+        >(
+            r#"
+            SELECT null_frac, n_distinct,
+                   most_common_vals::text::text[], most_common_freqs,
+                   histogram_bounds::text::text[]
+            FROM pg_stats
+            WHERE schemaname = $1 AND tablename = $2 AND attname = $3
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .bind(column)
+        .fetch_optional(pool)
+        .await?;
+
+        let Some((null_frac, n_distinct, common_values, common_freqs, histogram)) = stats else {
+            // Never analyzed
+            return Ok(ColumnStats {
+                row_count,
+                null_count: None,
+                distinct_count: None,
+                min: JsonValue::Null,
+                max: JsonValue::Null,
+                most_common: Vec::new(),
+                exact: false,
+            });
+        };
+
+        let rows = row_count as f64;
+        // A negative n_distinct is minus the fraction of rows that are distinct
+        let distinct = if n_distinct < 0.0 {
+            -(n_distinct as f64) * rows
+        } else {
+            n_distinct as f64
+        };
+        let most_common = common_values
+            .unwrap_or_default()
+            .into_iter()
+            .zip(common_freqs.unwrap_or_default())
+            .take(COLUMN_STATS_TOP_VALUES as usize)
+            .map(|(value, freq)| CommonValue {
+                value: JsonValue::String(value),
+                count: (freq as f64 * rows).round() as i64,
+            })
+            .collect();
+        let histogram = histogram.unwrap_or_default();
+        let bound =
+            |value: Option<&String>| value.cloned().map_or(JsonValue::Null, JsonValue::String);
+
+        Ok(ColumnStats {
+            row_count,
+            null_count: Some((null_frac as f64 * rows).round() as i64),
+            distinct_count: Some(distinct.round() as i64),
+            min: bound(histogram.first()),
+            max: bound(histogram.last()),
+            most_common,
+            exact: false,
+        })
+    }
+
+    /// Return the JSON plan for `sql` from `EXPLAIN (FORMAT JSON)`. A mutation
+    /// is refused unless `analyze` is set, which runs it to collect real
+    /// timings inside a transaction that is always rolled back, so it leaves
+    /// no trace; `verbose` adds output columns and schema-qualified names,
+    /// `buffers` adds shared-buffer usage.
+    pub async fn explain_query(
+        pool: &PgPool,
+        sql: &str,
+        analyze: bool,
+        verbose: bool,
+        buffers: bool,
+    ) -> Result<JsonValue> {
+        let sql_trimmed = sql.trim().trim_end_matches(';').trim_end();
+
+        if sql_trimmed.is_empty() {
+            return Err(DbViewerError::InvalidQuery("Empty query".to_string()));
+        }
+        if !analyze && !is_select_query(sql_trimmed) {
+            return Err(DbViewerError::InvalidQuery(
+                "Only queries can be explained without analyze".to_string(),
+            ));
+        }
+
+        let mut options = String::from("FORMAT JSON");
+        if analyze {
+            options.push_str(", ANALYZE");
+        }
+        if verbose {
+            options.push_str(", VERBOSE");
+        }
+        if buffers {
+            options.push_str(", BUFFERS");
+        }
+
+        let query = format!("EXPLAIN ({}) {}", options, sql_trimmed);
+        if !analyze {
+            let (plan,): (JsonValue,) = sqlx::query_as(&query).fetch_one(pool).await?;
+            return Ok(plan);
+        }
+
+        let mut tx = pool.begin().await?;
+        let plan = sqlx::query_as::<_, (JsonValue,)>(&query)
+            .fetch_one(&mut *tx)
+            .await;
+        tx.rollback().await?;
+        Ok(plan?.0)
+    }
+
+    /// Run an ad-hoc query and record it in `history` for `connection_id`.
+    /// On a `read_only` connection the query must be a single statement.
+    pub async fn execute_raw_query(
+        conn: &mut PgConnection,
+        history: Option<&QueryHistoryStore>,
+        connection_id: &str,
+        sql: &str,
+        read_only: bool,
+    ) -> Result<QueryResult> {
+        let start_time = Instant::now();
+        let result = Self::run_raw_query(conn, sql, read_only).await;
+
+        let Some(history) = history.cloned() else {
+            return result;
+        };
+        let (duration_ms, rows_returned, error) = match &result {
+            Ok(r) => (
+                r.execution_time_ms,
+                if r.rows.is_empty() {
+                    r.rows_affected
+                } else {
+                    r.rows.len() as u64
+                },
+                None,
+            ),
+            Err(e) => (start_time.elapsed().as_millis(), 0, Some(e.to_string())),
+        };
+        let connection_id = connection_id.to_string();
+        let sql = sql.trim().to_string();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = history.record_query(
+                &connection_id,
+                &sql,
+                duration_ms,
+                rows_returned,
+                error.as_deref(),
+            ) {
+                log::warn!("Failed to record query history: {}", e);
+            }
+        });
+
+        result
+    }
+
+    async fn run_raw_query(
+        conn: &mut PgConnection,
+        sql: &str,
+        read_only: bool,
+    ) -> Result<QueryResult> {
+        let sql_trimmed = sql.trim();
+
+        if sql_trimmed.is_empty() {
+            return Err(DbViewerError::InvalidQuery("Empty query".to_string()));
+        }
+        if !read_only {
+            return Self::run_statements(conn, sql_trimmed, false).await;
+        }
+
+        // The session is read-only, but a query could turn that off with SET.
+        // It runs in a read-only transaction of its own that is rolled back
+        // afterwards, taking any SET with it, and as a single statement, so
+        // it can't COMMIT that transaction and carry on.
+        conn.execute("BEGIN READ ONLY").await?;
+        let result = Self::run_statements(conn, sql_trimmed, true).await;
+        conn.execute("ROLLBACK").await?;
+        result
+    }
+
+    /// Run `sql`, as a prepared statement when `single`, which rules out
+    /// several statements in one string
+    async fn run_statements(
+        conn: &mut PgConnection,
+        sql_trimmed: &str,
+        single: bool,
+    ) -> Result<QueryResult> {
+        let start_time = std::time::Instant::now();
+
+        // Determine if this is a SELECT query or a mutation
+        if is_select_query(sql_trimmed) {
+            let rows = sqlx::query(sql_trimmed).fetch_all(&mut *conn).await?;
+            let (rows, mut columns) = rows_to_json(&rows);
+            if columns.is_empty() {
+                columns = describe_columns(&mut *conn, sql_trimmed).await?;
+            }
+
+            Ok(QueryResult {
+                rows,
+                columns,
+                rows_affected: 0,
+                execution_time_ms: start_time.elapsed().as_millis(),
+            })
+        } else {
+            let result = if single {
+                sqlx::query(sql_trimmed).execute(&mut *conn).await?
+            } else {
+                conn.execute(sql_trimmed).await?
+            };
+
+            Ok(QueryResult {
+                rows: Vec::new(),
+                columns: Vec::new(),
+                rows_affected: result.rows_affected(),
+                execution_time_ms: start_time.elapsed().as_millis(),
+            })
+        }
+    }
+
+    /// The PID of the server process serving `conn`, as used by `cancel_backend`
+    pub async fn backend_pid(conn: &mut PgConnection) -> Result<i32> {
+        Ok(sqlx::query_scalar("SELECT pg_backend_pid()")
+            .fetch_one(conn)
+            .await?)
+    }
+
+    /// Ask the server to cancel whatever backend `pid` is running. Runs on a
+    /// pool connection other than the busy one. Returns false when no such
+    /// backend exists.
+    pub async fn cancel_backend(pool: &PgPool, pid: i32) -> Result<bool> {
+        Ok(sqlx::query_scalar("SELECT pg_cancel_backend($1)")
+            .bind(pid)
+            .fetch_one(pool)
+            .await?)
+    }
+
+    /// Run `statements` in a single transaction. The first failing statement
+    /// rolls back everything and stops; its error is in the returned results.
+    pub async fn execute_transaction(
+        pool: &PgPool,
+        statements: &[String],
+        timeout_ms: Option<u32>,
+    ) -> Result<TransactionResult> {
+        let stmt_timeout = timeout_ms.unwrap_or(30000);
+        let total_start = Instant::now();
+
+        let mut tx = pool.begin().await?;
+        sqlx::query(&format!("SET LOCAL statement_timeout = '{stmt_timeout}ms'"))
+            .execute(&mut *tx)
+            .await?;
+
+        let mut results: Vec<StatementResult> = Vec::new();
+        for stmt in statements {
+            let trimmed = stmt.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let stmt_start = Instant::now();
+            match sqlx::query(trimmed).execute(&mut *tx).await {
+                Ok(r) => results.push(StatementResult {
+                    sql: trimmed.to_string(),
+                    ok: true,
+                    duration_ms: stmt_start.elapsed().as_secs_f64() * 1000.0,
+                    rows_affected: Some(r.rows_affected()),
+                    error: None,
+                }),
+                Err(e) => {
+                    results.push(StatementResult {
+                        sql: trimmed.to_string(),
+                        ok: false,
+                        duration_ms: stmt_start.elapsed().as_secs_f64() * 1000.0,
+                        rows_affected: None,
+                        error: Some(extract_pg_error(&e)),
+                    });
+                    tx.rollback().await.ok();
+                    return Ok(TransactionResult {
+                        committed: false,
+                        statements: results,
+                        duration_ms: total_start.elapsed().as_secs_f64() * 1000.0,
+                    });
+                }
+            }
+        }
+
+        let committed = match tx.commit().await {
+            Ok(_) => true,
+            Err(e) => {
+                results.push(StatementResult {
+                    sql: "COMMIT".to_string(),
+                    ok: false,
+                    duration_ms: 0.0,
+                    rows_affected: None,
+                    error: Some(extract_pg_error(&e)),
+                });
+                false
+            }
+        };
+
+        Ok(TransactionResult {
+            committed,
+            statements: results,
+            duration_ms: total_start.elapsed().as_secs_f64() * 1000.0,
+        })
+    }
+}
+
+// ============================================================================
+// Migration Operations
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationRequest {
+    pub connection_id: String,
+    pub statements: Vec<String>,
+    pub dry_run: bool,
+    pub lock_timeout_ms: Option<u32>,
+    pub statement_timeout_ms: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementError {
+    pub code: Option<String>,
+    pub message: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementResult {
+    pub sql: String,
+    pub ok: bool,
+    pub duration_ms: f64,
+    pub rows_affected: Option<u64>,
+    pub error: Option<StatementError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationResult {
+    pub ok: bool,
+    pub dry_run: bool,
+    pub committed: bool,
+    pub duration_ms: f64,
+    pub statements: Vec<StatementResult>,
+    pub lock_timeout_ms: u32,
+    pub statement_timeout_ms: u32,
+    /// SQL undoing a committed migration, in the order to run it. Each
+    /// statement's undo is worked out just before it runs, while whatever it
+    /// drops is still in the catalog.
+    #[serde(default)]
+    pub rollback: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRequest {
+    pub connection_id: String,
+    pub statements: Vec<String>,
+    pub timeout_ms: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionResult {
+    pub committed: bool,
+    pub statements: Vec<StatementResult>,
+    pub duration_ms: f64,
+}
+
+pub struct MigrationOperations;
+
+impl MigrationOperations {
+    pub async fn execute_migration(
+        pool: &PgPool,
+        statements: &[String],
+        dry_run: bool,
+        lock_timeout_ms: Option<u32>,
+        statement_timeout_ms: Option<u32>,
+    ) -> Result<MigrationResult> {
+        let lock_timeout = lock_timeout_ms.unwrap_or(5000);
+        let stmt_timeout = statement_timeout_ms.unwrap_or(30000);
+        let total_start = Instant::now();
+
+        // Acquire a connection and begin transaction
+        let mut tx = pool.begin().await?;
+
+        // Set session-local timeouts
+        let setup_sqls = [
+            format!("SET LOCAL lock_timeout = '{lock_timeout}ms'"),
+            format!("SET LOCAL statement_timeout = '{stmt_timeout}ms'"),
+            format!("SET LOCAL idle_in_transaction_session_timeout = '60s'"),
+            "SET LOCAL application_name = 'tusker-migration'".to_string(),
+        ];
+
+        for sql in &setup_sqls {
+            if let Err(e) = sqlx::query(sql).execute(&mut *tx).await {
+                return Ok(MigrationResult {
+                    ok: false,
+                    dry_run,
+                    committed: false,
+                    duration_ms: total_start.elapsed().as_secs_f64() * 1000.0,
+                    statements: vec![StatementResult {
+                        sql: sql.clone(),
+                        ok: false,
+                        duration_ms: 0.0,
+                        rows_affected: None,
+                        error: Some(extract_pg_error(&e)),
+                    }],
+                    lock_timeout_ms: lock_timeout,
+                    statement_timeout_ms: stmt_timeout,
+                    rollback: Vec::new(),
+                });
+            }
+        }
+
+        let mut results: Vec<StatementResult> = Vec::new();
+        let mut rollback = Vec::new();
+        let mut all_ok = true;
+
+        for (i, stmt) in statements.iter().enumerate() {
+            let trimmed = stmt.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let stmt_start = Instant::now();
+
+            if dry_run {
+                // Use savepoints so we can recover from errors and continue
+                // validating subsequent statements. Don't roll back on success —
+                // let effects accumulate so later statements see prior changes
+                // (e.g. RENAME TABLE followed by ALTER on the new name).
+                // The entire transaction is rolled back at the end.
+                let sp_name = format!("s{i}");
+                let _ = sqlx::query(&format!("SAVEPOINT {sp_name}"))
+                    .execute(&mut *tx)
+                    .await;
+
+                match sqlx::query(trimmed).execute(&mut *tx).await {
+                    Ok(r) => {
+                        let duration = stmt_start.elapsed().as_secs_f64() * 1000.0;
+                        results.push(StatementResult {
+                            sql: trimmed.to_string(),
+                            ok: true,
+                            duration_ms: duration,
+                            rows_affected: Some(r.rows_affected()),
+                            error: None,
+                        });
+                    }
+                    Err(e) => {
+                        let duration = stmt_start.elapsed().as_secs_f64() * 1000.0;
+                        all_ok = false;
+                        results.push(StatementResult {
+                            sql: trimmed.to_string(),
+                            ok: false,
+                            duration_ms: duration,
+                            rows_affected: None,
+                            error: Some(extract_pg_error(&e)),
+                        });
+                        // Roll back only on error so the transaction stays usable
+                        let _ = sqlx::query(&format!("ROLLBACK TO SAVEPOINT {sp_name}"))
+                            .execute(&mut *tx)
+                            .await;
+                    }
+                }
+            } else {
+                rollback.extend(rollback_statement(&mut tx, trimmed).await);
+
+                // Apply mode: execute directly, abort on first error
+                match sqlx::query(trimmed).execute(&mut *tx).await {
+                    Ok(r) => {
+                        let duration = stmt_start.elapsed().as_secs_f64() * 1000.0;
+                        results.push(StatementResult {
+                            sql: trimmed.to_string(),
+                            ok: true,
+                            duration_ms: duration,
+                            rows_affected: Some(r.rows_affected()),
+                            error: None,
+                        });
+                    }
+                    Err(e) => {
+                        let duration = stmt_start.elapsed().as_secs_f64() * 1000.0;
+                        results.push(StatementResult {
+                            sql: trimmed.to_string(),
+                            ok: false,
+                            duration_ms: duration,
+                            rows_affected: None,
+                            error: Some(extract_pg_error(&e)),
+                        });
+                        // Transaction is aborted — drop it (auto-rollback)
+                        return Ok(MigrationResult {
+                            ok: false,
+                            dry_run,
+                            committed: false,
+                            duration_ms: total_start.elapsed().as_secs_f64() * 1000.0,
+                            statements: results,
+                            lock_timeout_ms: lock_timeout,
+                            statement_timeout_ms: stmt_timeout,
+                            rollback: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+
+        // Finalize: rollback for dry-run, commit for apply
+        let committed = if dry_run {
+            tx.rollback().await.ok();
+            false
+        } else {
+            match tx.commit().await {
+                Ok(_) => true,
+                Err(e) => {
+                    results.push(StatementResult {
+                        sql: "COMMIT".to_string(),
+                        ok: false,
+                        duration_ms: 0.0,
+                        rows_affected: None,
+                        error: Some(extract_pg_error(&e)),
+                    });
+                    all_ok = false;
+                    false
+                }
+            }
+        };
+
+        if committed {
+            rollback.reverse();
+        } else {
+            rollback.clear();
+        }
+
+        Ok(MigrationResult {
+            ok: all_ok,
+            dry_run,
+            committed,
+            duration_ms: total_start.elapsed().as_secs_f64() * 1000.0,
+            statements: results,
+            lock_timeout_ms: lock_timeout,
+            statement_timeout_ms: stmt_timeout,
+            rollback,
+        })
+    }
+
+    /// Infer SQL that undoes `statements`, in reverse order. Each statement is
+    /// recognised by its prefix, e.g. `CREATE TABLE t` becomes `DROP TABLE t`;
+    /// anything else becomes a `-- MANUAL ROLLBACK REQUIRED` comment, as does
+    /// a CREATE or ADD COLUMN of an object that already exists. Undoing a
+    /// dropped column or index reads its definition from the catalog, so this
+    /// only works before the migration is applied; `execute_migration` returns
+    /// the rollback of what it applied in `MigrationResult::rollback`. A
+    /// dropped column comes back empty: its data is not restored.
+    pub async fn generate_rollback(pool: &PgPool, statements: &[String]) -> Result<Vec<String>> {
+        // Only read from, and rolled back when dropped
+        let mut tx = pool.begin().await?;
+        let mut rollback = Vec::with_capacity(statements.len());
+        for sql in statements {
+            rollback.extend(rollback_statement(&mut tx, sql).await);
+        }
+
+        rollback.reverse();
+        Ok(rollback)
+    }
+}
+
+/// SQL undoing `sql`, read against the catalog as it is before `sql` runs.
+/// `None` when there is no statement to undo. `conn` must be in a
+/// transaction, as the catalog lookups run under a savepoint.
+async fn rollback_statement(conn: &mut PgConnection, sql: &str) -> Option<String> {
+    let sql = strip_leading_comments(sql)
+        .trim()
+        .trim_end_matches(';')
+        .trim_end();
+    if sql.is_empty() {
+        return None;
+    }
+
+    let undo = match infer_rollback(sql) {
+        Rollback::Sql(undo) => Some(undo),
+        Rollback::Manual => None,
+        rollback => catalog_rollback(conn, rollback).await,
+    };
+    Some(undo.unwrap_or_else(|| {
+        format!(
+            "-- MANUAL ROLLBACK REQUIRED: {}",
+            sql.split_whitespace().collect::<Vec<_>>().join(" ")
+        )
+    }))
+}
+
+/// Resolve a rollback that reads the catalog. A lookup can fail, e.g.
+/// `to_regclass` on a cross-database name or a statement timeout, which
+/// would abort the caller's transaction; the savepoint is rolled back to
+/// instead and the statement gets a manual rollback note.
+async fn catalog_rollback(conn: &mut PgConnection, rollback: Rollback) -> Option<String> {
+    sqlx::query(&format!("SAVEPOINT {ROLLBACK_SAVEPOINT}"))
+        .execute(&mut *conn)
+        .await
+        .ok()?;
+
+    let undo = match rollback {
+        // Dropping an object the statement didn't create would lose it
+        Rollback::Drop { undo, created } => created
+            .exists(conn)
+            .await
+            .map(|exists| (!exists).then_some(undo)),
+        Rollback::RestoreColumn { table, column } => {
+            sqlx::query_as::<_, (String, Option<String>, bool)>(
+                "SELECT format_type(a.atttypid, a.atttypmod), pg_get_expr(d.adbin, d.adrelid),
+                        a.attnotnull
+                 FROM pg_attribute a
+                 LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+                 WHERE a.attrelid = to_regclass($1) AND a.attname = $2 AND NOT a.attisdropped",
+            )
+            .bind(&table)
+            .bind(unquote_identifier(&column))
+            .fetch_optional(&mut *conn)
+            .await
+            .map(|found| {
+                found.map(|(data_type, default, not_null)| {
+                    let default = default
+                        .map(|d| format!(" DEFAULT {}", d))
+                        .unwrap_or_default();
+                    let not_null = if not_null { " NOT NULL" } else { "" };
+                    format!(
+                        "ALTER TABLE {} ADD COLUMN {} {}{}{}",
+                        table, column, data_type, default, not_null
+                    )
+                })
+            })
+        }
+        Rollback::RestoreIndex { name } => {
+            sqlx::query_scalar::<_, Option<String>>("SELECT pg_get_indexdef(to_regclass($1))")
+                .bind(&name)
+                .fetch_one(&mut *conn)
+                .await
+        }
+        Rollback::Sql(undo) => Ok(Some(undo)),
+        Rollback::Manual => Ok(None),
+    };
+
+    if undo.is_err() {
+        let _ = sqlx::query(&format!("ROLLBACK TO SAVEPOINT {ROLLBACK_SAVEPOINT}"))
+            .execute(&mut *conn)
+            .await;
+    }
+    let _ = sqlx::query(&format!("RELEASE SAVEPOINT {ROLLBACK_SAVEPOINT}"))
+        .execute(&mut *conn)
+        .await;
+    undo.ok().flatten()
+}
+
+/// How to undo a single migration statement
+#[derive(Debug, PartialEq)]
+enum Rollback {
+    Sql(String),
+    /// Drop what the statement creates, unless it existed beforehand
+    Drop {
+        undo: String,
+        created: Created,
+    },
+    /// Re-add a dropped column from its catalog definition, without its data
+    RestoreColumn {
+        table: String,
+        column: String,
+    },
+    /// Recreate a dropped index from its catalog definition
+    RestoreIndex {
+        name: String,
+    },
+    Manual,
+}
+
+/// An object made by CREATE or ADD COLUMN, as named in the statement
+#[derive(Debug, PartialEq)]
+enum Created {
+    Relation(String),
+    Schema(String),
+    Type(String),
+    Column { table: String, column: String },
+}
+
+impl Created {
+    fn drop(self, undo: String) -> Rollback {
+        Rollback::Drop {
+            undo,
+            created: self,
+        }
+    }
+
+    /// Whether the object is in the catalog
+    async fn exists(&self, conn: &mut PgConnection) -> sqlx::Result<bool> {
+        let query = match self {
+            Created::Relation(name) => {
+                sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL").bind(name)
+            }
+            Created::Schema(name) => {
+                sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_namespace WHERE nspname = $1)")
+                    .bind(unquote_identifier(name))
+            }
+            Created::Type(name) => {
+                sqlx::query_scalar("SELECT to_regtype($1) IS NOT NULL").bind(name)
+            }
+            Created::Column { table, column } => sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM pg_attribute
+                                WHERE attrelid = to_regclass($1) AND attname = $2
+                                  AND NOT attisdropped)",
+            )
+            .bind(table)
+            .bind(unquote_identifier(column)),
+        };
+        query.fetch_one(conn).await
+    }
+}
+
+/// Words of a SQL statement: names, with quoted and dotted parts kept
+/// together, and single punctuation characters
+struct SqlWords<'a> {
+    words: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> SqlWords<'a> {
+    fn new(sql: &'a str) -> Self {
+        let is_name_char = |c: char| c == '_' || c == '$' || c == '.' || c.is_alphanumeric();
+        let mut words = Vec::new();
+        let mut chars = sql.char_indices().peekable();
+        while let Some(&(start, c)) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == '"' || c == '_' || c.is_alphanumeric() {
+                let mut end = start;
+                let mut quoted = false;
+                while let Some(&(i, c)) = chars.peek() {
+                    if c == '"' {
+                        quoted = !quoted;
+                    } else if !quoted && !is_name_char(c) {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                words.push(&sql[start..end]);
+            } else {
+                chars.next();
+                words.push(&sql[start..start + c.len_utf8()]);
+            }
+        }
+        Self { words, pos: 0 }
+    }
+
+    fn peek_is(&self, keyword: &str) -> bool {
+        self.words
+            .get(self.pos)
+            .is_some_and(|w| w.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Consume `keywords` if the next words match them all
+    fn keywords(&mut self, keywords: &[&str]) -> bool {
+        let matches = keywords.iter().enumerate().all(|(i, kw)| {
+            self.words
+                .get(self.pos + i)
+                .is_some_and(|w| w.eq_ignore_ascii_case(kw))
+        });
+        if matches {
+            self.pos += keywords.len();
+        }
+        matches
+    }
+
+    fn name(&mut self) -> Option<&'a str> {
+        let word = *self.words.get(self.pos)?;
+        let first = word.chars().next()?;
+        if first == '"' || first == '_' || first.is_alphanumeric() {
+            self.pos += 1;
+            Some(word)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the remaining words contain a comma outside parentheses
+    fn has_top_level_comma(&self) -> bool {
+        let mut depth = 0;
+        for word in &self.words[self.pos..] {
+            match *word {
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                "," if depth == 0 => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+fn infer_rollback(sql: &str) -> Rollback {
+    let mut w = SqlWords::new(sql);
+    if w.keywords(&["CREATE"]) {
+        let or_replace = w.keywords(&["OR", "REPLACE"]);
+        for modifier in ["UNIQUE", "TEMP", "TEMPORARY", "UNLOGGED"] {
+            w.keywords(&[modifier]);
+        }
+        let kind = if w.keywords(&["TABLE"]) {
+            "TABLE"
+        } else if w.keywords(&["INDEX"]) {
+            w.keywords(&["CONCURRENTLY"]);
+            "INDEX"
+        } else if w.keywords(&["MATERIALIZED", "VIEW"]) {
+            "MATERIALIZED VIEW"
+        } else if w.keywords(&["VIEW"]) && !or_replace {
+            "VIEW"
+        } else if w.keywords(&["SEQUENCE"]) {
+            "SEQUENCE"
+        } else if w.keywords(&["SCHEMA"]) {
+            "SCHEMA"
+        } else if w.keywords(&["TYPE"]) {
+            "TYPE"
+        } else {
+            return Rollback::Manual;
+        };
+        w.keywords(&["IF", "NOT", "EXISTS"]);
+        // An unnamed index gets a generated name we can't know
+        if w.peek_is("ON") {
+            return Rollback::Manual;
+        }
+        let Some(name) = w.name() else {
+            return Rollback::Manual;
+        };
+        // An index lives in its table's schema, which only the table names
+        let mut name = name.to_string();
+        if kind == "INDEX" && w.keywords(&["ON"]) {
+            w.keywords(&["ONLY"]);
+            if let Some((schema, _)) = w.name().and_then(|table| table.rsplit_once('.')) {
+                name = format!("{}.{}", schema, name);
+            }
+        }
+        let created = match kind {
+            "SCHEMA" => Created::Schema(name.clone()),
+            "TYPE" => Created::Type(name.clone()),
+            _ => Created::Relation(name.clone()),
+        };
+        return created.drop(format!("DROP {} {}", kind, name));
+    }
+
+    if w.keywords(&["DROP", "INDEX"]) {
+        w.keywords(&["CONCURRENTLY"]);
+        w.keywords(&["IF", "EXISTS"]);
+        return match w.name() {
+            Some(name) if !w.has_top_level_comma() => Rollback::RestoreIndex {
+                name: name.to_string(),
+            },
+            _ => Rollback::Manual,
+        };
+    }
+
+    if !w.keywords(&["ALTER", "TABLE"]) {
+        return Rollback::Manual;
+    }
+    w.keywords(&["IF", "EXISTS"]);
+    w.keywords(&["ONLY"]);
+    let Some(table) = w.name() else {
+        return Rollback::Manual;
+    };
+    // Several actions in one statement are left to the user
+    if w.has_top_level_comma() {
+        return Rollback::Manual;
+    }
+
+    if w.keywords(&["RENAME", "TO"]) {
+        let Some(new_name) = w.name() else {
+            return Rollback::Manual;
+        };
+        // The renamed table stays in its schema
+        let old_name = table.rsplit_once('.').map_or(table, |(_, name)| name);
+        let schema_prefix = &table[..table.len() - old_name.len()];
+        return Rollback::Sql(format!(
+            "ALTER TABLE {}{} RENAME TO {}",
+            schema_prefix, new_name, old_name
+        ));
+    }
+    if w.keywords(&["RENAME"]) {
+        let kind = if w.keywords(&["CONSTRAINT"]) {
+            "CONSTRAINT"
+        } else {
+            w.keywords(&["COLUMN"]);
+            "COLUMN"
+        };
+        return match (w.name(), w.keywords(&["TO"]), w.name()) {
+            (Some(old), true, Some(new)) => Rollback::Sql(format!(
+                "ALTER TABLE {} RENAME {} {} TO {}",
+                table, kind, new, old
+            )),
+            _ => Rollback::Manual,
+        };
+    }
+    if w.keywords(&["ADD", "CONSTRAINT"]) {
+        return match w.name() {
+            Some(name) => Rollback::Sql(format!("ALTER TABLE {} DROP CONSTRAINT {}", table, name)),
+            None => Rollback::Manual,
+        };
+    }
+    if w.keywords(&["ADD"]) {
+        w.keywords(&["COLUMN"]);
+        w.keywords(&["IF", "NOT", "EXISTS"]);
+        // Unnamed table constraints get generated names
+        if ["PRIMARY", "UNIQUE", "CHECK", "FOREIGN", "EXCLUDE"]
+            .iter()
+            .any(|kw| w.peek_is(kw))
+        {
+            return Rollback::Manual;
+        }
+        return match w.name() {
+            Some(column) => Created::Column {
+                table: table.to_string(),
+                column: column.to_string(),
+            }
+            .drop(format!("ALTER TABLE {} DROP COLUMN {}", table, column)),
+            None => Rollback::Manual,
+        };
+    }
+    if w.keywords(&["DROP"]) && !w.peek_is("CONSTRAINT") {
+        w.keywords(&["COLUMN"]);
+        w.keywords(&["IF", "EXISTS"]);
+        if let Some(column) = w.name() {
+            return Rollback::RestoreColumn {
+                table: table.to_string(),
+                column: column.to_string(),
+            };
+        }
+    }
+
+    Rollback::Manual
+}
+
+/// Inverse of `quote_identifier` for an identifier as written in SQL:
+/// quoted names keep their case, bare names fold to lower case
+fn unquote_identifier(identifier: &str) -> String {
+    match identifier
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+    {
+        Some(inner) => inner.replace("\"\"", "\""),
+        None => identifier.to_lowercase(),
+    }
+}
+
+/// Extract structured error info from a sqlx::Error
+fn extract_pg_error(err: &sqlx::Error) -> StatementError {
+    match err {
+        sqlx::Error::Database(db_err) => {
+            let pg_code = db_err.code().map(|c| c.to_string());
+            let detail = db_err
+                .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
+                .and_then(|pg| pg.detail().map(|s| s.to_string()));
+            let hint = db_err
+                .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
+                .and_then(|pg| pg.hint().map(|s| s.to_string()));
+
+            StatementError {
+                code: pg_code,
+                message: db_err.message().to_string(),
+                detail,
+                hint,
+            }
+        }
+        _ => StatementError {
+            code: None,
+            message: err.to_string(),
+            detail: None,
+            hint: None,
+        },
+    }
+}
+
+/// Run an UPDATE or DELETE, with `returning` appending `RETURNING *` and
+/// collecting the affected rows
+async fn execute_returning(
+    pool: &PgPool,
+    query: String,
+    returning: bool,
+) -> Result<(u64, Vec<serde_json::Map<String, JsonValue>>)> {
+    if !returning {
+        let result = sqlx::query(&query).execute(pool).await?;
+        return Ok((result.rows_affected(), Vec::new()));
+    }
+
+    let affected = sqlx::query(&format!("{} RETURNING *", query))
+        .fetch_all(pool)
+        .await?;
+    let (rows, _) = rows_to_json(&affected);
+    Ok((affected.len() as u64, rows))
+}
+
+/// Build a multi-row INSERT of `columns` for `rows`, writing `missing` for the
+/// columns a row lacks. `suffix` is appended after the VALUES list, e.g. an
+/// ON CONFLICT clause.
+fn bulk_insert_query(
+    schema: &str,
+    table: &str,
+    columns: &[&str],
+    rows: &[serde_json::Map<String, JsonValue>],
+    missing: MissingValue,
+    suffix: &str,
+) -> Result<String> {
+    if columns.is_empty() {
+        return Err(DbViewerError::InvalidQuery(
+            "No data provided for insert".to_string(),
+        ));
+    }
+
+    let column_list = columns
+        .iter()
+        .map(|c| quote_identifier(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let missing = match missing {
+        MissingValue::Null => "NULL",
+        MissingValue::Default => "DEFAULT",
+    };
+
+    // Build VALUES clause for all rows
+    let values_list: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let values: Vec<String> = columns
+                .iter()
+                .map(|col| {
+                    row.get(*col)
+                        .map(json_value_to_sql)
+                        .unwrap_or_else(|| missing.to_string())
+                })
+                .collect();
+            format!("({})", values.join(", "))
+        })
+        .collect();
+
+    Ok(format!(
+        "INSERT INTO {}.{} ({}) VALUES {}{}",
+        quote_identifier(schema),
+        quote_identifier(table),
+        column_list,
+        values_list.join(", "),
+        suffix
+    ))
+}
+
+/// Every key used by any of `rows`, in order of first appearance
+fn row_columns(rows: &[serde_json::Map<String, JsonValue>]) -> Vec<&str> {
+    let mut columns: Vec<&str> = Vec::new();
+    for key in rows.iter().flat_map(|row| row.keys()) {
+        if !columns.contains(&key.as_str()) {
+            columns.push(key);
+        }
+    }
+    columns
+}
+
+/// Append `row` to `buf` as a line of `COPY ... WITH (FORMAT csv)` data.
+/// NULL is an unquoted empty field and every other value is quoted, so empty
+/// strings and embedded quotes, commas and newlines survive. JSON objects and
+/// arrays are written as JSON text.
+fn write_csv_row(buf: &mut Vec<u8>, columns: &[&str], row: &serde_json::Map<String, JsonValue>) {
+    for (i, col) in columns.iter().enumerate() {
+        if i > 0 {
+            buf.push(b',');
+        }
+        let text = match row.get(*col) {
+            None | Some(JsonValue::Null) => continue,
+            Some(JsonValue::String(s)) => s.replace('"', "\"\""),
+            Some(value) => value.to_string().replace('"', "\"\""),
+        };
+        buf.push(b'"');
+        buf.extend_from_slice(text.as_bytes());
+        buf.push(b'"');
+    }
+    buf.push(b'\n');
+}
+
+/// Name the row a failed COPY stopped at, from the `line N` the server
+/// reports in the error context. Line 1 is row 0.
+fn copy_row_error(err: sqlx::Error) -> DbViewerError {
+    let line = match &err {
+        sqlx::Error::Database(db_err) => db_err
+            .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
+            .and_then(|pg| pg.r#where())
+            .and_then(|context| {
+                let rest = &context[context.find(", line ")? + ", line ".len()..];
+                let digits = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                rest[..digits].parse::<usize>().ok()
+            }),
+        _ => None,
+    };
+    match line {
+        Some(line) if line > 0 => DbViewerError::InvalidQuery(format!(
+            "Row {}: {}",
+            line - 1,
+            extract_pg_error(&err).message
+        )),
+        _ => err.into(),
+    }
+}
+
+/// ` ON CONFLICT (target) DO UPDATE` setting every column outside `target` to
+/// its `EXCLUDED` value, or `DO NOTHING` when there is no other column
+fn on_conflict_clause(target: &[&str], columns: &[&str]) -> String {
+    let target_list = target
+        .iter()
+        .map(|c| quote_identifier(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let updates: Vec<String> = columns
+        .iter()
+        .filter(|col| !target.contains(col))
+        .map(|col| format!("{0} = EXCLUDED.{0}", quote_identifier(col)))
+        .collect();
+    if updates.is_empty() {
+        format!(" ON CONFLICT ({}) DO NOTHING", target_list)
+    } else {
+        format!(
+            " ON CONFLICT ({}) DO UPDATE SET {}",
+            target_list,
+            updates.join(", ")
+        )
+    }
+}
+
+/// Surface an invalid filter regex (SQLSTATE 2201B) as a query error
+/// instead of a generic database failure
+fn filter_query_error(err: impl Into<DbViewerError>) -> DbViewerError {
+    match err.into() {
+        DbViewerError::Database(e) => {
+            let pg_err = extract_pg_error(&e);
+            if pg_err.code.as_deref() == Some("2201B") {
+                DbViewerError::InvalidQuery(format!(
+                    "Invalid regular expression: {}",
+                    pg_err.message
+                ))
+            } else {
+                DbViewerError::Database(e)
+            }
+        }
+        other => other,
+    }
+}
+
+/// `filter_query_error`, plus a cancellation (SQLSTATE 57014) reported as
+/// `Cancelled` when the caller `cancelled` the query, else as `QueryTimeout`
+/// when it ran under a `timeout_ms` statement timeout
+fn page_query_error(err: DbViewerError, timeout_ms: Option<u32>, cancelled: bool) -> DbViewerError {
+    if let DbViewerError::Database(e) = &err {
+        let pg_err = extract_pg_error(e);
+        if pg_err.code.as_deref() == Some("57014") {
+            return match timeout_ms {
+                Some(timeout_ms) if !cancelled => {
+                    let after = if timeout_ms % 1000 == 0 {
+                        format!("{}s", timeout_ms / 1000)
+                    } else {
+                        format!("{}ms", timeout_ms)
+                    };
+                    DbViewerError::QueryTimeout(after)
+                }
+                _ => DbViewerError::Cancelled,
+            };
+        }
+    }
+    filter_query_error(err)
+}
+
+/// Column names and types of a query without running it, for results with no
+/// rows to read them from
+async fn describe_columns<'e>(
+    executor: impl Executor<'e, Database = Postgres>,
+    sql: &'e str,
+) -> Result<Vec<ColumnMeta>> {
+    let described = executor.describe(sql).await?;
+    Ok(described
+        .columns()
+        .iter()
+        .map(|col| ColumnMeta {
+            name: col.name().to_string(),
+            data_type: col.type_info().name().to_string(),
+        })
+        .collect())
+}
+
+/// Convert PostgreSQL rows to JSON
+fn rows_to_json(rows: &[PgRow]) -> (Vec<serde_json::Map<String, JsonValue>>, Vec<ColumnMeta>) {
+    rows_to_json_with(rows, false)
+}
+
+/// `rows_to_json`, with bytea values cut by `bytea_preview` when
+/// `bytea_previews` is set. Only the grid asks for previews; everything else
+/// needs the full value.
+fn rows_to_json_with(
+    rows: &[PgRow],
+    bytea_previews: bool,
+) -> (Vec<serde_json::Map<String, JsonValue>>, Vec<ColumnMeta>) {
+    if rows.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    let columns: Vec<ColumnMeta> = rows[0]
+        .columns()
+        .iter()
+        .map(|col| ColumnMeta {
+            name: col.name().to_string(),
+            data_type: col.type_info().name().to_string(),
+        })
+        .collect();
+
+    let json_rows: Vec<serde_json::Map<String, JsonValue>> = rows
+        .iter()
+        .map(|row| {
+            let mut map = serde_json::Map::new();
+            for (i, col) in row.columns().iter().enumerate() {
+                let value = match col.type_info().name() {
+                    "BYTEA" if bytea_previews => bytea_preview(row, i),
+                    type_name => pg_value_to_json(row, i, type_name),
+                };
+                map.insert(col.name().to_string(), value);
+            }
+            map
+        })
+        .collect();
+
+    (json_rows, columns)
+}
+
+/// Hex-encode a bytea value, cut to `BYTEA_PREVIEW_BYTES` plus its full length
+/// when longer, e.g. `\x89504e47… (20480 bytes)`. The full value is available
+/// through `DataOperations::download_cell`.
+fn bytea_preview(row: &PgRow, idx: usize) -> JsonValue {
+    match row.try_get::<Option<Vec<u8>>, _>(idx).ok().flatten() {
+        Some(v) if v.len() > BYTEA_PREVIEW_BYTES => JsonValue::String(format!(
+            "\\x{}… ({} bytes)",
+            hex::encode(&v[..BYTEA_PREVIEW_BYTES]),
+            v.len()
+        )),
+        Some(v) => JsonValue::String(format!("\\x{}", hex::encode(v))),
+        None => JsonValue::Null,
+    }
+}
+
+/// Decode a NUMERIC as a JSON string so precision survives the trip through JSON.
+/// The wire value's display scale is reapplied, so `12.50` stays `12.50`.
+fn numeric_to_json(row: &PgRow, idx: usize) -> JsonValue {
+    let value = match row.try_get::<Option<BigDecimal>, _>(idx) {
+        Ok(Some(value)) => value,
+        Ok(None) => return JsonValue::Null,
+        // BigDecimal has no NaN or infinity; read them from the sign field
+        Err(_) => {
+            let sign = row
+                .try_get_raw(idx)
+                .ok()
+                .and_then(|raw| raw.as_bytes().ok().and_then(|b| b.get(4..6)))
+                .map(|d| u16::from_be_bytes([d[0], d[1]]));
+            return match sign {
+                Some(0xC000) => JsonValue::String("NaN".to_string()),
+                Some(0xD000) => JsonValue::String("Infinity".to_string()),
+                Some(0xF000) => JsonValue::String("-Infinity".to_string()),
+                _ => JsonValue::Null,
+            };
+        }
+    };
+    // Binary NUMERIC header: ndigits, weight, sign, dscale (all 16-bit)
+    let dscale = row
+        .try_get_raw(idx)
+        .ok()
+        .and_then(|raw| raw.as_bytes().ok().and_then(|b| b.get(6..8)))
+        .map(|d| u16::from_be_bytes([d[0], d[1]]));
+    let value = match dscale {
+        Some(scale) => value.with_scale(scale as i64),
+        None => value,
+    };
+    JsonValue::String(value.to_plain_string())
+}
+
+/// Render an interval the way PostgreSQL prints it with the default
+/// `IntervalStyle = postgres`, e.g. `1 year 2 mons -3 days +04:05:06.5`.
+/// Once a field is negative, following positive fields get an explicit `+`.
+fn format_interval(interval: &PgInterval) -> String {
+    let years = interval.months / 12;
+    let months = interval.months % 12;
+    let mut out = String::new();
+    let mut is_before = false;
+
+    for (value, unit) in [(years, "year"), (months, "mon"), (interval.days, "day")] {
+        if value == 0 {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        let sign = if is_before && value > 0 { "+" } else { "" };
+        let plural = if value != 1 { "s" } else { "" };
+        out.push_str(&format!("{}{} {}{}", sign, value, unit, plural));
+        is_before = value < 0;
+    }
+
+    let micros = interval.microseconds;
+    if out.is_empty() || micros != 0 {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        let sign = if micros < 0 {
+            "-"
+        } else if is_before {
+            "+"
+        } else {
+            ""
+        };
+        let abs = micros.unsigned_abs();
+        let (secs, frac) = (abs / 1_000_000, abs % 1_000_000);
+        out.push_str(&format!(
+            "{}{:02}:{:02}:{:02}",
+            sign,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        ));
+        if frac != 0 {
+            let frac = format!("{:06}", frac);
+            out.push('.');
+            out.push_str(frac.trim_end_matches('0'));
+        }
+    }
+
+    out
+}
+
+/// Render the binary wire form of an `inet`/`cidr` value as PostgreSQL's text:
+/// family, prefix bits, is-cidr flag, address length, then the address bytes.
+/// The prefix is omitted for `inet` host addresses, as the server does.
+fn inet_to_string(bytes: &[u8]) -> Option<String> {
+    let [family, bits, is_cidr, len, addr @ ..] = bytes else {
+        return None;
+    };
+    let (ip, max_bits) = match (*family, *len, addr.len()) {
+        // PGSQL_AF_INET
+        (2, 4, 4) => (IpAddr::from(<[u8; 4]>::try_from(addr).ok()?), 32),
+        // PGSQL_AF_INET6
+        (3, 16, 16) => (IpAddr::from(<[u8; 16]>::try_from(addr).ok()?), 128),
+        _ => return None,
+    };
+    if *is_cidr == 0 && *bits == max_bits {
+        Some(ip.to_string())
+    } else {
+        Some(format!("{}/{}", ip, bits))
+    }
+}
+
+/// Decode a one-dimensional array column into a JSON array, keeping NULL elements
+fn array_to_json<T>(row: &PgRow, idx: usize, to_json: impl Fn(T) -> JsonValue) -> JsonValue
+where
+    T: for<'r> sqlx::Decode<'r, Postgres> + sqlx::Type<Postgres> + PgHasArrayType,
+{
+    row.try_get::<Option<Vec<Option<T>>>, _>(idx)
+        .ok()
+        .flatten()
+        .map(|items| {
+            JsonValue::Array(
+                items
+                    .into_iter()
+                    .map(|item| item.map(&to_json).unwrap_or(JsonValue::Null))
+                    .collect(),
+            )
+        })
+        .unwrap_or(JsonValue::Null)
+}
+
+/// Convert a PostgreSQL value to JSON
+fn pg_value_to_json(row: &PgRow, idx: usize, type_name: &str) -> JsonValue {
+    // Try to get the value based on the type
+    match type_name {
+        "BOOL" => row
+            .try_get::<Option<bool>, _>(idx)
+            .ok()
+            .flatten()
+            .map(JsonValue::Bool)
+            .unwrap_or(JsonValue::Null),
+
+        "INT2" => row
+            .try_get::<Option<i16>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::Number(v.into()))
+            .unwrap_or(JsonValue::Null),
+
+        "INT4" => row
+            .try_get::<Option<i32>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::Number(v.into()))
+            .unwrap_or(JsonValue::Null),
+
+        "INT8" => row
+            .try_get::<Option<i64>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::Number(v.into()))
+            .unwrap_or(JsonValue::Null),
+
+        "FLOAT4" => row
+            .try_get::<Option<f32>, _>(idx)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::Number::from_f64(v as f64))
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+
+        "FLOAT8" => row
+            .try_get::<Option<f64>, _>(idx)
+            .ok()
+            .flatten()
+            .and_then(serde_json::Number::from_f64)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+
+        "JSON" | "JSONB" => row
+            .try_get::<Option<JsonValue>, _>(idx)
+            .ok()
+            .flatten()
+            .unwrap_or(JsonValue::Null),
+
+        "NUMERIC" => numeric_to_json(row, idx),
+
+        // Without the currency symbol and group separators of its text form,
+        // assuming the two fractional digits of most `lc_monetary` locales
+        "MONEY" => row
+            .try_get::<Option<PgMoney>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::String(v.to_bigdecimal(2).to_plain_string()))
+            .unwrap_or(JsonValue::Null),
+
+        "INTERVAL" => row
+            .try_get::<Option<PgInterval>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::String(format_interval(&v)))
+            .unwrap_or(JsonValue::Null),
+
+        "INET" | "CIDR" => row
+            .try_get_raw(idx)
+            .ok()
+            .and_then(|raw| raw.as_bytes().ok().and_then(inet_to_string))
+            .map(JsonValue::String)
+            .unwrap_or(JsonValue::Null),
+
+        "MACADDR" | "MACADDR8" => row
+            .try_get_raw(idx)
+            .ok()
+            .and_then(|raw| raw.as_bytes().ok())
+            .map(|bytes| {
+                let octets: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                JsonValue::String(octets.join(":"))
+            })
+            .unwrap_or(JsonValue::Null),
+
+        "INT2[]" => array_to_json::<i16>(row, idx, |v| JsonValue::Number(v.into())),
+        "INT4[]" => array_to_json::<i32>(row, idx, |v| JsonValue::Number(v.into())),
+        "INT8[]" => array_to_json::<i64>(row, idx, |v| JsonValue::Number(v.into())),
+        "FLOAT4[]" => array_to_json::<f32>(row, idx, |v| {
+            serde_json::Number::from_f64(v as f64)
+                .map(JsonValue::Number)
+                .unwrap_or(JsonValue::Null)
+        }),
+        "FLOAT8[]" => array_to_json::<f64>(row, idx, |v| {
+            serde_json::Number::from_f64(v)
+                .map(JsonValue::Number)
+                .unwrap_or(JsonValue::Null)
+        }),
+        "BOOL[]" => array_to_json::<bool>(row, idx, JsonValue::Bool),
+        "TEXT[]" | "VARCHAR[]" | "BPCHAR[]" | "NAME[]" => {
+            array_to_json::<String>(row, idx, JsonValue::String)
+        }
+        "UUID[]" => array_to_json::<uuid::Uuid>(row, idx, |v| JsonValue::String(v.to_string())),
+
+        "UUID" => row
+            .try_get::<Option<uuid::Uuid>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::String(v.to_string()))
+            .unwrap_or(JsonValue::Null),
+
+        "BYTEA" => row
+            .try_get::<Option<Vec<u8>>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::String(format!("\\x{}", hex::encode(v))))
+            .unwrap_or(JsonValue::Null),
+
+        "TIMESTAMPTZ" => row
+            .try_get::<Option<chrono::DateTime<chrono::Utc>>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::String(v.to_rfc3339()))
+            .unwrap_or(JsonValue::Null),
+
+        "TIMESTAMP" => row
+            .try_get::<Option<chrono::NaiveDateTime>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::String(v.to_string()))
+            .unwrap_or(JsonValue::Null),
+
+        "DATE" => row
+            .try_get::<Option<chrono::NaiveDate>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::String(v.to_string()))
+            .unwrap_or(JsonValue::Null),
+
+        "TIME" => row
+            .try_get::<Option<chrono::NaiveTime>, _>(idx)
+            .ok()
+            .flatten()
+            .map(|v| JsonValue::String(v.to_string()))
+            .unwrap_or(JsonValue::Null),
+
+        _ => {
+            // Try to get as string first
+            if let Ok(Some(s)) = row.try_get::<Option<String>, _>(idx) {
+                return JsonValue::String(s);
+            }
+
+            // For enum types and other USER-DEFINED types, try to get raw value
+            // PostgreSQL enum values are stored as strings but SQLx might not decode them directly
+            use sqlx::Row as _;
+            if let Ok(value_ref) = row.try_get_raw(idx) {
+                use sqlx::ValueRef;
+                if value_ref.is_null() {
+                    return JsonValue::Null;
+                }
+                // Try to decode as string from the raw bytes
+                use sqlx::Decode;
+                if let Ok(s) = <String as Decode<sqlx::Postgres>>::decode(value_ref) {
+                    return JsonValue::String(s);
+                }
+            }
+
+            JsonValue::Null
+        }
+    }
+}
+
+/// Convert a JSON value to a SQL string (with proper escaping)
+fn json_value_to_sql(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Null => "NULL".to_string(),
+        JsonValue::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        JsonValue::Number(n) => n.to_string(),
+        JsonValue::String(s) => format!("'{}'", escape_sql_string(s)),
+        JsonValue::Array(_) | JsonValue::Object(_) => {
+            format!("'{}'::jsonb", escape_sql_string(&value.to_string()))
+        }
+    }
+}
+
+/// Render a value for a column of SQL type `column_type`. Strings are cast to
+/// the column type, so text forms of intervals, network addresses and the like
+/// parse as those types. Decimal strings for number columns, as NUMERIC
+/// values are read, are written as bare numeric constants. JSON arrays become
+/// Postgres array literals for array columns and jsonb otherwise.
+fn column_value_to_sql(value: &JsonValue, column_type: Option<&String>) -> String {
+    match (value, column_type) {
+        (JsonValue::Array(items), Some(ty)) if ty.ends_with("[]") => {
+            format!(
+                "'{}'::{}",
+                escape_sql_string(&pg_array_literal(items)),
+                cast_type(ty)
+            )
+        }
+        (JsonValue::String(s), Some(ty)) if is_number_literal(s, ty) => s.clone(),
+        (JsonValue::String(_), Some(ty)) => {
+            format!("{}::{}", json_value_to_sql(value), cast_type(ty))
+        }
+        _ => json_value_to_sql(value),
+    }
+}
+
+/// `a = ... AND b = ...` matching the values in `where_clause`, each cast to
+/// its column type from `column_types`
+fn equality_conditions(
+    where_clause: &serde_json::Map<String, JsonValue>,
+    column_types: &HashMap<String, String>,
+) -> String {
+    where_clause
+        .iter()
+        .map(|(col, val)| {
+            format!(
+                "{} = {}",
+                quote_identifier(col),
+                column_value_to_sql(val, column_types.get(col))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// Whether `value` is `{"$default": true}`, which stands for a column's
+/// DEFAULT in inserted or updated data
+fn is_default_marker(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(map) => {
+            map.len() == 1 && map.get("$default") == Some(&JsonValue::Bool(true))
+        }
+        _ => false,
+    }
+}
+
+/// `a = ..., b = ...` assigning `data` to columns of `column_types`
+fn set_clause(
+    data: &serde_json::Map<String, JsonValue>,
+    column_types: &HashMap<String, String>,
+) -> String {
+    data.iter()
+        .map(|(col, val)| {
+            let value = if is_default_marker(val) {
+                "DEFAULT".to_string()
+            } else {
+                column_value_to_sql(val, column_types.get(col))
+            };
+            format!("{} = {}", quote_identifier(col), value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether `s` can be written unquoted for a column of SQL type `ty`. Integer
+/// columns only take whole numbers, as a bare `1.5` would be rounded where the
+/// quoted form is an error.
+fn is_number_literal(s: &str, ty: &str) -> bool {
+    match base_type(ty).as_str() {
+        "numeric" | "real" | "double precision" => is_decimal_literal(s),
+        "smallint" | "integer" | "bigint" => is_decimal_literal(s) && !s.contains('.'),
+        _ => false,
+    }
+}
+
+/// `ty` without its type modifier, e.g. `numeric` for `numeric(10,2)` and
+/// `timestamp without time zone` for `timestamp(3) without time zone`
+fn base_type(ty: &str) -> String {
+    match (ty.find('('), ty.find(')')) {
+        (Some(open), Some(close)) if open < close => {
+            format!("{}{}", &ty[..open], &ty[close + 1..])
+        }
+        _ => ty.to_string(),
+    }
+}
+
+/// The type a value is cast to for a column of SQL type `ty`. An explicit
+/// cast to a length-limited type cuts longer values short, so `abcd` would
+/// match `abc` in a `varchar(3)` column, or be stored as `abc`, where
+/// assignment rejects it. Lengths are dropped instead, with `bpchar` and
+/// `bit varying` for `character` and `bit`, which alone mean length one.
+fn cast_type(ty: &str) -> String {
+    let base = base_type(ty);
+    let (element, dims) = base.split_at(base.find("[]").unwrap_or(base.len()));
+    let element = match element {
+        "character" => "bpchar",
+        "bit" => "bit varying",
+        other => other,
+    };
+    format!("{}{}", element, dims)
+}
+
+/// Whether `s` is a plain decimal such as `-12.50`, safe to put in SQL
+/// unquoted
+fn is_decimal_literal(s: &str) -> bool {
+    let unsigned = s.strip_prefix('-').unwrap_or(s);
+    let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    !(int.is_empty() && frac.is_empty())
+        && int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+}
+
+/// Format JSON array items as a Postgres array literal, e.g. `{"a","b c",NULL}`.
+/// Nested arrays become multi-dimensional arrays.
+pub fn pg_array_literal(items: &[JsonValue]) -> String {
+    let elements: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+            JsonValue::Null => "NULL".to_string(),
+            JsonValue::Array(inner) => pg_array_literal(inner),
+            JsonValue::String(s) => quote_array_element(s),
+            other => quote_array_element(&other.to_string()),
+        })
+        .collect();
+    format!("{{{}}}", elements.join(","))
+}
+
+fn quote_array_element(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Bind a JSON value for a column of type `udt_name`. The placeholder is cast to
+/// the column type, so anything without a native binding is sent as text and
+/// parsed by the server.
+fn bind_json_value<'q>(
+    query: Query<'q, Postgres, PgArguments>,
+    value: &JsonValue,
+    udt_name: &str,
+) -> Query<'q, Postgres, PgArguments> {
+    match (value, udt_name) {
+        (JsonValue::Null, _) => query.bind(None::<String>),
+        (_, "json" | "jsonb") => query.bind(Json(value.clone())),
+        (JsonValue::Bool(b), "bool") => query.bind(*b),
+        (JsonValue::Number(n), "int2" | "int4" | "int8") if n.is_i64() => query.bind(n.as_i64()),
+        (JsonValue::Number(n), "float4" | "float8") if n.is_f64() => query.bind(n.as_f64()),
+        (JsonValue::Array(items), udt) if udt.starts_with('_') => {
+            query.bind(pg_array_literal(items))
+        }
+        (JsonValue::String(s), _) => query.bind(s.clone()),
+        _ => query.bind(value.to_string()),
+    }
+}
+
+/// Whether a statement returns rows, judging by its leading keyword
+fn is_select_query(sql: &str) -> bool {
+    let sql_upper = strip_leading_comments(sql).to_uppercase();
+    sql_upper.starts_with("SELECT")
+        || sql_upper.starts_with("WITH")
+        || sql_upper.starts_with("EXPLAIN")
+        || sql_upper.starts_with("SHOW")
+        || sql_upper.starts_with("TABLE")
+        || sql_upper.starts_with("VALUES")
+}
+
+/// Skip leading whitespace, `--` line comments and (nested) `/* */` block comments
+fn strip_leading_comments(sql: &str) -> &str {
+    let mut rest = sql.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            rest = after.find('\n').map_or("", |i| &after[i + 1..]);
+        } else if rest.starts_with("/*") {
+            let mut depth = 0;
+            let mut end = rest.len();
+            let bytes = rest.as_bytes();
+            let mut i = 0;
+            while i + 1 < bytes.len() {
+                match &bytes[i..i + 2] {
+                    b"/*" => {
+                        depth += 1;
+                        i += 2;
+                    }
+                    b"*/" => {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            end = i;
+                            break;
+                        }
+                    }
+                    _ => i += 1,
+                }
+            }
+            rest = &rest[end..];
+        } else {
+            return rest;
+        }
+        rest = rest.trim_start();
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape a string for SQL (prevent SQL injection)
+fn escape_sql_string(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+/// Functions allowed in an `OrderSpec::Expression`
+const ORDER_FUNCTIONS: &[&str] = &[
+    "abs",
+    "btrim",
+    "char_length",
+    "coalesce",
+    "date_part",
+    "date_trunc",
+    "greatest",
+    "jsonb_extract_path_text",
+    "least",
+    "length",
+    "lower",
+    "ltrim",
+    "nullif",
+    "round",
+    "rtrim",
+    "upper",
+];
+
+/// Operators and punctuation allowed in an `OrderSpec::Expression`, longest
+/// first so `->>` is not read as `->`
+const ORDER_OPERATORS: &[&str] = &[
+    "->>", "#>>", "::", "->", "#>", "||", "<>", "!=", "<=", ">=", "+", "-", "*", "/", "%", "=",
+    "<", ">", "(", ")", ",",
+];
+
+/// Check an `OrderSpec::Expression` token by token. Identifiers must be
+/// columns of the table (`columns`), functions from `ORDER_FUNCTIONS` or type
+/// names after `::`; string literals, numbers and `ORDER_OPERATORS` are the
+/// only other tokens. Rejects the first token outside that grammar, which
+/// also rules out semicolons, comments, parameters and subqueries.
+fn validate_order_expression(expression: &str, columns: &HashMap<String, String>) -> Result<()> {
+    let unexpected = |token: &str| {
+        DbViewerError::InvalidQuery(format!("Unexpected token in order expression: {}", token))
+    };
+
+    let mut rest = expression.trim_start();
+    if rest.is_empty() {
+        return Err(DbViewerError::InvalidQuery(
+            "Order expression is empty".to_string(),
+        ));
+    }
+    let mut depth = 0;
+    let mut after_cast = false;
+    while !rest.is_empty() {
+        let first = rest.chars().next().unwrap_or_default();
+        let len = if first.is_ascii_alphabetic() || first == '_' || first == '"' {
+            let (name, len) = if first == '"' {
+                // Quoted identifier; "" is an escaped quote
+                let end = quoted_end(rest, '"').ok_or_else(|| unexpected(rest))?;
+                (rest[1..end].replace("\"\"", "\""), end + 1)
+            } else {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                // Unquoted identifiers fold to lower case
+                (rest[..len].to_lowercase(), len)
+            };
+
+            if after_cast {
+                after_cast = false;
+                if rest[len..].starts_with("[]") {
+                    len + 2
+                } else {
+                    len
+                }
+            } else if first != '"' && rest[len..].trim_start().starts_with('(') {
+                if !ORDER_FUNCTIONS.contains(&name.as_str()) {
+                    return Err(unexpected(&rest[..len]));
+                }
+                len
+            } else if columns.contains_key(&name) {
+                len
+            } else {
+                return Err(unexpected(&rest[..len]));
+            }
+        } else if first == '\'' {
+            // Backslashes are refused so the literal can't end early under
+            // any string escaping rules
+            let end = quoted_end(rest, '\'').ok_or_else(|| unexpected(rest))?;
+            if rest[..end].contains('\\') {
+                return Err(unexpected("\\"));
+            }
+            end + 1
+        } else if first.is_ascii_digit() {
+            rest.find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len())
+        } else if rest.starts_with("--") || rest.starts_with("/*") {
+            return Err(unexpected(&rest[..2]));
+        } else if let Some(op) = ORDER_OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            match *op {
+                "(" => depth += 1,
+                ")" if depth == 0 => return Err(unexpected(")")),
+                ")" => depth -= 1,
+                "::" => after_cast = true,
+                _ => {}
+            }
+            op.len()
+        } else {
+            return Err(unexpected(&first.to_string()));
+        };
+        rest = rest[len..].trim_start();
+    }
+    if depth != 0 || after_cast {
+        return Err(DbViewerError::InvalidQuery(format!(
+            "Incomplete order expression: {}",
+            expression
+        )));
+    }
+    Ok(())
+}
+
+/// Byte offset of the quote closing the quoted token at the start of `s`,
+/// where a doubled quote is an escaped one
+fn quoted_end(s: &str, quote: char) -> Option<usize> {
+    let mut chars = s.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            if chars.peek().is_some_and(|&(_, next)| next == quote) {
+                chars.next();
+            } else {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Quote an identifier to prevent SQL injection
+pub(crate) fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{create_test_schema, drop_test_schema, test_pool};
+
+    fn condition(column: &str, operator: FilterOperator, value: Option<&str>) -> FilterCondition {
+        FilterCondition {
+            column: column.to_string(),
+            operator,
+            value: value.map(|v| v.to_string()),
+            value2: None,
+            values: None,
+            path: None,
+            case_sensitive: None,
+        }
+    }
+
+    fn texts(values: &[&str]) -> Vec<BoundValue> {
+        values
+            .iter()
+            .map(|v| BoundValue::Text(v.to_string()))
+            .collect()
+    }
+
+    fn and_group(conditions: Vec<FilterCondition>) -> Vec<FilterGroup> {
+        vec![FilterGroup {
+            conditions: conditions.into_iter().map(FilterNode::from).collect(),
+            logic: FilterLogic::And,
+        }]
+    }
+
+    #[test]
+    fn test_where_clause_mixed_and_or() {
+        let groups = vec![
+            FilterGroup {
+                conditions: vec![
+                    condition("name", FilterOperator::Contains, Some("50%_off")).into(),
+                    condition("deleted_at", FilterOperator::IsNull, None).into(),
+                ],
+                logic: FilterLogic::Or,
+            },
+            FilterGroup {
+                conditions: vec![
+                    condition("status", FilterOperator::StartsWith, Some("act")).into(),
+                    condition("owner", FilterOperator::IsNotNull, None).into(),
+                ],
+                logic: FilterLogic::And,
+            },
+        ];
+
+        let (sql, binds) = build_where_clause(&groups, &HashMap::new()).unwrap();
+        assert_eq!(
+            sql,
+            "WHERE (\"name\"::text ILIKE $1 ESCAPE '\\' OR \"deleted_at\" IS NULL) \
+             AND (\"status\"::text ILIKE $2 ESCAPE '\\' AND \"owner\" IS NOT NULL)"
+        );
+        assert_eq!(binds, texts(&["%50\\%\\_off%", "act%"]));
+    }
+
+    #[test]
+    fn test_where_clause_skips_empty_groups() {
+        let groups = vec![
+            FilterGroup {
+                conditions: vec![condition("email", FilterOperator::Equals, None).into()],
+                logic: FilterLogic::Or,
+            },
+            FilterGroup {
+                conditions: vec![condition("deleted_at", FilterOperator::IsNull, None).into()],
+                logic: FilterLogic::Or,
+            },
+        ];
+
+        let (sql, binds) = build_where_clause(&groups, &HashMap::new()).unwrap();
+        assert_eq!(sql, "WHERE \"deleted_at\" IS NULL");
+        assert!(binds.is_empty());
+        assert_eq!(
+            build_where_clause(&[], &HashMap::new()).unwrap(),
+            (String::new(), vec![])
+        );
+    }
+
+    #[test]
+    fn test_where_clause_nested_groups() {
+        // (status = 'active' OR (status = 'pending' AND ())) AND created_at > '2024-01-01'
+        let groups: Vec<FilterGroup> = serde_json::from_value(serde_json::json!([{
+            "logic": "and",
+            "conditions": [
+                {
+                    "logic": "or",
+                    "conditions": [
+                        {"column": "status", "operator": "equals", "value": "active"},
+                        {
+                            "conditions": [
+                                {"column": "status", "operator": "equals", "value": "pending"},
+                                {"logic": "or", "conditions": []}
+                            ]
+                        }
+                    ]
+                },
+                {"column": "created_at", "operator": "greater_than", "value": "2024-01-01"}
+            ]
+        }]))
+        .unwrap();
+
+        let (sql, binds) = build_where_clause(&groups, &HashMap::new()).unwrap();
+        assert_eq!(
+            sql,
+            "WHERE ((\"status\" = $1 OR \"status\" = $2) AND \"created_at\" > $3)"
+        );
+        assert_eq!(binds, texts(&["active", "pending", "2024-01-01"]));
+    }
+
+    #[test]
+    fn test_where_clause_binds_by_column_type() {
+        let column_types = HashMap::from([
+            ("id".to_string(), "integer".to_string()),
+            ("created".to_string(), "date".to_string()),
+            ("active".to_string(), "boolean".to_string()),
+            ("score".to_string(), "double precision".to_string()),
+        ]);
+        let mut between = condition("created", FilterOperator::Between, Some("2024-01-01"));
+        between.value2 = Some("2024-12-31".to_string());
+        let mut in_list = condition("id", FilterOperator::In, None);
+        in_list.values = Some(vec!["1".to_string(), " 2 ".to_string(), "x".to_string()]);
+
+        let (sql, binds) = build_where_clause(
+            &and_group(vec![
+                between,
+                in_list,
+                condition("active", FilterOperator::Equals, Some("TRUE")),
+                condition("score", FilterOperator::GreaterThan, Some("1.5")),
+            ]),
+            &column_types,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "WHERE (\"created\" BETWEEN $1::date AND $2::date \
+             AND \"id\" IN ($3, $4, $5::integer) \
+             AND \"active\" = $6 AND \"score\" > $7)"
+        );
+        assert_eq!(
+            binds,
+            vec![
+                BoundValue::Text("2024-01-01".to_string()),
+                BoundValue::Text("2024-12-31".to_string()),
+                BoundValue::Int(1),
+                BoundValue::Int(2),
+                BoundValue::Text("x".to_string()),
+                BoundValue::Bool(true),
+                BoundValue::Float(1.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_where_clause_drops_type_lengths() {
+        let column_types = HashMap::from([
+            ("code".to_string(), "character(3)".to_string()),
+            ("name".to_string(), "character varying(5)".to_string()),
+            ("flags".to_string(), "bit(4)".to_string()),
+            ("tags".to_string(), "character(2)[]".to_string()),
+        ]);
+        let mut contains = condition("tags", FilterOperator::ArrayContains, None);
+        contains.values = Some(vec!["ab".to_string()]);
+
+        let (sql, _) = build_where_clause(
+            &and_group(vec![
+                condition("code", FilterOperator::Equals, Some("abcd")),
+                condition("name", FilterOperator::Equals, Some("abcdef")),
+                condition("flags", FilterOperator::Equals, Some("1010")),
+                contains,
+            ]),
+            &column_types,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "WHERE (\"code\" = $1::bpchar AND \"name\" = $2::character varying \
+             AND \"flags\" = $3::bit varying AND \"tags\" @> ARRAY[$4]::bpchar[])"
+        );
+    }
+
+    #[test]
+    fn test_where_clause_not_in() {
+        let mut not_in = condition("id", FilterOperator::NotIn, None);
+        not_in.values = Some(vec!["3".to_string(), "4".to_string()]);
+        let (sql, binds) =
+            build_where_clause(&and_group(vec![not_in.clone()]), &HashMap::new()).unwrap();
+        assert_eq!(sql, "WHERE \"id\" NOT IN ($1, $2)");
+        assert_eq!(binds, texts(&["3", "4"]));
+
+        not_in.values = Some(vec![]);
+        assert_eq!(
+            build_where_clause(&and_group(vec![not_in]), &HashMap::new()).unwrap(),
+            (String::new(), vec![])
+        );
+    }
+
+    #[test]
+    fn test_where_clause_regex() {
+        // Regex patterns are bound verbatim, without LIKE escaping
+        let patterns = [r"^\d{3}\.\d+$", r"a.b\\c", r"^[0-9a-f-]{36}$"];
+        for pattern in patterns {
+            let (sql, binds) = build_where_clause(
+                &and_group(vec![condition(
+                    "code",
+                    FilterOperator::Regex,
+                    Some(pattern),
+                )]),
+                &HashMap::new(),
+            )
+            .unwrap();
+            assert_eq!(sql, "WHERE \"code\"::text ~ $1");
+            assert_eq!(binds, texts(&[pattern]));
+        }
+
+        let (sql, _) = build_where_clause(
+            &and_group(vec![condition(
+                "email",
+                FilterOperator::NotRegex,
+                Some(r"@example\.com$"),
+            )]),
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(sql, "WHERE \"email\"::text !~ $1");
+
+        let empty = and_group(vec![
+            condition("code", FilterOperator::Regex, Some("")),
+            condition("code", FilterOperator::NotRegex, None),
+        ]);
+        assert_eq!(
+            build_where_clause(&empty, &HashMap::new()).unwrap(),
+            (String::new(), vec![])
+        );
+    }
+
+    #[test]
+    fn test_where_clause_case_sensitive_like() {
+        let sensitive = |operator| FilterCondition {
+            case_sensitive: Some(true),
+            ..condition("name", operator, Some("50%_off"))
+        };
+        let (sql, binds) = build_where_clause(
+            &and_group(vec![
+                sensitive(FilterOperator::Contains),
+                sensitive(FilterOperator::NotContains),
+                sensitive(FilterOperator::StartsWith),
+                sensitive(FilterOperator::EndsWith),
+            ]),
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "WHERE (\"name\"::text LIKE $1 ESCAPE '\\' AND \"name\"::text NOT LIKE $2 ESCAPE '\\' \
+             AND \"name\"::text LIKE $3 ESCAPE '\\' AND \"name\"::text LIKE $4 ESCAPE '\\')"
+        );
+        assert_eq!(
+            binds,
+            texts(&[r"%50\%\_off%", r"%50\%\_off%", r"50\%\_off%", r"%50\%\_off"])
+        );
+
+        // Unset and false both keep the ILIKE default
+        let insensitive = FilterCondition {
+            case_sensitive: Some(false),
+            ..condition("name", FilterOperator::Contains, Some("a"))
+        };
+        let (sql, _) = build_where_clause(
+            &and_group(vec![
+                insensitive,
+                condition("name", FilterOperator::NotContains, Some("b")),
+            ]),
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "WHERE (\"name\"::text ILIKE $1 ESCAPE '\\' AND \"name\"::text NOT ILIKE $2 ESCAPE '\\')"
+        );
+    }
+
+    #[test]
+    fn test_where_clause_regex_insensitive() {
+        let (sql, binds) = build_where_clause(
+            &and_group(vec![
+                condition("name", FilterOperator::Regex, Some("^A")),
+                condition("name", FilterOperator::RegexInsensitive, Some("smith$")),
+                condition("name", FilterOperator::RegexInsensitive, Some("")),
+            ]),
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(sql, "WHERE (\"name\"::text ~ $1 AND \"name\"::text ~* $2)");
+        assert_eq!(binds, texts(&["^A", "smith$"]));
+
+        // The names the operators had before are still accepted
+        let operator = |name: &str| serde_json::from_value(serde_json::json!(name)).unwrap();
+        assert!(matches!(operator("matches"), FilterOperator::Regex));
+        assert!(matches!(
+            operator("matches_insensitive"),
+            FilterOperator::RegexInsensitive
+        ));
+    }
+
+    #[test]
+    fn test_where_clause_json_path() {
+        let column_types = HashMap::from([
+            ("meta".to_string(), "jsonb".to_string()),
+            ("name".to_string(), "text".to_string()),
+        ]);
+        let json = |operator, path: &str, value: Option<&str>| FilterCondition {
+            path: Some(path.to_string()),
+            ..condition("meta", operator, value)
+        };
+
+        let (sql, binds) = build_where_clause(
+            &and_group(vec![
+                json(FilterOperator::JsonPathEquals, "plan.tier", Some("pro")),
+                json(FilterOperator::JsonPathExists, "trial", None),
+                json(FilterOperator::JsonPathExists, "billing.card", None),
+            ]),
+            &column_types,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "WHERE (\"meta\" #>> $1 = $2 AND \"meta\" ? $3 AND \"meta\" #> $4 ? $5)"
+        );
+        assert_eq!(
+            binds,
+            vec![
+                BoundValue::TextArray(vec!["plan".to_string(), "tier".to_string()]),
+                BoundValue::Text("pro".to_string()),
+                BoundValue::Text("trial".to_string()),
+                BoundValue::TextArray(vec!["billing".to_string()]),
+                BoundValue::Text("card".to_string()),
+            ]
+        );
+
+        let mut on_text = json(FilterOperator::JsonPathExists, "plan", None);
+        on_text.column = "name".to_string();
+        let err = build_where_clause(&and_group(vec![on_text]), &column_types);
+        assert!(matches!(err, Err(DbViewerError::InvalidQuery(_))));
+
+        let empty_key = json(FilterOperator::JsonPathEquals, "plan..tier", Some("x"));
+        let err = build_where_clause(&and_group(vec![empty_key]), &column_types);
+        assert!(matches!(err, Err(DbViewerError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn test_where_clause_array_contains() {
+        let column_types = HashMap::from([
+            ("tags".to_string(), "text[]".to_string()),
+            ("scores".to_string(), "integer[]".to_string()),
+            ("name".to_string(), "text".to_string()),
+        ]);
+        let (sql, binds) = build_where_clause(
+            &and_group(vec![
+                condition("tags", FilterOperator::ArrayContains, Some("red")),
+                condition("scores", FilterOperator::ArrayContains, Some("7")),
+            ]),
+            &column_types,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "WHERE ($1::text = ANY(\"tags\") AND $2 = ANY(\"scores\"))"
+        );
+        assert_eq!(
+            binds,
+            vec![BoundValue::Text("red".to_string()), BoundValue::Int(7)]
+        );
+
+        let on_text = condition("name", FilterOperator::ArrayContains, Some("x"));
+        let err = build_where_clause(&and_group(vec![on_text]), &column_types);
+        assert!(matches!(err, Err(DbViewerError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn test_where_clause_array_values() {
+        let column_types = HashMap::from([
+            ("tags".to_string(), "text[]".to_string()),
+            ("scores".to_string(), "integer[]".to_string()),
+            ("name".to_string(), "text".to_string()),
+        ]);
+        let array_condition = |column: &str, operator, values: &[&str]| FilterCondition {
+            values: Some(values.iter().map(|v| v.to_string()).collect()),
+            ..condition(column, operator, None)
+        };
+
+        let (sql, binds) = build_where_clause(
+            &and_group(vec![
+                array_condition("scores", FilterOperator::ArrayContains, &["1", "2", "3"]),
+                array_condition("tags", FilterOperator::ArrayOverlaps, &["red", "blue"]),
+                array_condition("labels", FilterOperator::ArrayOverlaps, &["x"]),
+            ]),
+            &column_types,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "WHERE (\"scores\" @> ARRAY[$1, $2, $3]::integer[] \
+             AND \"tags\" && ARRAY[$4, $5]::text[] \
+             AND \"labels\" && ARRAY[$6]::text[])"
+        );
+        assert_eq!(binds, texts(&["1", "2", "3", "red", "blue", "x"]));
+
+        // An empty list drops the condition
+        let empty = array_condition("scores", FilterOperator::ArrayOverlaps, &[]);
+        let (sql, _) = build_where_clause(&and_group(vec![empty]), &column_types).unwrap();
+        assert_eq!(sql, "");
+
+        let on_text = array_condition("name", FilterOperator::ArrayOverlaps, &["x"]);
+        let err = build_where_clause(&and_group(vec![on_text]), &column_types);
+        assert!(matches!(err, Err(DbViewerError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn test_is_select_query_skips_comments() {
+        assert!(is_select_query("-- monthly report\nSELECT 1"));
+        assert!(is_select_query(
+            "  /* header\n * /* nested */ still comment */\n  -- note\n  select 1"
+        ));
+        assert!(is_select_query("VALUES (1), (2)"));
+        assert!(is_select_query("table users"));
+        assert!(!is_select_query("-- SELECT\nDELETE FROM users"));
+        assert!(!is_select_query("/* SELECT */ UPDATE users SET a = 1"));
+        assert!(!is_select_query("-- only a comment"));
+        assert!(!is_select_query("/* unterminated SELECT"));
+    }
+
+    #[test]
+    fn test_infer_rollback() {
+        let sql = |undo: &str| Rollback::Sql(undo.to_string());
+        let relation =
+            |name: &str, undo: &str| Created::Relation(name.to_string()).drop(undo.to_string());
+        let column = |column: &str, undo: &str| {
+            Created::Column {
+                table: "users".to_string(),
+                column: column.to_string(),
+            }
+            .drop(undo.to_string())
+        };
+        let cases = [
+            (
+                "CREATE TABLE IF NOT EXISTS app.users (id int)",
+                relation("app.users", "DROP TABLE app.users"),
+            ),
+            (
+                "create unique index concurrently idx_email on users (email)",
+                relation("idx_email", "DROP INDEX idx_email"),
+            ),
+            (
+                "CREATE INDEX users_name ON ONLY app.users (name)",
+                relation("app.users_name", "DROP INDEX app.users_name"),
+            ),
+            (
+                "CREATE SCHEMA IF NOT EXISTS audit",
+                Created::Schema("audit".to_string()).drop("DROP SCHEMA audit".to_string()),
+            ),
+            ("CREATE INDEX ON users (email)", Rollback::Manual),
+            ("CREATE OR REPLACE VIEW v AS SELECT 1", Rollback::Manual),
+            (
+                "CREATE MATERIALIZED VIEW mv AS SELECT 1",
+                relation("mv", "DROP MATERIALIZED VIEW mv"),
+            ),
+            (
+                "ALTER TABLE users ADD COLUMN IF NOT EXISTS age numeric(5, 2) DEFAULT 0",
+                column("age", "ALTER TABLE users DROP COLUMN age"),
+            ),
+            (
+                "ALTER TABLE \"My Table\" ADD \"Nick Name\" text",
+                Created::Column {
+                    table: "\"My Table\"".to_string(),
+                    column: "\"Nick Name\"".to_string(),
+                }
+                .drop("ALTER TABLE \"My Table\" DROP COLUMN \"Nick Name\"".to_string()),
+            ),
+            (
+                "ALTER TABLE users ADD COLUMN a int, ADD COLUMN b int",
+                Rollback::Manual,
+            ),
+            ("ALTER TABLE users ADD PRIMARY KEY (id)", Rollback::Manual),
+            (
+                "ALTER TABLE users ADD CONSTRAINT age_positive CHECK (age > 0)",
+                sql("ALTER TABLE users DROP CONSTRAINT age_positive"),
+            ),
+            (
+                "ALTER TABLE app.users RENAME TO members",
+                sql("ALTER TABLE app.members RENAME TO users"),
+            ),
+            (
+                "ALTER TABLE users RENAME COLUMN name TO full_name",
+                sql("ALTER TABLE users RENAME COLUMN full_name TO name"),
+            ),
+            (
+                "ALTER TABLE ONLY users DROP COLUMN IF EXISTS age",
+                Rollback::RestoreColumn {
+                    table: "users".to_string(),
+                    column: "age".to_string(),
+                },
+            ),
+            (
+                "DROP INDEX IF EXISTS idx_email",
+                Rollback::RestoreIndex {
+                    name: "idx_email".to_string(),
+                },
+            ),
+            ("UPDATE users SET age = 1", Rollback::Manual),
+        ];
+        for (statement, expected) in cases {
+            assert_eq!(infer_rollback(statement), expected, "{statement}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_rollback() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.users (id int, email text, age int NOT NULL DEFAULT 18);
+                 CREATE INDEX users_email ON {s}.users (email);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let statements = [
+            format!("CREATE TABLE {}.posts (id int);", schema),
+            format!("ALTER TABLE {}.users DROP COLUMN age", schema),
+            format!("DROP INDEX {}.users_email", schema),
+            "-- backfill\nUPDATE users\n   SET email = lower(email)".to_string(),
+        ];
+        let rollback = MigrationOperations::generate_rollback(&pool, &statements)
+            .await
+            .unwrap();
+        assert_eq!(
+            rollback,
+            [
+                "-- MANUAL ROLLBACK REQUIRED: UPDATE users SET email = lower(email)".to_string(),
+                format!(
+                    "CREATE INDEX users_email ON {}.users USING btree (email)",
+                    schema
+                ),
+                format!(
+                    "ALTER TABLE {}.users ADD COLUMN age integer DEFAULT 18 NOT NULL",
+                    schema
+                ),
+                format!("DROP TABLE {}.posts", schema),
+            ]
+        );
+
+        // Applying the migration captures the same rollback before each drop
+        let result =
+            MigrationOperations::execute_migration(&pool, &statements[..3], false, None, None)
+                .await
+                .unwrap();
+        assert!(result.committed);
+        assert_eq!(result.rollback, rollback[1..]);
+
+        // Afterwards the dropped definitions are gone from the catalog
+        let late = MigrationOperations::generate_rollback(&pool, &statements[1..3])
+            .await
+            .unwrap();
+        assert!(late
+            .iter()
+            .all(|s| s.starts_with("-- MANUAL ROLLBACK REQUIRED")));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_generate_rollback_keeps_existing_objects() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(format!("CREATE TABLE {}.users (id int, email text)", schema).as_str())
+            .await
+            .unwrap();
+
+        // Only what the migration creates may be dropped again
+        let statements = [
+            format!("CREATE SCHEMA IF NOT EXISTS {}", schema),
+            format!("CREATE TABLE IF NOT EXISTS {}.users (id int)", schema),
+            format!(
+                "ALTER TABLE {}.users ADD COLUMN IF NOT EXISTS email text",
+                schema
+            ),
+            format!(
+                "ALTER TABLE {}.users ADD COLUMN IF NOT EXISTS age int",
+                schema
+            ),
+            format!("CREATE TABLE IF NOT EXISTS {}.posts (id int)", schema),
+        ];
+        let rollback = MigrationOperations::generate_rollback(&pool, &statements)
+            .await
+            .unwrap();
+        let manual = |sql: &String| format!("-- MANUAL ROLLBACK REQUIRED: {}", sql);
+        assert_eq!(
+            rollback,
+            [
+                format!("DROP TABLE {}.posts", schema),
+                format!("ALTER TABLE {}.users DROP COLUMN age", schema),
+                manual(&statements[2]),
+                manual(&statements[1]),
+                manual(&statements[0]),
+            ]
+        );
+
+        let result = MigrationOperations::execute_migration(&pool, &statements, false, None, None)
+            .await
+            .unwrap();
+        assert!(result.committed);
+        assert_eq!(result.rollback, rollback);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_generate_rollback_survives_failed_lookups() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+
+        // to_regclass raises on a cross-database name; later lookups still run
+        let statements = [
+            "DROP INDEX otherdb.public.users_email".to_string(),
+            format!("CREATE TABLE {}.posts (id int)", schema),
+        ];
+        let rollback = MigrationOperations::generate_rollback(&pool, &statements)
+            .await
+            .unwrap();
+        assert_eq!(
+            rollback,
+            [
+                format!("DROP TABLE {}.posts", schema),
+                format!("-- MANUAL ROLLBACK REQUIRED: {}", statements[0]),
+            ]
+        );
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_execute_transaction_rolls_back() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(format!("CREATE TABLE {}.items (id int PRIMARY KEY)", schema).as_str())
+            .await
+            .unwrap();
+
+        let statements = [
+            format!("INSERT INTO {}.items VALUES (1)", schema),
+            format!("INSERT INTO {}.items VALUES (1)", schema),
+            format!("INSERT INTO {}.items VALUES (2)", schema),
+        ];
+        let result = DataOperations::execute_transaction(&pool, &statements, None)
+            .await
+            .unwrap();
+        assert!(!result.committed);
+        assert_eq!(result.statements.len(), 2);
+        assert!(result.statements[0].ok);
+        assert_eq!(result.statements[0].rows_affected, Some(1));
+        let error = result.statements[1].error.as_ref().unwrap();
+        assert_eq!(error.code.as_deref(), Some("23505"));
+
+        let count: i64 = sqlx::query_scalar(&format!("SELECT count(*) FROM {}.items", schema))
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+
+        let result = DataOperations::execute_transaction(&pool, &statements[..1], Some(1000))
+            .await
+            .unwrap();
+        assert!(result.committed);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[test]
+    fn test_pg_array_literal() {
+        let items = serde_json::json!(["a", "b c", "quote\"d", "back\\slash", null, 3]);
+        assert_eq!(
+            pg_array_literal(items.as_array().unwrap()),
+            r#"{"a","b c","quote\"d","back\\slash",NULL,"3"}"#
+        );
+        let nested = serde_json::json!([[1, 2], [3, 4]]);
+        assert_eq!(
+            pg_array_literal(nested.as_array().unwrap()),
+            r#"{{"1","2"},{"3","4"}}"#
+        );
+        assert_eq!(pg_array_literal(&[]), "{}");
+    }
+
+    #[test]
+    fn test_column_value_to_sql_numeric() {
+        let numeric = Some("numeric".to_string());
+        let sql = |value: JsonValue| column_value_to_sql(&value, numeric.as_ref());
+        assert_eq!(
+            sql(serde_json::json!("1234567890123456789.99")),
+            "1234567890123456789.99"
+        );
+        assert_eq!(sql(serde_json::json!("-0.5")), "-0.5");
+        assert_eq!(sql(serde_json::json!("12.")), "12.");
+        assert_eq!(sql(serde_json::json!(7)), "7");
+        // Anything else stays a quoted, cast string
+        for text in ["NaN", "1e5", "-", ".", "1.2.3", "1; DROP TABLE t", ""] {
+            assert_eq!(
+                sql(serde_json::json!(text)),
+                format!("'{}'::numeric", escape_sql_string(text))
+            );
+        }
+        assert_eq!(
+            column_value_to_sql(&serde_json::json!("12.50"), Some(&"money".to_string())),
+            "'12.50'::money"
+        );
+
+        let typed = |value: &str, ty: &str| {
+            column_value_to_sql(&serde_json::json!(value), Some(&ty.to_string()))
+        };
+        assert_eq!(typed("0.25", "double precision"), "0.25");
+        assert_eq!(typed("-3", "bigint"), "-3");
+        assert_eq!(typed("1.5", "integer"), "'1.5'::integer");
+        assert_eq!(typed("12.50", "numeric(10,2)"), "12.50");
+        assert_eq!(typed("abc", "character(3)"), "'abc'::bpchar");
+        assert_eq!(
+            typed("abc", "character varying(3)"),
+            "'abc'::character varying"
+        );
+        assert_eq!(
+            typed("7c9e6679-7425-40de-944b-e07fc1f90ae7", "uuid"),
+            "'7c9e6679-7425-40de-944b-e07fc1f90ae7'::uuid"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_json_path_filters() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                r#"CREATE TABLE {s}.accounts (id int PRIMARY KEY, meta jsonb);
+                 INSERT INTO {s}.accounts VALUES
+                    (1, '{{"plan": {{"tier": "pro"}}, "trial": true}}'),
+                    (2, '{{"plan": {{"tier": "free"}}}}'),
+                    (3, '{{"it''s": 1}}');"#,
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |operator, path: &str, value: Option<&str>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            let groups = and_group(vec![FilterCondition {
+                path: Some(path.to_string()),
+                ..condition("meta", operator, value)
+            }]);
+            async move {
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "accounts".to_string(),
+                        filters: Some(groups),
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+                .unwrap()
+                .rows
+                .iter()
+                .map(|r| r["id"].as_i64().unwrap())
+                .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            fetch(FilterOperator::JsonPathEquals, "plan.tier", Some("pro")).await,
+            vec![1]
+        );
+        assert_eq!(
+            fetch(FilterOperator::JsonPathExists, "trial", None).await,
+            vec![1]
+        );
+        assert_eq!(
+            fetch(FilterOperator::JsonPathExists, "it's", None).await,
+            vec![3]
+        );
+        // A path that tries to break out of the expression is just a key that doesn't exist
+        assert!(fetch(
+            FilterOperator::JsonPathEquals,
+            "plan'}') OR 1=1 --",
+            Some("pro")
+        )
+        .await
+        .is_empty());
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    fn column_info(name: &str, udt_name: &str) -> ColumnInfo {
+        ColumnInfo {
+            name: name.to_string(),
+            data_type: udt_name.to_string(),
+            udt_name: udt_name.to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_unique: false,
+            is_foreign_key: false,
+            default_value: None,
+            character_maximum_length: None,
+            numeric_precision: None,
+            numeric_scale: None,
+            ordinal_position: 1,
+            description: None,
+            foreign_key_info: None,
+            enum_values: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_filters() {
+        let columns = vec![
+            column_info("id", "int4"),
+            column_info("price", "numeric"),
+            column_info("born", "date"),
+            column_info("seen", "timestamptz"),
+            column_info("active", "bool"),
+            column_info("name", "text"),
+        ];
+        let validate =
+            |filters: Vec<FilterCondition>| validate_filters(&filters, &columns).unwrap();
+
+        let mut between = condition("seen", FilterOperator::Between, Some("2024-01-01"));
+        between.value2 = Some("2024-06-30T12:00:00+02:00".to_string());
+        let mut in_list = condition("id", FilterOperator::In, None);
+        in_list.values = Some(vec!["1".to_string(), " 2 ".to_string()]);
+        let valid = validate(vec![
+            condition("id", FilterOperator::Equals, Some("42")),
+            condition("price", FilterOperator::GreaterThan, Some("-1.25")),
+            condition("born", FilterOperator::LessThan, Some("2000-02-29")),
+            condition("born", FilterOperator::Equals, Some("today")),
+            condition(
+                "seen",
+                FilterOperator::Equals,
+                Some("2024-03-01 08:30:00.5"),
+            ),
+            condition("active", FilterOperator::Equals, Some("TRUE")),
+            condition("active", FilterOperator::NotEquals, Some("0")),
+            condition("name", FilterOperator::Equals, Some("anything")),
+            condition("id", FilterOperator::Contains, Some("abc")),
+            condition("missing", FilterOperator::Equals, Some("abc")),
+            between,
+            in_list,
+        ]);
+        assert!(valid.is_empty(), "{:?}", valid);
+
+        let mut open_between = condition("born", FilterOperator::Between, Some("someday"));
+        open_between.value2 = None;
+        let mut in_list = condition("price", FilterOperator::NotIn, None);
+        in_list.values = Some(vec!["1".to_string(), "one".to_string()]);
+        let errors = validate(vec![
+            condition("id", FilterOperator::Equals, Some("abc")),
+            condition("id", FilterOperator::GreaterThan, Some("1.5")),
+            condition("seen", FilterOperator::LessThan, Some("2024-13-01")),
+            condition("active", FilterOperator::Equals, Some("yes")),
+            open_between,
+            in_list,
+        ]);
+        let found: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.column.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("id", "\"abc\" is not an integer"),
+                ("id", "\"1.5\" is not an integer"),
+                (
+                    "seen",
+                    "\"2024-13-01\" is not a timestamp (YYYY-MM-DD HH:MM:SS)"
+                ),
+                ("active", "\"yes\" is not true, false, 1 or 0"),
+                ("born", "Between needs an upper bound"),
+                ("born", "\"someday\" is not a date (YYYY-MM-DD)"),
+                ("price", "\"one\" is not a number"),
+            ]
+        );
+        assert!(matches!(errors[4].operator, FilterOperator::Between));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_rejects_invalid_filter_values() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(format!("CREATE TABLE {}.items (id int, created date);", schema).as_str())
+            .await
+            .unwrap();
+
+        let groups = vec![FilterGroup {
+            conditions: vec![
+                condition("id", FilterOperator::Equals, Some("abc")).into(),
+                FilterGroup {
+                    conditions: vec![
+                        condition("created", FilterOperator::Equals, Some("soon")).into()
+                    ],
+                    logic: FilterLogic::Or,
+                }
+                .into(),
+            ],
+            logic: FilterLogic::And,
+        }];
+        let err = DataOperations::fetch_paginated(
+            &pool,
+            &PageRequest {
+                schema: schema.clone(),
+                table: "items".to_string(),
+                filters: Some(groups),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap_err();
+        match err {
+            DbViewerError::InvalidQuery(message) => {
+                assert!(
+                    message.contains("id: \"abc\" is not an integer"),
+                    "{}",
+                    message
+                );
+                assert!(message.contains("created: \"soon\""), "{}", message);
+            }
+            other => panic!("expected InvalidQuery, got {:?}", other),
+        }
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_filters_use_binds() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.items (id int PRIMARY KEY, name text, created date);
+                 INSERT INTO {s}.items VALUES
+                    (1, 'apple', '2024-01-05'),
+                    (2, 'O''Brien', '2024-03-10'),
+                    (3, 'back\\slash', '2024-06-01');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |filters: Vec<FilterCondition>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                let groups = and_group(filters);
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "items".to_string(),
+                        filters: Some(groups),
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let result = fetch(vec![condition(
+            "id",
+            FilterOperator::GreaterThan,
+            Some("1"),
+        )])
+        .await;
+        assert_eq!(result.total_count, 2);
+
+        let result = fetch(vec![condition(
+            "name",
+            FilterOperator::Equals,
+            Some("O'Brien"),
+        )])
+        .await;
+        assert_eq!(result.rows[0]["id"], 2);
+
+        let result = fetch(vec![condition(
+            "name",
+            FilterOperator::Contains,
+            Some("k\\s"),
+        )])
+        .await;
+        assert_eq!(result.rows[0]["id"], 3);
+
+        let mut between = condition("created", FilterOperator::Between, Some("2024-02-01"));
+        between.value2 = Some("2024-12-31".to_string());
+        let mut in_list = condition("id", FilterOperator::In, None);
+        in_list.values = Some(vec!["1".to_string(), "3".to_string()]);
+        let result = fetch(vec![between, in_list]).await;
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.rows[0]["id"], 3);
+
+        let injection = "'; DROP TABLE x; --";
+        let result = fetch(vec![condition(
+            "name",
+            FilterOperator::Equals,
+            Some(injection),
+        )])
+        .await;
+        assert_eq!(result.total_count, 0);
+        assert!(result.rows.is_empty());
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_insert_row_binds_typed_values() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {}.things (
+                    id int PRIMARY KEY,
+                    label varchar(20),
+                    price numeric(30, 10),
+                    meta jsonb,
+                    raw bytea,
+                    active boolean,
+                    seen date
+                )",
+                schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let data = serde_json::json!({
+            "id": 7,
+            "label": "O'Brien",
+            "price": "12345678901.0123456789",
+            "meta": {"tags": ["a", "b"]},
+            "raw": "\\xdeadbeef",
+            "active": true,
+            "seen": null,
+        });
+        let row = DataOperations::insert_row(
+            &pool,
+            InsertRequest {
+                schema: schema.clone(),
+                table: "things".to_string(),
+                data: data.as_object().unwrap().clone(),
+                on_conflict: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(row["id"], 7);
+        assert_eq!(row["label"], "O'Brien");
+        assert_eq!(row["meta"], serde_json::json!({"tags": ["a", "b"]}));
+        assert_eq!(row["active"], true);
+        assert_eq!(row["seen"], JsonValue::Null);
+
+        let (price, raw): (String, Vec<u8>) = sqlx::query_as(&format!(
+            "SELECT price::text, raw FROM {}.things WHERE id = 7",
+            schema
+        ))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(price, "12345678901.0123456789");
+        assert_eq!(raw, vec![0xde, 0xad, 0xbe, 0xef]);
+
+        // An over-long value is refused, not cut to the column's length
+        let err = DataOperations::insert_row(
+            &pool,
+            InsertRequest {
+                schema: schema.clone(),
+                table: "things".to_string(),
+                data: serde_json::json!({"id": 8, "label": "x".repeat(21)})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+                on_conflict: None,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(&err, DbViewerError::Database(e)
+                if extract_pg_error(e).code.as_deref() == Some("22001")),
+            "{err}"
+        );
+
+        let missing = DataOperations::insert_row(
+            &pool,
+            InsertRequest {
+                schema: schema.clone(),
+                table: "nope".to_string(),
+                data: data.as_object().unwrap().clone(),
+                on_conflict: None,
+            },
+        )
+        .await;
+        assert!(matches!(missing, Err(DbViewerError::TableNotFound(_))));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_upsert_row() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.users (id int PRIMARY KEY, name text, visits int);
+                 INSERT INTO {s}.users VALUES (1, 'ada', 1);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let upsert = |data: JsonValue, conflict: &[&str], update: Option<&[&str]>| UpsertRequest {
+            schema: schema.clone(),
+            table: "users".to_string(),
+            data: data.as_object().unwrap().clone(),
+            conflict_columns: conflict.iter().map(|c| c.to_string()).collect(),
+            update_columns: update.map(|u| u.iter().map(|c| c.to_string()).collect()),
+        };
+
+        // Conflict updates every non-conflict column by default
+        let row = DataOperations::upsert_row(
+            &pool,
+            upsert(
+                serde_json::json!({"id": 1, "name": "grace", "visits": 2}),
+                &["id"],
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(row["name"], "grace");
+        assert_eq!(row["visits"], 2);
+
+        // update_columns restricts what gets overwritten
+        let row = DataOperations::upsert_row(
+            &pool,
+            upsert(
+                serde_json::json!({"id": 1, "name": "linus", "visits": 3}),
+                &["id"],
+                Some(&["visits"]),
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(row["name"], "grace");
+        assert_eq!(row["visits"], 3);
+
+        // Nothing left to update still returns the existing row
+        let row =
+            DataOperations::upsert_row(&pool, upsert(serde_json::json!({"id": 1}), &["id"], None))
+                .await
+                .unwrap();
+        assert_eq!(row["name"], "grace");
+
+        // No conflict target is a plain insert, which fails on a duplicate key
+        let dup = DataOperations::upsert_row(
+            &pool,
+            upsert(serde_json::json!({"id": 1, "name": "dup"}), &[], None),
+        )
+        .await;
+        assert!(matches!(dup, Err(DbViewerError::Database(_))));
+        let row = DataOperations::upsert_row(
+            &pool,
+            upsert(serde_json::json!({"id": 2, "name": "new"}), &[], None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(row["id"], 2);
+
+        let mut missing = upsert(serde_json::json!({"id": 1}), &["id"], None);
+        missing.table = "nope".to_string();
+        let missing = DataOperations::upsert_row(&pool, missing).await;
+        assert!(matches!(missing, Err(DbViewerError::TableNotFound(_))));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_default_marker() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {}.tasks (id serial PRIMARY KEY, title text DEFAULT 'untitled', note text)",
+                schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let insert = |data: JsonValue| {
+            DataOperations::insert_row(
+                &pool,
+                InsertRequest {
+                    schema: schema.clone(),
+                    table: "tasks".to_string(),
+                    data: data.as_object().unwrap().clone(),
+                    on_conflict: None,
+                },
+            )
+        };
+        let default = serde_json::json!({"$default": true});
+
+        let row = insert(serde_json::json!({"title": default, "note": null}))
+            .await
+            .unwrap();
+        assert_eq!(row["id"], 1);
+        assert_eq!(row["title"], "untitled");
+        assert_eq!(row["note"], JsonValue::Null);
+
+        // Only defaults
+        let row = insert(serde_json::json!({"id": default, "title": default}))
+            .await
+            .unwrap();
+        assert_eq!(row["id"], 2);
+        assert_eq!(row["title"], "untitled");
+
+        // Anything else is an ordinary (jsonb) value
+        for not_default in [
+            serde_json::json!({"$default": false}),
+            serde_json::json!({"$default": true, "x": 1}),
+        ] {
+            assert!(!is_default_marker(&not_default));
+        }
+
+        let result = DataOperations::update_row(
+            &pool,
+            UpdateRequest {
+                schema: schema.clone(),
+                table: "tasks".to_string(),
+                data: serde_json::json!({"title": default, "note": "kept"})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+                where_clause: serde_json::json!({"id": 1}).as_object().unwrap().clone(),
+                returning: true,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows[0]["title"], "untitled");
+        assert_eq!(result.rows[0]["note"], "kept");
+
+        pool.execute(format!("UPDATE {}.tasks SET title = 'renamed'", schema).as_str())
+            .await
+            .unwrap();
+        let updated = DataOperations::update_by_filter(
+            &pool,
+            UpdateByFilterRequest {
+                schema: schema.clone(),
+                table: "tasks".to_string(),
+                data: serde_json::json!({"title": default})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+                filters: vec![condition("id", FilterOperator::Equals, Some("2"))],
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated, 1);
+        let titles: Vec<String> =
+            sqlx::query_scalar(&format!("SELECT title FROM {}.tasks ORDER BY id", schema))
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(titles, ["renamed", "untitled"]);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_row() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.items (
+                     id int GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                     seq serial,
+                     name text,
+                     price numeric,
+                     doubled numeric GENERATED ALWAYS AS (price * 2) STORED,
+                     tags text[]
+                 );
+                 INSERT INTO {s}.items (name, price, tags) VALUES ('lamp', 12.50, '{{a,b}}');
+                 CREATE TABLE {s}.users (email text PRIMARY KEY, code text UNIQUE);
+                 INSERT INTO {s}.users VALUES ('ada@example.com', 'A1');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let key = |value: JsonValue| value.as_object().unwrap().clone();
+
+        let copy = DataOperations::duplicate_row(
+            &pool,
+            &schema,
+            "items",
+            &key(serde_json::json!({"id": 1})),
+        )
+        .await
+        .unwrap();
+        assert_eq!(copy["id"], 2);
+        assert_eq!(copy["seq"], 2);
+        assert_eq!(copy["name"], "lamp");
+        assert_eq!(copy["price"], "12.50");
+        assert_eq!(copy["doubled"], "25.00");
+        assert_eq!(copy["tags"], serde_json::json!(["a", "b"]));
+
+        // The where clause must pick out exactly one row
+        for where_clause in [
+            serde_json::json!({"name": "lamp"}),
+            serde_json::json!({"id": 99}),
+            serde_json::json!({}),
+        ] {
+            let err = DataOperations::duplicate_row(&pool, &schema, "items", &key(where_clause))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, DbViewerError::InvalidQuery(_)), "{err}");
+        }
+
+        // A natural key is copied as is, so the database refuses it
+        let err = DataOperations::duplicate_row(
+            &pool,
+            &schema,
+            "users",
+            &key(serde_json::json!({"email": "ada@example.com"})),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, DbViewerError::InvalidQuery(_)), "{err}");
+        assert!(err.to_string().contains("ada@example.com"), "{err}");
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_insert_row_on_conflict() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.users (id int PRIMARY KEY, email text UNIQUE, name text);
+                 INSERT INTO {s}.users VALUES (1, 'ada@example.com', 'ada');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let insert = |data: JsonValue, columns: &[&str], action: ConflictAction| InsertRequest {
+            schema: schema.clone(),
+            table: "users".to_string(),
+            data: data.as_object().unwrap().clone(),
+            on_conflict: Some(OnConflict {
+                columns: columns.iter().map(|c| c.to_string()).collect(),
+                action,
+                update_columns: None,
+            }),
+        };
+        let duplicate = serde_json::json!({"id": 2, "email": "ada@example.com", "name": "grace"});
+
+        // Skipped rows come back as null
+        let row = DataOperations::insert_row(
+            &pool,
+            insert(duplicate.clone(), &["email"], ConflictAction::DoNothing),
+        )
+        .await
+        .unwrap();
+        assert!(row.is_null());
+        let row = DataOperations::insert_row(
+            &pool,
+            insert(duplicate.clone(), &[], ConflictAction::DoNothing),
+        )
+        .await
+        .unwrap();
+        assert!(row.is_null());
+
+        let row = DataOperations::insert_row(
+            &pool,
+            insert(
+                serde_json::json!({"id": 3, "email": "new@example.com"}),
+                &["email"],
+                ConflictAction::DoNothing,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(row["id"], 3);
+
+        let mut update = insert(duplicate.clone(), &["email"], ConflictAction::DoUpdate);
+        update.on_conflict.as_mut().unwrap().update_columns = Some(vec!["name".to_string()]);
+        let row = DataOperations::insert_row(&pool, update).await.unwrap();
+        assert_eq!(row["id"], 1);
+        assert_eq!(row["name"], "grace");
+
+        for (columns, action) in [
+            (&["nope"][..], ConflictAction::DoNothing),
+            (&[][..], ConflictAction::DoUpdate),
+        ] {
+            let err = DataOperations::insert_row(&pool, insert(duplicate.clone(), columns, action))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, DbViewerError::InvalidQuery(_)));
+        }
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_keyset_cursor() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.pairs (a int, b char(2), PRIMARY KEY (a, b));
+                 INSERT INTO {s}.pairs SELECT g / 2, 'k' || (g % 2) FROM generate_series(0, 6) g;
+                 CREATE TABLE {s}.loose (n int);
+                 INSERT INTO {s}.loose SELECT generate_series(1, 5);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |table: &'static str, cursor: Option<PageCursor>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: table.to_string(),
+                        mode: PaginationMode::OffsetBased {
+                            page: 1,
+                            page_size: Some(3),
+                        },
+                        cursor,
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+                .unwrap()
+            }
+        };
+        let keys = |result: &PaginatedResult| -> Vec<String> {
+            result
+                .rows
+                .iter()
+                .map(|r| format!("{}{}", r["a"], r["b"].as_str().unwrap()))
+                .collect()
+        };
+
+        let first = fetch("pairs", None).await;
+        assert_eq!(keys(&first), vec!["0k0", "0k1", "1k0"]);
+        assert!(first.prev_cursor.is_none());
+        let next = first.next_cursor.clone().unwrap();
+        assert_eq!(
+            next.values,
+            vec![serde_json::json!(1), serde_json::json!("k0")]
+        );
+
+        let second = fetch("pairs", Some(next)).await;
+        assert_eq!(keys(&second), vec!["1k1", "2k0", "2k1"]);
+        let third = fetch("pairs", second.next_cursor.clone()).await;
+        assert_eq!(keys(&third), vec!["3k0"]);
+        assert!(third.next_cursor.is_none());
+        assert_eq!(third.total_count, 7);
+
+        let back = fetch("pairs", third.prev_cursor.clone()).await;
+        assert_eq!(keys(&back), vec!["1k1", "2k0", "2k1"]);
+        assert_eq!(back.next_cursor.unwrap().direction, CursorDirection::Next);
+
+        // Without a primary key the cursor is ignored and OFFSET paging is used
+        let loose = fetch(
+            "loose",
+            Some(PageCursor {
+                values: vec![serde_json::json!(3)],
+                direction: CursorDirection::Next,
+            }),
+        )
+        .await;
+        assert_eq!(loose.rows.len(), 3);
+        assert!(loose.next_cursor.is_none() && loose.prev_cursor.is_none());
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_cursor_column() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.events (id serial PRIMARY KEY, at timestamptz UNIQUE, kind text);
+                 INSERT INTO {s}.events (at, kind)
+                 SELECT '2024-01-01'::timestamptz + g * interval '1 hour', 'k' || (g % 2)
+                 FROM generate_series(1, 7) g;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |column: &'static str,
+                     value: Option<JsonValue>,
+                     direction: CursorDirection,
+                     filters: Option<Vec<FilterGroup>>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "events".to_string(),
+                        mode: PaginationMode::CursorBased {
+                            cursor_column: column.to_string(),
+                            cursor_value: value,
+                            limit: 3,
+                            direction,
+                        },
+                        filters,
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+            }
+        };
+        let ids = |result: &PaginatedResult| -> Vec<i64> {
+            result
+                .rows
+                .iter()
+                .map(|r| r["id"].as_i64().unwrap())
+                .collect()
+        };
+        let value = |cursor: &Option<PageCursor>| cursor.as_ref().unwrap().values[0].clone();
+
+        // Forward from the start by timestamp
+        let first = fetch("at", None, CursorDirection::Next, None)
+            .await
+            .unwrap();
+        assert_eq!(ids(&first), vec![1, 2, 3]);
+        assert!(first.prev_cursor.is_none());
+        assert_eq!(first.total_count, 7);
+        let second = fetch(
+            "at",
+            Some(value(&first.next_cursor)),
+            CursorDirection::Next,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(&second), vec![4, 5, 6]);
+        let third = fetch(
+            "at",
+            Some(value(&second.next_cursor)),
+            CursorDirection::Next,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(&third), vec![7]);
+        assert!(third.next_cursor.is_none());
+
+        // Backward from the last page
+        let back = fetch(
+            "at",
+            Some(value(&third.prev_cursor)),
+            CursorDirection::Prev,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(&back), vec![4, 5, 6]);
+        assert_eq!(value(&back.prev_cursor), second.rows[0]["at"]);
+        let last = fetch("id", None, CursorDirection::Prev, None)
+            .await
+            .unwrap();
+        assert_eq!(ids(&last), vec![5, 6, 7]);
+        assert!(last.next_cursor.is_none());
+
+        // Filters apply to both the page and the count
+        let filtered = fetch(
+            "id",
+            Some(serde_json::json!(2)),
+            CursorDirection::Next,
+            Some(and_group(vec![condition(
+                "kind",
+                FilterOperator::Equals,
+                Some("k1"),
+            )])),
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(&filtered), vec![3, 5, 7]);
+        assert_eq!(filtered.total_count, 4);
+
+        // Past either end there is nothing to return and nowhere to go
+        let empty = fetch(
+            "id",
+            Some(serde_json::json!(7)),
+            CursorDirection::Next,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(empty.rows.is_empty());
+        assert!(empty.next_cursor.is_none() && empty.prev_cursor.is_none());
+        let empty = fetch(
+            "id",
+            Some(serde_json::json!(1)),
+            CursorDirection::Prev,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(empty.rows.is_empty());
+        assert!(empty.next_cursor.is_none() && empty.prev_cursor.is_none());
+
+        let missing = fetch("nope", None, CursorDirection::Next, None).await;
+        assert!(matches!(missing, Err(DbViewerError::InvalidQuery(_))));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[test]
+    fn test_validate_order_expression() {
+        let columns: HashMap<String, String> =
+            [("name", "text"), ("data", "jsonb"), ("Score", "int")]
+                .into_iter()
+                .map(|(c, t)| (c.to_string(), t.to_string()))
+                .collect();
+        let check = |expression: &str| validate_order_expression(expression, &columns);
+        let rejected = |expression: &str| match check(expression) {
+            Err(DbViewerError::InvalidQuery(message)) => message,
+            other => panic!("{} was not rejected: {:?}", expression, other),
+        };
+
+        for ok in [
+            "lower(name)",
+            "data->>'city'",
+            "(data->'stats'->>'visits')::int",
+            "coalesce(\"Score\", 0) * -1",
+            "NAME || 'it''s'",
+            "data #>> '{a,b}'",
+            "length(name)::numeric(10, 2)",
+            "nullif(data->>'tags', '')::text[]",
+        ] {
+            check(ok).unwrap();
+        }
+
+        assert!(rejected("name; DROP TABLE users").contains(";"));
+        assert!(rejected("name -- comment").contains("--"));
+        assert!(rejected("name /* x */").contains("/*"));
+        assert!(rejected("pg_sleep(10)").contains("pg_sleep"));
+        assert!(rejected("(SELECT password FROM users LIMIT 1)").contains("SELECT"));
+        assert!(rejected("missing").contains("missing"));
+        assert!(rejected("score").contains("score"));
+        assert!(rejected("$1").contains("$"));
+        assert!(rejected("data->>'a\\'").contains("\\"));
+        assert!(rejected("lower(name").contains("Incomplete"));
+        assert!(rejected("name)").contains(")"));
+        assert!(rejected("'unterminated").contains("unterminated"));
+        assert!(rejected("  ").contains("empty"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_sample() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.readings (id int PRIMARY KEY, value float8);
+                 INSERT INTO {s}.readings SELECT g, random() FROM generate_series(1, 2000) g;
+                 ANALYZE {s}.readings;
+                 CREATE VIEW {s}.recent AS SELECT * FROM {s}.readings;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |table: &'static str, sample: TableSample| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: table.to_string(),
+                        mode: PaginationMode::OffsetBased {
+                            page: 1,
+                            page_size: Some(1000),
+                        },
+                        sample: Some(sample),
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+            }
+        };
+        let sample = |percentage, method, seed| TableSample {
+            percentage,
+            method,
+            seed,
+        };
+        let ids = |result: &PaginatedResult| -> Vec<i64> {
+            result
+                .rows
+                .iter()
+                .map(|r| r["id"].as_i64().unwrap())
+                .collect()
+        };
+
+        // The count is the table's estimate scaled to the sample, never exact
+        let half = fetch("readings", sample(50.0, SampleMethod::Bernoulli, Some(7)))
+            .await
+            .unwrap();
+        assert_eq!(half.total_count, 1000);
+        assert!(!half.total_count_exact);
+        assert!(half.rows.len() > 800 && half.rows.len() < 1000);
+
+        // The same seed reads the same rows
+        let again = fetch("readings", sample(50.0, SampleMethod::Bernoulli, Some(7)))
+            .await
+            .unwrap();
+        assert_eq!(ids(&half), ids(&again));
+
+        let all = fetch("readings", sample(100.0, SampleMethod::System, None))
+            .await
+            .unwrap();
+        assert_eq!(all.rows.len(), 1000);
+        assert_eq!(all.total_count, 2000);
+
+        let err = fetch("recent", sample(10.0, SampleMethod::System, None))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DbViewerError::InvalidQuery(m) if m.contains("view")));
+        let err = fetch("readings", sample(150.0, SampleMethod::System, None))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DbViewerError::InvalidQuery(_)));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_timeout() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE VIEW {s}.slow AS SELECT g AS id, pg_sleep(0.1)::text AS pause
+                 FROM generate_series(1, 5) g;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |timeout_ms: Option<u32>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "slow".to_string(),
+                        timeout_ms,
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+            }
+        };
+
+        let err = fetch(Some(200)).await.unwrap_err();
+        assert!(matches!(err, DbViewerError::QueryTimeout(ref after) if after == "200ms"));
+        assert_eq!(err.to_string(), "Query timed out after 200ms");
+
+        let result = fetch(Some(10_000)).await.unwrap();
+        assert_eq!(result.rows.len(), 5);
+        assert_eq!(result.total_count, 5);
+
+        // On a caller's connection, cancelling its backend stops the fetch
+        let mut conn = pool.acquire().await.unwrap();
+        let pid = DataOperations::backend_pid(&mut conn).await.unwrap();
+        let cancel_pool = pool.clone();
+        let cancelled = std::sync::Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            flag.store(true, Ordering::SeqCst);
+            DataOperations::cancel_backend(&cancel_pool, pid).await
+        });
+        let err = DataOperations::fetch_paginated(
+            &pool,
+            &PageRequest {
+                schema: schema.clone(),
+                table: "slow".to_string(),
+                timeout_ms: Some(10_000),
+                ..Default::default()
+            },
+            Some(CancellableConn {
+                conn: &mut conn,
+                cancelled: &cancelled,
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert!(canceller.await.unwrap().unwrap());
+        assert!(matches!(err, DbViewerError::Cancelled));
+
+        // The timeout only applied to the fetch's own transaction
+        let (timeout,): (String,) = sqlx::query_as("SHOW statement_timeout")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(timeout, "0");
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_order_expression() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                r#"CREATE TABLE {s}.people (id int PRIMARY KEY, name text, data jsonb);
+                 INSERT INTO {s}.people VALUES
+                   (1, 'bob', '{{"age": 40}}'),
+                   (2, 'Alice', '{{"age": 9}}'),
+                   (3, 'carol', '{{"age": 25}}');"#,
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |order_by: Vec<OrderSpec>, directions: Vec<&str>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            let directions: Vec<String> = directions.into_iter().map(String::from).collect();
+            async move {
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "people".to_string(),
+                        order_by: Some(order_by),
+                        order_direction: Some(directions),
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+            }
+        };
+        let ids = |result: &PaginatedResult| -> Vec<i64> {
+            result
+                .rows
+                .iter()
+                .map(|r| r["id"].as_i64().unwrap())
+                .collect()
+        };
+
+        let by_name = fetch(vec![OrderSpec::Column("name".to_string())], vec![])
+            .await
+            .unwrap();
+        assert_eq!(ids(&by_name), vec![2, 1, 3]);
+        let by_lower = fetch(
+            vec![OrderSpec::Expression("lower(name)".to_string())],
+            vec!["desc"],
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(&by_lower), vec![3, 1, 2]);
+        // Compared as numbers, not as text
+        let by_age = fetch(
+            vec![OrderSpec::Expression("(data->>'age')::int".to_string())],
+            vec![],
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(&by_age), vec![2, 3, 1]);
+
+        let err = fetch(
+            vec![OrderSpec::Expression("name, (SELECT 1)".to_string())],
+            vec![],
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, DbViewerError::InvalidQuery(m) if m.contains("SELECT")));
+
+        // A bare string in JSON is a column, an object is an expression
+        let specs: Vec<OrderSpec> =
+            serde_json::from_str(r#"["name", {"expression": "lower(name)"}]"#).unwrap();
+        assert_eq!(
+            specs,
+            vec![
+                OrderSpec::Column("name".to_string()),
+                OrderSpec::Expression("lower(name)".to_string())
+            ]
+        );
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_keyset() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.scores (id int PRIMARY KEY, team text, points int);
+                 INSERT INTO {s}.scores VALUES
+                    (1, 'red', 10), (2, 'blue', 30), (3, 'red', 30),
+                    (4, 'blue', 20), (5, 'red', 20), (6, 'green', 30);
+                 CREATE TABLE {s}.loose (n int);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |order_by: Vec<&str>, directions: Vec<&str>, cursor: Option<PageCursor>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            let order_by: Vec<String> = order_by.into_iter().map(String::from).collect();
+            let directions: Vec<String> = directions.into_iter().map(String::from).collect();
+            async move {
+                DataOperations::fetch_keyset(
+                    &pool,
+                    &KeysetRequest {
+                        schema,
+                        table: "scores".to_string(),
+                        page_size: Some(2),
+                        order_by: Some(order_by),
+                        order_direction: Some(directions),
+                        cursor,
+                        ..Default::default()
+                    },
+                )
+                .await
+            }
+        };
+        let ids = |page: &KeysetPage| -> Vec<i64> {
+            page.rows
+                .iter()
+                .map(|r| r["id"].as_i64().unwrap())
+                .collect()
+        };
+
+        // points DESC, team ASC, then id as the tie-breaker
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = fetch(vec!["points", "team"], vec!["desc", "asc"], cursor)
+                .await
+                .unwrap();
+            seen.extend(ids(&page));
+            match page.next_cursor {
+                Some(next) => {
+                    assert_eq!(next.values.len(), 3);
+                    cursor = Some(next);
+                }
+                None => break,
+            }
+        }
+        assert_eq!(seen, vec![2, 6, 3, 4, 5, 1]);
+
+        // Without a sort column the primary key is used
+        let first = fetch(vec![], vec![], None).await.unwrap();
+        assert_eq!(ids(&first), vec![1, 2]);
+        assert_eq!(
+            first.next_cursor.as_ref().unwrap().values,
+            vec![serde_json::json!(2)]
+        );
+        let second = fetch(vec![], vec![], first.next_cursor).await.unwrap();
+        assert_eq!(ids(&second), vec![3, 4]);
+
+        let prev = PageCursor {
+            values: vec![serde_json::json!(2)],
+            direction: CursorDirection::Prev,
+        };
+        let err = fetch(vec![], vec![], Some(prev)).await;
+        assert!(matches!(err, Err(DbViewerError::InvalidQuery(_))));
+        let err = fetch(vec!["nope"], vec![], None).await;
+        assert!(matches!(err, Err(DbViewerError::InvalidQuery(_))));
+
+        let no_key = DataOperations::fetch_keyset(
+            &pool,
+            &KeysetRequest {
+                schema: schema.clone(),
+                table: "loose".to_string(),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(matches!(no_key, Err(DbViewerError::InvalidQuery(_))));
+
+        // NULL sort values are carried in the cursor and paged past like any other
+        pool.execute(
+            format!(
+                "INSERT INTO {s}.scores VALUES
+                    (7, 'red', NULL), (8, 'blue', NULL), (9, 'green', NULL);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let read_all = |direction: &'static str| {
+            let fetch = &fetch;
+            async move {
+                let mut seen = Vec::new();
+                let mut cursor = None;
+                loop {
+                    let page = fetch(vec!["points"], vec![direction], cursor)
+                        .await
+                        .unwrap();
+                    seen.extend(ids(&page));
+                    match page.next_cursor {
+                        Some(next) => cursor = Some(next),
+                        None => return seen,
+                    }
+                }
+            }
+        };
+        assert_eq!(read_all("asc").await, vec![1, 4, 5, 2, 3, 6, 7, 8, 9]);
+        assert_eq!(read_all("desc").await, vec![7, 8, 9, 2, 3, 6, 4, 5, 1]);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_chunks_in_one_transaction() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(format!("CREATE TABLE {}.nums (n int PRIMARY KEY)", schema).as_str())
+            .await
+            .unwrap();
+
+        let rows = |range: std::ops::Range<i64>| -> Vec<serde_json::Map<String, JsonValue>> {
+            range
+                .map(|n| serde_json::json!({"n": n}).as_object().unwrap().clone())
+                .collect()
+        };
+        let count = || {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                sqlx::query_as::<_, (i64,)>(&format!("SELECT count(*) FROM {}.nums", schema))
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+                    .0
+            }
+        };
+
+        let mut progress = Vec::new();
+        let inserted = DataOperations::bulk_insert(
+            &pool,
+            BulkInsertRequest {
+                schema: schema.clone(),
+                table: "nums".to_string(),
+                rows: rows(0..2500),
+                chunk_size: Some(1000),
+                conflict_target: Vec::new(),
+                conflict_action: ConflictAction::DoNothing,
+                copy_threshold: None,
+                missing_values: MissingValue::Null,
+            },
+            |p| progress.push(p.inserted),
+        )
+        .await
+        .unwrap();
+        assert_eq!(inserted, 2500);
+        assert_eq!(progress, [1000, 2000, 2500]);
+
+        // The duplicate is in the last chunk; the earlier chunks roll back too
+        let mut batch = rows(2500..4500);
+        batch.push(serde_json::json!({"n": 0}).as_object().unwrap().clone());
+        let err = DataOperations::bulk_insert(
+            &pool,
+            BulkInsertRequest {
+                schema: schema.clone(),
+                table: "nums".to_string(),
+                rows: batch,
+                chunk_size: None,
+                conflict_target: Vec::new(),
+                conflict_action: ConflictAction::DoNothing,
+                copy_threshold: None,
+                missing_values: MissingValue::Null,
+            },
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, DbViewerError::InvalidQuery(_)), "{err}");
+        assert!(
+            err.to_string().contains("Chunk 2 (rows 2000 to 2000)"),
+            "{err}"
+        );
+        assert_eq!(count().await, 2500);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_copy() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {}.notes (id int PRIMARY KEY, body text, meta jsonb, done bool)",
+                schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let copy = |rows: Vec<JsonValue>| BulkInsertRequest {
+            schema: schema.clone(),
+            table: "notes".to_string(),
+            rows: rows
+                .into_iter()
+                .map(|r| r.as_object().unwrap().clone())
+                .collect(),
+            chunk_size: None,
+            conflict_target: Vec::new(),
+            conflict_action: ConflictAction::DoNothing,
+            copy_threshold: Some(1),
+            missing_values: MissingValue::Null,
+        };
+
+        let inserted = DataOperations::bulk_insert(
+            &pool,
+            copy(vec![
+                serde_json::json!({
+                    "id": 1,
+                    "body": "say \"hi\", then\nleave",
+                    "meta": {"k": "v,\""},
+                    "done": true,
+                }),
+                serde_json::json!({"id": 2, "body": "", "meta": null, "done": false}),
+                serde_json::json!({"id": 3, "body": null}),
+            ]),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(inserted, 3);
+
+        type NoteRow = (i32, Option<String>, Option<JsonValue>, Option<bool>);
+        let rows: Vec<NoteRow> = sqlx::query_as(&format!(
+            "SELECT id, body, meta, done FROM {}.notes ORDER BY id",
+            schema
+        ))
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rows[0].1.as_deref(), Some("say \"hi\", then\nleave"));
+        assert_eq!(rows[0].2, Some(serde_json::json!({"k": "v,\""})));
+        assert_eq!(rows[0].3, Some(true));
+        // Empty strings and NULLs stay distinct
+        assert_eq!(rows[1].1.as_deref(), Some(""));
+        assert_eq!(rows[1].2, None);
+        assert_eq!(rows[2].1, None);
+        assert_eq!(rows[2].3, None);
+
+        let err = DataOperations::bulk_insert(
+            &pool,
+            copy(vec![
+                serde_json::json!({"id": 4}),
+                serde_json::json!({"id": 5}),
+                serde_json::json!({"id": "six"}),
+            ]),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Row 2"), "{err}");
+        let err = DataOperations::bulk_insert(
+            &pool,
+            copy(vec![
+                serde_json::json!({"id": 7}),
+                serde_json::json!({"id": 1}),
+            ]),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Row 1"), "{err}");
+
+        let (count,): (i64,) = sqlx::query_as(&format!("SELECT count(*) FROM {}.notes", schema))
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_mixed_row_shapes() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {}.mixed (id serial PRIMARY KEY, a text, b text DEFAULT 'dflt', c int)",
+                schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let insert = |missing_values: MissingValue, copy_threshold: Option<usize>| {
+            let schema = schema.clone();
+            let pool = pool.clone();
+            async move {
+                pool.execute(format!("TRUNCATE {}.mixed", schema).as_str())
+                    .await
+                    .unwrap();
+                let rows = [
+                    serde_json::json!({"a": "x"}),
+                    serde_json::json!({"a": "y", "b": "z"}),
+                    serde_json::json!({"c": 3}),
+                ];
+                let request = BulkInsertRequest {
+                    schema: schema.clone(),
+                    table: "mixed".to_string(),
+                    rows: rows
+                        .iter()
+                        .map(|r| r.as_object().unwrap().clone())
+                        .collect(),
+                    chunk_size: Some(1),
+                    conflict_target: Vec::new(),
+                    conflict_action: ConflictAction::DoNothing,
+                    copy_threshold,
+                    missing_values,
+                };
+                assert_eq!(
+                    DataOperations::bulk_insert(&pool, request, |_| {})
+                        .await
+                        .unwrap(),
+                    3
+                );
+                let rows: Vec<(Option<String>, Option<String>, Option<i32>)> = sqlx::query_as(
+                    &format!("SELECT a, b, c FROM {}.mixed ORDER BY a NULLS LAST", schema),
+                )
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+                rows
+            }
+        };
+
+        // Keys from later rows are inserted too, even across chunks
+        let rows = insert(MissingValue::Null, None).await;
+        assert_eq!(
+            rows,
+            [
+                (Some("x".to_string()), None, None),
+                (Some("y".to_string()), Some("z".to_string()), None),
+                (None, None, Some(3)),
+            ]
+        );
+        assert_eq!(insert(MissingValue::Null, Some(1)).await, rows);
+
+        // COPY can't write DEFAULT, so this falls back to INSERT
+        for copy_threshold in [None, Some(1)] {
+            assert_eq!(
+                insert(MissingValue::Default, copy_threshold).await,
+                [
+                    (Some("x".to_string()), Some("dflt".to_string()), None),
+                    (Some("y".to_string()), Some("z".to_string()), None),
+                    (None, Some("dflt".to_string()), Some(3)),
+                ]
+            );
+        }
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_on_conflict() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.stock (sku text PRIMARY KEY, qty int);
+                 INSERT INTO {s}.stock VALUES ('a', 1), ('b', 2);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let insert = |conflict_target: Vec<String>, conflict_action: ConflictAction| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            let rows = serde_json::json!([{"sku": "b", "qty": 20}, {"sku": "c", "qty": 30}]);
+            async move {
+                DataOperations::bulk_insert(
+                    &pool,
+                    BulkInsertRequest {
+                        schema,
+                        table: "stock".to_string(),
+                        rows: rows
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .map(|r| r.as_object().unwrap().clone())
+                            .collect(),
+                        chunk_size: None,
+                        conflict_target,
+                        conflict_action,
+                        copy_threshold: None,
+                        missing_values: MissingValue::Null,
+                    },
+                    |_| {},
+                )
+                .await
+            }
+        };
+        let stock = || {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                sqlx::query_as::<_, (String, i32)>(&format!(
+                    "SELECT sku, qty FROM {}.stock ORDER BY sku",
+                    schema
+                ))
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+            }
+        };
+        let sku = vec!["sku".to_string()];
+
+        // Without a conflict target the duplicate fails the batch
+        assert!(insert(Vec::new(), ConflictAction::DoUpdate).await.is_err());
+
+        let inserted = insert(sku.clone(), ConflictAction::DoNothing)
+            .await
+            .unwrap();
+        assert_eq!(inserted, 1);
+        assert_eq!(stock().await[1], ("b".to_string(), 2));
+
+        let written = insert(sku, ConflictAction::DoUpdate).await.unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(
+            stock().await,
+            [
+                ("a".to_string(), 1),
+                ("b".to_string(), 20),
+                ("c".to_string(), 30)
+            ]
+        );
+
+        let err = insert(vec!["id".to_string()], ConflictAction::DoNothing)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DbViewerError::InvalidQuery(ref m) if m.contains("id")),
+            "{err}"
+        );
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_import_csv() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.people (id int PRIMARY KEY, name text, bio text, age int);
+                 INSERT INTO {s}.people VALUES (1, 'existing', NULL, 99);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("people.csv");
+        std::fs::write(
+            &path,
+            "id;name;bio;age\n\
+             1;Ann;\"likes \"\"quotes\"\"; and semicolons\";30\n\
+             2;Bob;\"line one\nline two\";\\N\n\
+             3;only;two\n\
+             4;O'Brien;\\N;41\n",
+        )
+        .unwrap();
+        let request = |on_conflict| ImportCsvRequest {
+            schema: schema.clone(),
+            table: "people".to_string(),
+            file_path: path.to_string_lossy().into_owned(),
+            has_header: true,
+            delimiter: ';',
+            null_string: "\\N".to_string(),
+            on_conflict,
+            columns: None,
+        };
+        let people = || async {
+            sqlx::query_as::<_, (i32, String, Option<String>, Option<i32>)>(&format!(
+                "SELECT id, name, bio, age FROM {}.people ORDER BY id",
+                schema
+            ))
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+        };
+
+        // Fail rolls back everything, including rows from the same batch
+        let failed = DataOperations::import_csv(&pool, request(ConflictStrategy::Fail)).await;
+        assert!(matches!(failed, Err(DbViewerError::Database(_))));
+        assert_eq!(people().await.len(), 1);
+
+        let result = DataOperations::import_csv(&pool, request(ConflictStrategy::Skip))
+            .await
+            .unwrap();
+        assert_eq!(result.rows_inserted, 2);
+        // The conflicting row 1 and the short record on line 5
+        assert_eq!(result.rows_skipped, 2);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].line, 5);
+        assert_eq!(
+            people().await,
+            vec![
+                (1, "existing".to_string(), None, Some(99)),
+                (
+                    2,
+                    "Bob".to_string(),
+                    Some("line one\nline two".to_string()),
+                    None
+                ),
+                (4, "O'Brien".to_string(), None, Some(41)),
+            ]
+        );
+
+        let result = DataOperations::import_csv(&pool, request(ConflictStrategy::Replace))
+            .await
+            .unwrap();
+        assert_eq!(result.rows_inserted, 3);
+        assert_eq!(
+            people().await[0],
+            (
+                1,
+                "Ann".to_string(),
+                Some("likes \"quotes\"; and semicolons".to_string()),
+                Some(30)
+            )
+        );
+
+        // Without a header the columns come from the request
+        let headless = dir.path().join("headless.csv");
+        std::fs::write(&headless, "5,Eve\n6,\n").unwrap();
+        let result = DataOperations::import_csv(
+            &pool,
+            ImportCsvRequest {
+                file_path: headless.to_string_lossy().into_owned(),
+                has_header: false,
+                delimiter: ',',
+                null_string: String::new(),
+                columns: Some(vec!["id".to_string(), "name".to_string()]),
+                ..request(ConflictStrategy::Fail)
+            },
+        )
+        .await;
+        // name is nullable, so the empty field imports as NULL
+        assert_eq!(result.unwrap().rows_inserted, 2);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_stream_to_csv() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.events (id int PRIMARY KEY, note text);
+                 INSERT INTO {s}.events SELECT g, 'row ' || g FROM generate_series(1, 10000) g;
+                 UPDATE {s}.events SET note = 'say \"hi\", bye' WHERE id = 1;
+                 UPDATE {s}.events SET note = NULL WHERE id = 2;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.csv");
+        let mut progress = Vec::new();
+        let written = DataOperations::stream_to_csv(&pool, &schema, "events", None, &path, |n| {
+            progress.push(n)
+        })
+        .await
+        .unwrap();
+        assert_eq!(written, 10_000);
+        assert_eq!(progress.len(), 10);
+        assert_eq!(progress.last(), Some(&10_000));
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 10_001);
+        assert_eq!(lines[0], "id,note");
+        assert!(lines.contains(&"1,\"say \"\"hi\"\", bye\""));
+        assert!(lines.contains(&"2,"));
+
+        // Filters apply, and the header is still written when nothing matches
+        let none = condition("id", FilterOperator::GreaterThan, Some("20000"));
+        let written = DataOperations::stream_to_csv(
+            &pool,
+            &schema,
+            "events",
+            Some(&and_group(vec![none])),
+            &path,
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(written, 0);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "id,note\n");
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_bytea_preview_and_download_cell() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.files (id int PRIMARY KEY, kind text, data bytea);
+                 INSERT INTO {s}.files VALUES
+                    (1, 'big', decode(repeat('ab', 1000), 'hex')),
+                    (2, 'small', '\\xcafe'),
+                    (3, 'small', NULL);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        // The grid asks for a cell limit and gets previews
+        let result = DataOperations::fetch_paginated(
+            &pool,
+            &PageRequest {
+                schema: schema.clone(),
+                table: "files".to_string(),
+                columns: Some(vec!["data".to_string()]),
+                max_cell_bytes: Some(16 * 1024),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            result.rows[0]["data"],
+            format!("\\x{}… (1000 bytes)", "ab".repeat(BYTEA_PREVIEW_BYTES))
+        );
+        assert_eq!(result.rows[1]["data"], "\\xcafe");
+        assert_eq!(result.rows[2]["data"], JsonValue::Null);
+
+        // Query results keep the whole value
+        let result = DataOperations::execute_raw_query(
+            &mut pool.acquire().await.unwrap(),
+            None,
+            "test",
+            &format!("SELECT data FROM {}.files ORDER BY id", schema),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows[0]["data"], format!("\\x{}", "ab".repeat(1000)));
+        assert_eq!(result.rows[1]["data"], "\\xcafe");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cell.bin");
+        let download = |where_clause: JsonValue| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            let path = path.clone();
+            async move {
+                DataOperations::download_cell(
+                    &pool,
+                    &schema,
+                    "files",
+                    where_clause.as_object().unwrap(),
+                    "data",
+                    &path,
+                )
+                .await
+            }
+        };
+
+        let written = download(serde_json::json!({"id": 1})).await.unwrap();
+        assert_eq!(written, 1000);
+        assert_eq!(std::fs::read(&path).unwrap(), vec![0xab; 1000]);
+
+        let err = download(serde_json::json!({"kind": "small"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("more than one row"), "{err}");
+        assert!(download(serde_json::json!({"id": 3})).await.is_err());
+        assert!(download(serde_json::json!({"id": 4})).await.is_err());
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_truncated_cells() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.docs (id int PRIMARY KEY, body varchar(1000), meta jsonb, n int);
+                 INSERT INTO {s}.docs VALUES
+                     (1, repeat('x', 500), '{{\"a\": 1}}', 1),
+                     (2, 'short', jsonb_build_object('blob', repeat('y', 500)), 2);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let fetch = |columns: Option<Vec<String>>, max_cell_bytes: Option<usize>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "docs".to_string(),
+                        columns,
+                        max_cell_bytes,
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let names = |columns: &[ColumnMeta]| -> Vec<String> {
+            columns.iter().map(|c| c.name.clone()).collect()
+        };
+
+        let result = fetch(None, Some(100)).await;
+        assert_eq!(names(&result.columns), ["id", "body", "meta", "n"]);
+        assert_eq!(result.rows[0]["body"], "x".repeat(100));
+        assert_eq!(result.rows[0]["meta"], serde_json::json!({"a": 1}));
+        assert_eq!(result.rows[1]["body"], "short");
+        let meta = result.rows[1]["meta"].as_str().unwrap();
+        assert!(meta.starts_with("{\"blob\": \"yyy"));
+        assert_eq!(meta.len(), 100);
+        assert_eq!(result.rows[1].len(), 4);
+        assert_eq!(
+            result.truncated_cells,
+            [
+                TruncatedCell {
+                    row: 0,
+                    column: "body".to_string()
+                },
+                TruncatedCell {
+                    row: 1,
+                    column: "meta".to_string()
+                },
+            ]
+        );
+
+        let result = fetch(Some(vec!["n".to_string(), "body".to_string()]), Some(100)).await;
+        assert_eq!(names(&result.columns), ["n", "body"]);
+        assert_eq!(result.truncated_cells.len(), 1);
+
+        // Without a limit values come back whole
+        let result = fetch(None, None).await;
+        assert!(result.truncated_cells.is_empty());
+        assert_eq!(result.rows[0]["body"], "x".repeat(500));
+        let result = fetch(None, Some(0)).await;
+        assert_eq!(result.rows[0]["body"], "x".repeat(500));
+
+        let full = DataOperations::fetch_full_cell(
+            &pool,
+            &schema,
+            "docs",
+            serde_json::json!({"id": 2}).as_object().unwrap(),
+            "meta",
+        )
+        .await
+        .unwrap();
+        assert_eq!(full, serde_json::json!({"blob": "y".repeat(500)}));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_referenced_row() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.users (id bigint PRIMARY KEY, name text);
+                 CREATE TABLE {s}.posts (id int, user_id bigint REFERENCES {s}.users (id));
+                 INSERT INTO {s}.users VALUES (42, 'Ada');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let lookup = |column: &'static str, value: JsonValue| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                DataOperations::fetch_referenced_row(&pool, &schema, "posts", column, &value).await
+            }
+        };
+
+        let row = lookup("user_id", serde_json::json!(42))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(row["name"], "Ada");
+        assert!(lookup("user_id", serde_json::json!(7))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(lookup("user_id", JsonValue::Null).await.unwrap().is_none());
+
+        // Text is parsed as the key's type, never run as SQL
+        let row = lookup("user_id", serde_json::json!("42"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(row["id"], 42);
+        assert!(lookup("user_id", serde_json::json!("0 OR true"))
+            .await
+            .is_err());
+
+        let err = lookup("id", serde_json::json!(1)).await.unwrap_err();
+        assert!(err.to_string().contains("no foreign key"), "{err}");
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_column_stats() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.events (id int, kind text, payload json);
+                 INSERT INTO {s}.events
+                 SELECT g, CASE WHEN g % 10 = 0 THEN NULL WHEN g % 3 = 0 THEN 'click' ELSE 'view' END,
+                        '{{}}'
+                 FROM generate_series(1, 100) g;
+                 ANALYZE {s}.events;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let stats = DataOperations::column_stats(&pool, &schema, "events", "kind", None)
+            .await
+            .unwrap();
+        assert!(stats.exact);
+        assert_eq!(stats.row_count, 100);
+        assert_eq!(stats.null_count, Some(10));
+        assert_eq!(stats.distinct_count, Some(2));
+        assert_eq!(
+            (stats.min.as_str(), stats.max.as_str()),
+            (Some("click"), Some("view"))
+        );
+        let common: Vec<(&JsonValue, i64)> = stats
+            .most_common
+            .iter()
+            .map(|c| (&c.value, c.count))
+            .collect();
+        assert_eq!(
+            common,
+            [
+                (&serde_json::json!("view"), 60),
+                (&serde_json::json!("click"), 30)
+            ]
+        );
+
+        let stats = DataOperations::column_stats(&pool, &schema, "events", "id", None)
+            .await
+            .unwrap();
+        assert_eq!(
+            (stats.min, stats.max),
+            (serde_json::json!(1), serde_json::json!(100))
+        );
+
+        // json has no equality or ordering, so only the counts are available
+        let stats = DataOperations::column_stats(&pool, &schema, "events", "payload", None)
+            .await
+            .unwrap();
+        assert_eq!(stats.null_count, Some(0));
+        assert_eq!(stats.distinct_count, None);
+        assert_eq!(stats.min, JsonValue::Null);
+        assert!(stats.most_common.is_empty());
+
+        // Above the scan limit the answer comes from ANALYZE
+        let stats = DataOperations::column_stats(&pool, &schema, "events", "kind", Some(10))
+            .await
+            .unwrap();
+        assert!(!stats.exact);
+        assert_eq!(stats.row_count, 100);
+        assert_eq!(stats.null_count, Some(10));
+        assert_eq!(stats.distinct_count, Some(2));
+        assert_eq!(stats.most_common[0].value, "view");
+        assert_eq!(stats.most_common[0].count, 60);
+
+        let err = DataOperations::column_stats(&pool, &schema, "events", "missing", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DbViewerError::InvalidQuery(_)));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_get_referencing_rows() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.users (id int PRIMARY KEY, email text UNIQUE);
+                 CREATE TABLE {s}.posts (id int, author_id int REFERENCES {s}.users (id));
+                 CREATE TABLE {s}.invites (email text REFERENCES {s}.users (email));
+                 INSERT INTO {s}.users VALUES
+                    (1, 'a@example.com'), (2, 'b@example.com'), (3, 'o''hara@example.com');
+                 INSERT INTO {s}.posts SELECT g, 1 + g % 2 FROM generate_series(1, 25) g;
+                 INSERT INTO {s}.invites VALUES ('o''hara@example.com');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let key = serde_json::json!({"id": 2});
+        let results =
+            DataOperations::get_referencing_rows(&pool, &schema, "users", key.as_object().unwrap())
+                .await
+                .unwrap();
+        assert_eq!(results.len(), 2);
+
+        // The invites key is the email column, which the lookup key lacks
+        assert_eq!(results[0].table, "invites");
+        assert!(results[0].error.as_deref().unwrap().contains("email"));
+
+        let posts = &results[1];
+        assert_eq!(posts.table, "posts");
+        assert_eq!(posts.constraint, "posts_author_id_fkey");
+        assert_eq!(posts.count, 13);
+        assert_eq!(posts.sample_rows.len(), 10);
+        assert!(posts.sample_rows.iter().all(|r| r["author_id"] == 2));
+        assert!(posts.sample_rows.iter().all(|r| r.len() == 2));
+        assert!(posts.error.is_none());
+
+        let key = serde_json::json!({"id": 3, "email": "o'hara@example.com"});
+        let results =
+            DataOperations::get_referencing_rows(&pool, &schema, "users", key.as_object().unwrap())
+                .await
+                .unwrap();
+        assert_eq!(results[0].count, 1);
+        assert_eq!(results[0].sample_rows[0]["email"], "o'hara@example.com");
+        assert_eq!(results[1].count, 0);
+        assert!(results[1].sample_rows.is_empty());
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_count_modes() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.nums (n int PRIMARY KEY);
+                 INSERT INTO {s}.nums SELECT generate_series(1, 500);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch =
+            |filters: Option<Vec<FilterGroup>>, count_mode: CountMode, hint: Option<i64>| {
+                let pool = pool.clone();
+                let schema = schema.clone();
+                async move {
+                    DataOperations::fetch_paginated(
+                        &pool,
+                        &PageRequest {
+                            schema: schema.clone(),
+                            table: "nums".to_string(),
+                            mode: PaginationMode::OffsetBased {
+                                page: 2,
+                                page_size: Some(100),
+                            },
+                            filters,
+                            count_mode,
+                            total_count_hint: hint,
+                            ..Default::default()
+                        },
+                        None,
+                    )
+                    .await
+                    .unwrap()
+                }
+            };
+
+        let exact = fetch(None, CountMode::Exact, None).await;
+        assert_eq!((exact.total_count, exact.total_count_exact), (500, true));
+
+        // A hint from an earlier page is passed through without counting
+        let hinted = fetch(None, CountMode::Exact, Some(480)).await;
+        assert_eq!((hinted.total_count, hinted.total_count_exact), (480, true));
+        assert_eq!(hinted.total_pages, 5);
+        assert_eq!(hinted.rows.len(), 100);
+
+        pool.execute(format!("ANALYZE {}.nums", schema).as_str())
+            .await
+            .unwrap();
+        let estimated = fetch(None, CountMode::Estimated, None).await;
+        assert_eq!(
+            (estimated.total_count, estimated.total_count_exact),
+            (500, false)
+        );
+
+        let filtered = fetch(
+            Some(and_group(vec![condition(
+                "n",
+                FilterOperator::GreaterThan,
+                Some("250"),
+            )])),
+            CountMode::Estimated,
+            None,
+        )
+        .await;
+        assert!(!filtered.total_count_exact);
+        assert!(filtered.total_count > 0 && filtered.total_count <= 500);
+
+        let uncounted = fetch(None, CountMode::None, None).await;
+        assert_eq!(uncounted.rows.len(), 100);
+        assert_eq!(
+            (uncounted.total_count, uncounted.total_count_exact),
+            (201, false)
+        );
+        assert_eq!(uncounted.total_pages, 3);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_clamps_page_and_size() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.nums (n int PRIMARY KEY);
+                 INSERT INTO {s}.nums SELECT generate_series(1, 30);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |page: i64, page_size: Option<i64>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "nums".to_string(),
+                        mode: PaginationMode::OffsetBased { page, page_size },
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        for page in [0, -5] {
+            let result = fetch(page, Some(10)).await;
+            assert_eq!(result.page, 1);
+            assert_eq!(result.rows[0]["n"], 1);
+            assert_eq!(result.rows.len(), 10);
+        }
+
+        let result = fetch(2, Some(0)).await;
+        assert_eq!((result.page_size, result.total_pages), (1, 30));
+        assert_eq!(result.rows[0]["n"], 2);
+
+        let result = fetch(1, Some(1_000_000)).await;
+        assert_eq!(result.page_size, MAX_PAGE_SIZE);
+        assert_eq!(result.rows.len(), 30);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_selects_columns() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.docs (id int PRIMARY KEY, title text, body text);
+                 INSERT INTO {s}.docs SELECT g, 'doc ' || g, repeat('x', 1000)
+                 FROM generate_series(1, 5) g;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = |columns: Vec<&str>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            let columns: Vec<String> = columns.into_iter().map(String::from).collect();
+            async move {
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "docs".to_string(),
+                        mode: PaginationMode::OffsetBased {
+                            page: 1,
+                            page_size: Some(2),
+                        },
+                        columns: Some(columns),
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+            }
+        };
+
+        let result = fetch(vec!["id", "title"]).await.unwrap();
+        let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["id", "title"]);
+        assert_eq!(result.rows[0].len(), 2);
+        assert_eq!(result.total_count, 5);
+        assert!(result.next_cursor.is_some());
+
+        // Without the primary key there is nothing to build a cursor from
+        let result = fetch(vec!["title"]).await.unwrap();
+        assert_eq!(result.rows[0]["title"], "doc 1");
+        assert!(result.next_cursor.is_none());
+
+        let result = fetch(Vec::new()).await.unwrap();
+        assert_eq!(result.columns.len(), 3);
+
+        let err = fetch(vec!["id", "missing_col"]).await.unwrap_err();
+        assert!(err.to_string().contains("missing_col"), "{err}");
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_paginated_search() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.people (id int PRIMARY KEY, name text, meta jsonb, photo bytea);
+                 INSERT INTO {s}.people VALUES
+                    (1, 'Alice', '{{\"city\": \"Paris\"}}', NULL),
+                    (2, 'Bob', '{{\"city\": \"Malibu\"}}', NULL),
+                    (3, '100% Carol', NULL, '\\x616c69'),
+                    (12, 'Dave', NULL, NULL);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let search = |text: &str, filters: Option<Vec<FilterGroup>>| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            let text = text.to_string();
+            async move {
+                let result = DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "people".to_string(),
+                        filters,
+                        search: Some(text),
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+                .unwrap();
+                assert_eq!(result.total_count, result.rows.len() as i64);
+                result
+                    .rows
+                    .iter()
+                    .map(|r| r["id"].as_i64().unwrap())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        // Matches text, jsonb and numbers; bytea ("ali" in hex) is skipped
+        assert_eq!(search("ALI", None).await, [1, 2]);
+        assert_eq!(search("paris", None).await, [1]);
+        assert_eq!(search("2", None).await, [2, 12]);
+        // LIKE wildcards are literal
+        assert_eq!(search("0%", None).await, [3]);
+        assert_eq!(search("_", None).await, Vec::<i64>::new());
+        // Blank search is ignored
+        assert_eq!(search("  ", None).await.len(), 4);
+
+        let filters = and_group(vec![condition(
+            "id",
+            FilterOperator::GreaterThan,
+            Some("1"),
+        )]);
+        assert_eq!(search("ali", Some(filters)).await, [2]);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_numeric_values_keep_precision() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let literals = [
+            "12345.6789",
+            "-0.000000000000000000000000000001",
+            "42",
+            "12.50",
+            "123456789012345678901234567890123456789.123456789",
+            "-98765432109876543210",
+            "NaN",
+            "Infinity",
+            "-Infinity",
+        ];
+        for literal in literals {
+            let rows = sqlx::query(&format!("SELECT '{}'::numeric AS n", literal))
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+            let (rows, columns) = rows_to_json(&rows);
+            assert_eq!(columns[0].data_type, "NUMERIC");
+            assert_eq!(rows[0]["n"], JsonValue::String(literal.to_string()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_numeric_round_trip() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {}.ledger (id int PRIMARY KEY, amount numeric, fee money)",
+                schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let value = "12345678901234567890.123456789";
+        let data = serde_json::json!({"id": 1, "amount": value, "fee": "-1234567.5"});
+        DataOperations::insert_row(
+            &pool,
+            InsertRequest {
+                schema: schema.clone(),
+                table: "ledger".to_string(),
+                data: data.as_object().unwrap().clone(),
+                on_conflict: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let request = PageRequest {
+            schema: schema.clone(),
+            table: "ledger".to_string(),
+            ..Default::default()
+        };
+        let fetch = || DataOperations::fetch_paginated(&pool, &request, None);
+        let fetched = fetch().await.unwrap();
+        assert_eq!(fetched.columns[1].data_type, "NUMERIC");
+        assert_eq!(fetched.rows[0]["amount"], value);
+        assert_eq!(fetched.rows[0]["fee"], "-1234567.50");
+
+        // Write back what was displayed, matching on the displayed value too
+        let edited = "-0.000000000000000000001";
+        DataOperations::update_row(
+            &pool,
+            UpdateRequest {
+                schema: schema.clone(),
+                table: "ledger".to_string(),
+                data: serde_json::json!({"amount": edited})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+                where_clause: serde_json::json!({"amount": fetched.rows[0]["amount"]})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+                returning: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        let fetched = fetch().await.unwrap();
+        assert_eq!(fetched.rows[0]["amount"], edited);
+        let (text,): (String,) =
+            sqlx::query_as(&format!("SELECT amount::text FROM {}.ledger", schema))
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(text, edited);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_distinct_values() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.tickets (id serial, status text);
+                 INSERT INTO {s}.tickets (status) VALUES
+                    ('open'), ('open'), ('on_hold'), ('onboard'), ('closed'), (NULL);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let all = DataOperations::distinct_values(&pool, &schema, "tickets", "status", None, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            all.values,
+            vec![
+                serde_json::json!("closed"),
+                serde_json::json!("on_hold"),
+                serde_json::json!("onboard"),
+                serde_json::json!("open"),
+                JsonValue::Null,
+            ]
+        );
+        assert!(!all.limit_reached);
+
+        // `_` in the prefix is literal, not a wildcard
+        let prefixed =
+            DataOperations::distinct_values(&pool, &schema, "tickets", "status", Some("ON_"), 10)
+                .await
+                .unwrap();
+        assert_eq!(prefixed.values, vec![serde_json::json!("on_hold")]);
+
+        let limited =
+            DataOperations::distinct_values(&pool, &schema, "tickets", "status", Some("o"), 2)
+                .await
+                .unwrap();
+        assert_eq!(limited.values.len(), 2);
+        assert!(limited.limit_reached);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_explain_query() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+
+        let plan = DataOperations::explain_query(&pool, "SELECT 1;", true, false, true)
+            .await
+            .unwrap();
+        let root = &plan[0];
+        assert!(root.get("Plan").is_some());
+        assert!(root.get("Execution Time").is_some());
+
+        let plan = DataOperations::explain_query(&pool, "SELECT 1", false, true, false)
+            .await
+            .unwrap();
+        assert!(plan[0].get("Plan").is_some());
+        assert!(plan[0]["Plan"].get("Output").is_some());
+        assert!(plan[0].get("Execution Time").is_none());
+
+        // Analyzing a mutation runs it, but the changes are rolled back
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.items (id int); INSERT INTO {s}.items VALUES (1), (2);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let count = || {
+            let pool = pool.clone();
+            let sql = format!("SELECT count(*) FROM {}.items", schema);
+            async move {
+                sqlx::query_scalar::<_, i64>(&sql)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        let plan = DataOperations::explain_query(
+            &pool,
+            &format!("DELETE FROM {}.items", schema),
+            true,
+            false,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(plan[0]["Plan"]["Plans"][0]["Actual Rows"], 2);
+        assert_eq!(count().await, 2);
+
+        // Without analyze a mutation is refused
+        let err = DataOperations::explain_query(
+            &pool,
+            &format!("DELETE FROM {}.items", schema),
+            false,
+            false,
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, DbViewerError::InvalidQuery(_)));
+        assert_eq!(count().await, 2);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_array_columns_decode_to_json_arrays() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let rows = sqlx::query(
+            "SELECT '{a,NULL,c}'::text[] AS t, '{1,NULL,3}'::int4[] AS i, \
+                    '{9000000000}'::int8[] AS b, '{true,false}'::bool[] AS f, \
+                    '{1.5}'::float8[] AS d, \
+                    '{6ba7b810-9dad-11d1-80b4-00c04fd430c8}'::uuid[] AS u, \
+                    '{}'::int4[] AS empty, NULL::text[] AS missing",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        let (rows, _) = rows_to_json(&rows);
+        let row = &rows[0];
+        assert_eq!(row["t"], serde_json::json!(["a", null, "c"]));
+        assert_eq!(row["i"], serde_json::json!([1, null, 3]));
+        assert_eq!(row["b"], serde_json::json!([9000000000i64]));
+        assert_eq!(row["f"], serde_json::json!([true, false]));
+        assert_eq!(row["d"], serde_json::json!([1.5]));
+        assert_eq!(
+            row["u"],
+            serde_json::json!(["6ba7b810-9dad-11d1-80b4-00c04fd430c8"])
+        );
+        assert_eq!(row["empty"], serde_json::json!([]));
+        assert_eq!(row["missing"], JsonValue::Null);
+    }
+
+    #[tokio::test]
+    async fn test_array_columns_round_trip() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {}.posts (id int PRIMARY KEY, tags text[], scores int4[], \
+                 ids uuid[], meta jsonb)",
+                schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let data = serde_json::json!({
+            "id": 1,
+            "tags": ["rust", "it's \"quoted\"", null],
+            "scores": [3, 1, 2],
+            "ids": ["6ba7b810-9dad-11d1-80b4-00c04fd430c8"],
+            "meta": ["stays", "jsonb"],
+        });
+        let row = DataOperations::insert_row(
+            &pool,
+            InsertRequest {
+                schema: schema.clone(),
+                table: "posts".to_string(),
+                data: data.as_object().unwrap().clone(),
+                on_conflict: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(row["tags"], data["tags"]);
+        assert_eq!(row["scores"], data["scores"]);
+        assert_eq!(row["ids"], data["ids"]);
+        assert_eq!(row["meta"], data["meta"]);
+
+        let update = serde_json::json!({
+            "tags": ["updated", "O'Brien"],
+            "scores": [],
+            "meta": [1, 2],
+        });
+        DataOperations::update_row(
+            &pool,
+            UpdateRequest {
+                schema: schema.clone(),
+                table: "posts".to_string(),
+                data: update.as_object().unwrap().clone(),
+                where_clause: serde_json::json!({"id": 1}).as_object().unwrap().clone(),
+                returning: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        let filters = and_group(vec![condition(
+            "tags",
+            FilterOperator::ArrayContains,
+            Some("O'Brien"),
+        )]);
+        let result = DataOperations::fetch_paginated(
+            &pool,
+            &PageRequest {
+                schema: schema.clone(),
+                table: "posts".to_string(),
+                filters: Some(filters),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows.len(), 1);
+        let row = &result.rows[0];
+        assert_eq!(row["tags"], update["tags"]);
+        assert_eq!(row["scores"], update["scores"]);
+        assert_eq!(row["meta"], update["meta"]);
+        let types: HashMap<_, _> = result
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type.as_str()))
+            .collect();
+        assert_eq!(types["tags"], "TEXT[]");
+        assert_eq!(types["scores"], "INT4[]");
+
+        let mut overlaps = condition("tags", FilterOperator::ArrayOverlaps, None);
+        overlaps.values = Some(vec!["O'Brien".to_string(), "other".to_string()]);
+        let result = DataOperations::fetch_paginated(
+            &pool,
+            &PageRequest {
+                schema: schema.clone(),
+                table: "posts".to_string(),
+                filters: Some(and_group(vec![overlaps])),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.total_count, 1);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_execute_raw_query_returns_rows_after_comments() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let result = DataOperations::execute_raw_query(
+            &mut pool.acquire().await.unwrap(),
+            None,
+            "test",
+            "-- header comment\n/* block */\nSELECT 42 AS answer",
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0]["answer"], 42);
+
+        let result = DataOperations::execute_raw_query(
+            &mut pool.acquire().await.unwrap(),
+            None,
+            "test",
+            "VALUES (1), (2)",
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.columns[0].name, "column1");
+    }
+
+    #[tokio::test]
+    async fn test_execute_raw_query_read_only() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        let mut conn = pool.acquire().await.unwrap();
+        conn.execute("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY")
+            .await
+            .unwrap();
+
+        // Switching the session to read-write doesn't outlast the query
+        for sql in [
+            "SET default_transaction_read_only = off",
+            "SET SESSION CHARACTERISTICS AS TRANSACTION READ WRITE",
+            "SELECT set_config('default_transaction_read_only', 'off', false)",
+            "COMMIT",
+        ] {
+            DataOperations::execute_raw_query(&mut conn, None, "test", sql, true)
+                .await
+                .unwrap();
+        }
+        let create = format!("CREATE TABLE {}.probe (id int)", schema);
+        let err = DataOperations::execute_raw_query(&mut conn, None, "test", &create, true)
+            .await
+            .unwrap_err();
+        let DbViewerError::Database(err) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(
+            err.as_database_error().and_then(|e| e.code()).as_deref(),
+            Some("25006")
+        );
+
+        // Nor can it end the read-only transaction and carry on
+        let sql = format!("COMMIT; {}", create);
+        assert!(
+            DataOperations::execute_raw_query(&mut conn, None, "test", &sql, true)
+                .await
+                .is_err()
+        );
+        let setting: String = sqlx::query_scalar("SHOW default_transaction_read_only")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(setting, "on");
+
+        conn.execute("SET SESSION CHARACTERISTICS AS TRANSACTION READ WRITE")
+            .await
+            .unwrap();
+        drop(conn);
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_cancel_backend() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let mut conn = pool.acquire().await.unwrap();
+        let pid = DataOperations::backend_pid(&mut conn).await.unwrap();
+
+        let query = tokio::spawn(async move {
+            DataOperations::execute_raw_query(&mut conn, None, "test", "SELECT pg_sleep(30)", false)
+                .await
+        });
+        // Wait for the query to start before cancelling it
+        let mut cancelled = false;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            let active: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM pg_stat_activity WHERE pid = $1 AND state = 'active')",
+            )
+            .bind(pid)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            if active {
+                cancelled = DataOperations::cancel_backend(&pool, pid).await.unwrap();
+                break;
+            }
+        }
+        assert!(cancelled);
+
+        let err = query.await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("canceling statement"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_bulk_delete() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.logs (id int, level text);
+                 INSERT INTO {s}.logs SELECT g, CASE WHEN g % 2 = 0 THEN 'debug' ELSE 'info' END
+                 FROM generate_series(1, 20) g;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let request = |filters: Vec<FilterCondition>, limit: Option<i64>| BulkDeleteRequest {
+            schema: schema.clone(),
+            table: "logs".to_string(),
+            filters,
+            limit,
+        };
+        let debug = || vec![condition("level", FilterOperator::Equals, Some("debug"))];
+
+        let deleted = DataOperations::bulk_delete(&pool, request(debug(), Some(3)))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 3);
+        let deleted = DataOperations::bulk_delete(&pool, request(debug(), None))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 7);
+
+        let empty = DataOperations::bulk_delete(&pool, request(vec![], None)).await;
+        assert!(matches!(empty, Err(DbViewerError::InvalidQuery(_))));
+        let unusable = DataOperations::bulk_delete(
+            &pool,
+            request(vec![condition("level", FilterOperator::Equals, None)], None),
+        )
+        .await;
+        assert!(matches!(unusable, Err(DbViewerError::InvalidQuery(_))));
+
+        let (remaining,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {}.logs", schema))
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 10);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_update_by_filter() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.tokens (id int PRIMARY KEY, status text, created_at date);
+                 INSERT INTO {s}.tokens SELECT i, 'active', date '2024-01-01' + i FROM generate_series(1, 10) i;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let filters = vec![
+            condition("status", FilterOperator::Equals, Some("active")),
+            condition("created_at", FilterOperator::LessThan, Some("2024-01-05")),
+        ];
+        let matching = DataOperations::count_matching_rows(&pool, &schema, "tokens", &filters)
+            .await
+            .unwrap();
+        assert_eq!(matching, 3);
+
+        let request = |filters: Vec<FilterCondition>| UpdateByFilterRequest {
+            schema: schema.clone(),
+            table: "tokens".to_string(),
+            data: serde_json::json!({"status": "expired"})
+                .as_object()
+                .unwrap()
+                .clone(),
+            filters,
+        };
+        let updated = DataOperations::update_by_filter(&pool, request(filters.clone()))
+            .await
+            .unwrap();
+        assert_eq!(updated, matching);
+        let expired = vec![condition("status", FilterOperator::Equals, Some("expired"))];
+        assert_eq!(
+            DataOperations::count_matching_rows(&pool, &schema, "tokens", &expired)
+                .await
+                .unwrap(),
+            3
+        );
+        // Already updated rows no longer match
+        assert_eq!(
+            DataOperations::count_matching_rows(&pool, &schema, "tokens", &filters)
+                .await
+                .unwrap(),
+            0
+        );
+
+        // Without a condition nothing runs
+        let empty = DataOperations::update_by_filter(&pool, request(vec![])).await;
+        assert!(matches!(empty, Err(DbViewerError::InvalidQuery(_))));
+        let empty = DataOperations::count_matching_rows(&pool, &schema, "tokens", &[]).await;
+        assert!(matches!(empty, Err(DbViewerError::InvalidQuery(_))));
+        let unusable = DataOperations::update_by_filter(
+            &pool,
+            request(vec![condition("status", FilterOperator::Equals, None)]),
+        )
+        .await;
+        assert!(matches!(unusable, Err(DbViewerError::InvalidQuery(_))));
+        assert_eq!(
+            DataOperations::count_matching_rows(&pool, &schema, "tokens", &expired)
+                .await
+                .unwrap(),
+            3
+        );
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[test]
+    fn test_check_truncate_confirmation() {
+        assert!(
+            DataOperations::check_truncate_confirmation("public", "users", "public.users").is_ok()
+        );
+        for confirmation in ["", "users", "public.user", "Public.users", "public.users "] {
+            let err = DataOperations::check_truncate_confirmation("public", "users", confirmation)
+                .unwrap_err();
+            assert!(matches!(err, DbViewerError::InvalidQuery(_)), "{err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_truncate_table() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.parents (id int PRIMARY KEY);
+                 CREATE TABLE {s}.children (id int, parent_id int REFERENCES {s}.parents);
+                 INSERT INTO {s}.parents SELECT generate_series(1, 10);
+                 INSERT INTO {s}.children VALUES (1, 1), (2, 2);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let count = |table: &'static str| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>(&format!("SELECT count(*) FROM {}.{}", schema, table))
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
         };
 
-        Ok(MigrationResult {
-            ok: all_ok,
-            dry_run,
-            committed,
-            duration_ms: total_start.elapsed().as_secs_f64() * 1000.0,
-            statements: results,
-            lock_timeout_ms: lock_timeout,
-            statement_timeout_ms: stmt_timeout,
-        })
+        // Referenced tables need CASCADE
+        let err = DataOperations::truncate_table(&pool, &schema, "parents", false).await;
+        assert!(err.is_err());
+        assert_eq!(count("parents").await, 10);
+
+        let removed = DataOperations::truncate_table(&pool, &schema, "parents", true)
+            .await
+            .unwrap();
+        assert_eq!(removed, 10);
+        assert_eq!(count("parents").await, 0);
+        assert_eq!(count("children").await, 0);
+
+        let missing = DataOperations::truncate_table(&pool, &schema, "nope", false).await;
+        assert!(matches!(missing, Err(DbViewerError::TableNotFound(_))));
+
+        drop_test_schema(&pool, &schema).await;
     }
-}
 
-/// Extract structured error info from a sqlx::Error
-fn extract_pg_error(err: &sqlx::Error) -> StatementError {
-    match err {
-        sqlx::Error::Database(db_err) => {
-            let pg_code = db_err.code().map(|c| c.to_string());
-            let detail = db_err
-                .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
-                .and_then(|pg| pg.detail().map(|s| s.to_string()));
-            let hint = db_err
-                .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
-                .and_then(|pg| pg.hint().map(|s| s.to_string()));
+    #[tokio::test]
+    async fn test_interval_matches_postgres_text() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let literals = [
+            "0",
+            "3 months",
+            "14 months",
+            "-1 year",
+            "1 mon 2 days 03:04:05",
+            "-1 mon +3 days",
+            "-2 days -00:00:01.5",
+            "1 day -10:00:00",
+            "-1 year -2 mons 3 days 04:05:06.789",
+            "100 hours 0.000001 seconds",
+        ];
+        for literal in literals {
+            // The server's own text form, under the default IntervalStyle
+            let rows = sqlx::query(&format!(
+                "SELECT '{0}'::interval AS i, '{0}'::interval::text AS t",
+                literal
+            ))
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            let (rows, _) = rows_to_json(&rows);
+            assert_eq!(rows[0]["i"], rows[0]["t"], "interval {}", literal);
+        }
+    }
 
-            StatementError {
-                code: pg_code,
-                message: db_err.message().to_string(),
-                detail,
-                hint,
-            }
+    #[tokio::test]
+    async fn test_network_types_match_postgres_text() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let values = [
+            ("192.168.0.1", "inet"),
+            ("10.0.0.0/8", "inet"),
+            ("10.0.0.0/8", "cidr"),
+            ("2001:db8::/32", "inet"),
+            ("2001:db8::/32", "cidr"),
+            ("::1", "inet"),
+            ("::ffff:1.2.3.4", "inet"),
+            ("fe80::1:2:3:4/64", "inet"),
+            ("08:00:2b:01:02:03", "macaddr"),
+            ("08:00:2b:01:02:03:04:05", "macaddr8"),
+        ];
+        for (literal, ty) in values {
+            // format() uses the type's output function; inet's ::text cast
+            // would always append the prefix length
+            let rows = sqlx::query(&format!(
+                "SELECT '{0}'::{1} AS v, format('%s', '{0}'::{1}) AS t",
+                literal, ty
+            ))
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            let (rows, _) = rows_to_json(&rows);
+            assert_eq!(rows[0]["v"], rows[0]["t"], "{}::{}", literal, ty);
         }
-        _ => StatementError {
-            code: None,
-            message: err.to_string(),
-            detail: None,
-            hint: None,
-        },
+
+        let rows = sqlx::query("SELECT NULL::inet AS v")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        let (rows, _) = rows_to_json(&rows);
+        assert_eq!(rows[0]["v"], JsonValue::Null);
     }
-}
 
-/// Convert PostgreSQL rows to JSON
-fn rows_to_json(rows: &[PgRow]) -> (Vec<serde_json::Map<String, JsonValue>>, Vec<ColumnMeta>) {
-    if rows.is_empty() {
-        return (Vec::new(), Vec::new());
+    #[tokio::test]
+    async fn test_interval_and_network_round_trip() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {}.hosts (id int PRIMARY KEY, lease interval, addr inet, \
+                 subnet cidr, mac macaddr)",
+                schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let fetch = || async {
+            DataOperations::fetch_paginated(
+                &pool,
+                &PageRequest {
+                    schema: schema.clone(),
+                    table: "hosts".to_string(),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap()
+            .rows
+        };
+
+        let inserted = serde_json::json!({
+            "id": 1,
+            "lease": "1 day 02:00:00",
+            "addr": "192.168.0.10",
+            "subnet": "10.0.0.0/8",
+            "mac": "08:00:2b:01:02:03",
+        });
+        DataOperations::insert_row(
+            &pool,
+            InsertRequest {
+                schema: schema.clone(),
+                table: "hosts".to_string(),
+                data: inserted.as_object().unwrap().clone(),
+                on_conflict: None,
+            },
+        )
+        .await
+        .unwrap();
+        let nulls = serde_json::json!({
+            "id": 2, "lease": null, "addr": null, "subnet": null, "mac": null,
+        });
+        DataOperations::insert_row(
+            &pool,
+            InsertRequest {
+                schema: schema.clone(),
+                table: "hosts".to_string(),
+                data: nulls.as_object().unwrap().clone(),
+                on_conflict: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let rows = fetch().await;
+        for col in ["lease", "addr", "subnet", "mac"] {
+            assert_eq!(rows[0][col], inserted[col], "{col}");
+            assert_eq!(rows[1][col], JsonValue::Null, "{col}");
+        }
+
+        // Match on the displayed values and write new ones in the same text forms
+        let updated = serde_json::json!({
+            "lease": "-3 days +04:05:06.5",
+            "addr": "2001:db8::1/64",
+            "subnet": "2001:db8::/32",
+            "mac": "aa:bb:cc:dd:ee:ff",
+        });
+        let affected = DataOperations::update_row(
+            &pool,
+            UpdateRequest {
+                schema: schema.clone(),
+                table: "hosts".to_string(),
+                data: updated.as_object().unwrap().clone(),
+                where_clause: rows[0].clone(),
+                returning: false,
+            },
+        )
+        .await
+        .unwrap()
+        .rows_affected;
+        assert_eq!(affected, 1);
+
+        let rows = fetch().await;
+        for col in ["lease", "addr", "subnet", "mac"] {
+            assert_eq!(rows[0][col], updated[col], "{col}");
+        }
+
+        drop_test_schema(&pool, &schema).await;
     }
 
-    let columns: Vec<ColumnMeta> = rows[0]
-        .columns()
-        .iter()
-        .map(|col| ColumnMeta {
-            name: col.name().to_string(),
-            data_type: col.type_info().name().to_string(),
-        })
-        .collect();
+    #[tokio::test]
+    async fn test_update_row_returns_trigger_values() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.items (id int PRIMARY KEY, name text, revision int NOT NULL DEFAULT 1);
+                 CREATE FUNCTION {s}.bump_revision() RETURNS trigger LANGUAGE plpgsql AS $$
+                 BEGIN NEW.revision := OLD.revision + 1; RETURN NEW; END $$;
+                 CREATE TRIGGER bump BEFORE UPDATE ON {s}.items
+                     FOR EACH ROW EXECUTE FUNCTION {s}.bump_revision();
+                 INSERT INTO {s}.items (id, name) VALUES (1, 'old');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
 
-    let json_rows: Vec<serde_json::Map<String, JsonValue>> = rows
-        .iter()
-        .map(|row| {
-            let mut map = serde_json::Map::new();
-            for (i, col) in row.columns().iter().enumerate() {
-                let value = pg_value_to_json(row, i, col.type_info().name());
-                map.insert(col.name().to_string(), value);
-            }
-            map
-        })
-        .collect();
+        let result = DataOperations::update_row(
+            &pool,
+            UpdateRequest {
+                schema: schema.clone(),
+                table: "items".to_string(),
+                data: serde_json::json!({"name": "new"})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+                where_clause: serde_json::json!({"id": 1}).as_object().unwrap().clone(),
+                returning: true,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows_affected, 1);
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0]["name"], "new");
+        assert_eq!(result.rows[0]["revision"], 2);
 
-    (json_rows, columns)
-}
+        let result = DataOperations::update_row(
+            &pool,
+            UpdateRequest {
+                schema: schema.clone(),
+                table: "items".to_string(),
+                data: serde_json::json!({"name": "newer"})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+                where_clause: serde_json::json!({"id": 1}).as_object().unwrap().clone(),
+                returning: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows_affected, 1);
+        assert!(result.rows.is_empty());
 
-/// Convert a PostgreSQL value to JSON
-fn pg_value_to_json(row: &PgRow, idx: usize, type_name: &str) -> JsonValue {
-    // Try to get the value based on the type
-    match type_name {
-        "BOOL" => row
-            .try_get::<Option<bool>, _>(idx)
-            .ok()
-            .flatten()
-            .map(JsonValue::Bool)
-            .unwrap_or(JsonValue::Null),
+        drop_test_schema(&pool, &schema).await;
+    }
 
-        "INT2" => row
-            .try_get::<Option<i16>, _>(idx)
-            .ok()
-            .flatten()
-            .map(|v| JsonValue::Number(v.into()))
-            .unwrap_or(JsonValue::Null),
+    #[tokio::test]
+    async fn test_update_and_delete_char_columns() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.codes (code char(3), flags bit(4), price numeric(10,2));
+                 INSERT INTO {s}.codes VALUES ('abc', B'1010', 1.50), ('abd', B'0101', 2.00);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let object = |value: JsonValue| value.as_object().unwrap().clone();
 
-        "INT4" => row
-            .try_get::<Option<i32>, _>(idx)
-            .ok()
-            .flatten()
-            .map(|v| JsonValue::Number(v.into()))
-            .unwrap_or(JsonValue::Null),
+        let result = DataOperations::update_row(
+            &pool,
+            UpdateRequest {
+                schema: schema.clone(),
+                table: "codes".to_string(),
+                data: object(serde_json::json!({"code": "xyz", "flags": "1111"})),
+                where_clause: object(serde_json::json!({"code": "abc", "price": "1.50"})),
+                returning: true,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows_affected, 1);
+        assert_eq!(result.rows[0]["code"], "xyz");
 
-        "INT8" => row
-            .try_get::<Option<i64>, _>(idx)
-            .ok()
-            .flatten()
-            .map(|v| JsonValue::Number(v.into()))
-            .unwrap_or(JsonValue::Null),
+        let err = DataOperations::update_row(
+            &pool,
+            UpdateRequest {
+                schema: schema.clone(),
+                table: "codes".to_string(),
+                data: object(serde_json::json!({"code": "wxyz"})),
+                where_clause: object(serde_json::json!({"code": "xyz"})),
+                returning: false,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(&err, DbViewerError::Database(e)
+                if extract_pg_error(e).code.as_deref() == Some("22001")),
+            "{err}"
+        );
 
-        "FLOAT4" => row
-            .try_get::<Option<f32>, _>(idx)
-            .ok()
-            .flatten()
-            .and_then(|v| serde_json::Number::from_f64(v as f64))
-            .map(JsonValue::Number)
-            .unwrap_or(JsonValue::Null),
+        let result = DataOperations::delete_row(
+            &pool,
+            DeleteRequest {
+                schema: schema.clone(),
+                table: "codes".to_string(),
+                where_clause: object(serde_json::json!({"code": "xyz", "flags": "1111"})),
+                returning: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows_affected, 1);
 
-        "FLOAT8" => row
-            .try_get::<Option<f64>, _>(idx)
-            .ok()
-            .flatten()
-            .and_then(serde_json::Number::from_f64)
-            .map(JsonValue::Number)
-            .unwrap_or(JsonValue::Null),
+        let remaining: Vec<String> =
+            sqlx::query_scalar(&format!("SELECT code::text FROM {}.codes", schema))
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(remaining, vec!["abd".to_string()]);
 
-        "JSON" | "JSONB" => row
-            .try_get::<Option<JsonValue>, _>(idx)
-            .ok()
-            .flatten()
-            .unwrap_or(JsonValue::Null),
+        drop_test_schema(&pool, &schema).await;
+    }
 
-        "UUID" => row
-            .try_get::<Option<uuid::Uuid>, _>(idx)
-            .ok()
-            .flatten()
-            .map(|v| JsonValue::String(v.to_string()))
-            .unwrap_or(JsonValue::Null),
+    #[tokio::test]
+    async fn test_length_limited_keys_match_exactly() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.tags (code varchar(3) PRIMARY KEY, label text);
+                 CREATE TABLE {s}.posts (id int, tag varchar(3) REFERENCES {s}.tags);
+                 INSERT INTO {s}.tags VALUES ('abc', 'first');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let object = |value: JsonValue| value.as_object().unwrap().clone();
 
-        "BYTEA" => row
-            .try_get::<Option<Vec<u8>>, _>(idx)
-            .ok()
-            .flatten()
-            .map(|v| JsonValue::String(format!("\\x{}", hex::encode(v))))
-            .unwrap_or(JsonValue::Null),
+        let referenced = DataOperations::fetch_referenced_row(
+            &pool,
+            &schema,
+            "posts",
+            "tag",
+            &serde_json::json!("abcd"),
+        )
+        .await
+        .unwrap();
+        assert!(referenced.is_none());
 
-        "TIMESTAMPTZ" => row
-            .try_get::<Option<chrono::DateTime<chrono::Utc>>, _>(idx)
-            .ok()
-            .flatten()
-            .map(|v| JsonValue::String(v.to_rfc3339()))
-            .unwrap_or(JsonValue::Null),
+        let result = DataOperations::update_row(
+            &pool,
+            UpdateRequest {
+                schema: schema.clone(),
+                table: "tags".to_string(),
+                data: object(serde_json::json!({"label": "changed"})),
+                where_clause: object(serde_json::json!({"code": "abcd"})),
+                returning: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows_affected, 0);
 
-        "TIMESTAMP" => row
-            .try_get::<Option<chrono::NaiveDateTime>, _>(idx)
-            .ok()
-            .flatten()
-            .map(|v| JsonValue::String(v.to_string()))
-            .unwrap_or(JsonValue::Null),
+        let result = DataOperations::delete_row(
+            &pool,
+            DeleteRequest {
+                schema: schema.clone(),
+                table: "tags".to_string(),
+                where_clause: object(serde_json::json!({"code": "abcd"})),
+                returning: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows_affected, 0);
 
-        "DATE" => row
-            .try_get::<Option<chrono::NaiveDate>, _>(idx)
-            .ok()
-            .flatten()
-            .map(|v| JsonValue::String(v.to_string()))
-            .unwrap_or(JsonValue::Null),
+        let label: String = sqlx::query_scalar(&format!(
+            "SELECT label FROM {}.tags WHERE code = 'abc'",
+            schema
+        ))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(label, "first");
 
-        "TIME" => row
-            .try_get::<Option<chrono::NaiveTime>, _>(idx)
-            .ok()
-            .flatten()
-            .map(|v| JsonValue::String(v.to_string()))
-            .unwrap_or(JsonValue::Null),
+        drop_test_schema(&pool, &schema).await;
+    }
 
-        _ => {
-            // Try to get as string first
-            if let Ok(Some(s)) = row.try_get::<Option<String>, _>(idx) {
-                return JsonValue::String(s);
-            }
+    #[tokio::test]
+    async fn test_delete_row_returns_deleted_rows() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.items (id int PRIMARY KEY, kind text, name text);
+                 INSERT INTO {s}.items VALUES (1, 'a', 'one'), (2, 'a', 'two'), (3, 'b', 'three');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let request = |where_clause: JsonValue| DeleteRequest {
+            schema: schema.clone(),
+            table: "items".to_string(),
+            where_clause: where_clause.as_object().unwrap().clone(),
+            returning: true,
+        };
 
-            // For enum types and other USER-DEFINED types, try to get raw value
-            // PostgreSQL enum values are stored as strings but SQLx might not decode them directly
-            use sqlx::Row as _;
-            if let Ok(value_ref) = row.try_get_raw(idx) {
-                use sqlx::ValueRef;
-                if value_ref.is_null() {
-                    return JsonValue::Null;
-                }
-                // Try to decode as string from the raw bytes
-                use sqlx::Decode;
-                if let Ok(s) = <String as Decode<sqlx::Postgres>>::decode(value_ref) {
-                    return JsonValue::String(s);
-                }
-            }
+        let result = DataOperations::delete_row(&pool, request(serde_json::json!({"kind": "a"})))
+            .await
+            .unwrap();
+        assert_eq!(result.rows_affected, 2);
+        let mut names: Vec<&JsonValue> = result.rows.iter().map(|r| &r["name"]).collect();
+        names.sort_by_key(|n| n.as_str());
+        assert_eq!(names, ["one", "two"]);
+        assert_eq!(result.rows[0].len(), 3);
 
-            JsonValue::Null
-        }
+        let result = DataOperations::delete_row(&pool, request(serde_json::json!({"id": 9})))
+            .await
+            .unwrap();
+        assert_eq!(result.rows_affected, 0);
+        assert!(result.rows.is_empty());
+
+        let err = DataOperations::delete_row(&pool, request(serde_json::json!({})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DbViewerError::InvalidQuery(_)));
+
+        // Without returning only the count comes back
+        let result = DataOperations::delete_row(
+            &pool,
+            DeleteRequest {
+                returning: false,
+                ..request(serde_json::json!({"kind": "b"}))
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows_affected, 1);
+        assert!(result.rows.is_empty());
+
+        // Keys are cast to their column types, so an array matches an array column
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.tagged (id uuid, tags int[]);
+                 INSERT INTO {s}.tagged VALUES
+                    ('7c9e6679-7425-40de-944b-e07fc1f90ae7', '{{1,2}}'),
+                    ('7c9e6679-7425-40de-944b-e07fc1f90ae7', '{{3}}');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        let result = DataOperations::delete_row(
+            &pool,
+            DeleteRequest {
+                table: "tagged".to_string(),
+                ..request(serde_json::json!({
+                    "id": "7c9e6679-7425-40de-944b-e07fc1f90ae7",
+                    "tags": [1, 2],
+                }))
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows_affected, 1);
+        assert_eq!(result.rows[0]["tags"], serde_json::json!([1, 2]));
+
+        drop_test_schema(&pool, &schema).await;
     }
-}
 
-/// Convert a JSON value to a SQL string (with proper escaping)
-fn json_value_to_sql(value: &JsonValue) -> String {
-    match value {
-        JsonValue::Null => "NULL".to_string(),
-        JsonValue::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
-        JsonValue::Number(n) => n.to_string(),
-        JsonValue::String(s) => format!("'{}'", escape_sql_string(s)),
-        JsonValue::Array(_) | JsonValue::Object(_) => {
-            format!("'{}'::jsonb", escape_sql_string(&value.to_string()))
-        }
+    #[tokio::test]
+    async fn test_reset_sequence() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(format!("CREATE SEQUENCE {}.\"Ticket No\"", schema).as_str())
+            .await
+            .unwrap();
+
+        DataOperations::reset_sequence(&pool, &schema, "Ticket No", 41)
+            .await
+            .unwrap();
+        let (next,): (i64,) =
+            sqlx::query_as(&format!("SELECT nextval('{}.\"Ticket No\"')", schema))
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(next, 42);
+
+        DataOperations::set_sequence_value(&pool, &schema, "Ticket No", 7, false)
+            .await
+            .unwrap();
+        let (next,): (i64,) =
+            sqlx::query_as(&format!("SELECT nextval('{}.\"Ticket No\"')", schema))
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(next, 7);
+
+        drop_test_schema(&pool, &schema).await;
     }
-}
 
-/// Escape a string for SQL (prevent SQL injection)
-fn escape_sql_string(s: &str) -> String {
-    s.replace('\'', "''")
-}
+    #[tokio::test]
+    async fn test_fetch_paginated_regex_filters() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.tickets (id int PRIMARY KEY, code text);
+                 INSERT INTO {s}.tickets VALUES
+                    (1, 'ABC-12'), (2, 'abc-34'), (3, 'ABCD-5'), (4, 'O''Brien');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
 
-/// Quote an identifier to prevent SQL injection
-fn quote_identifier(identifier: &str) -> String {
-    format!("\"{}\"", identifier.replace('"', "\"\""))
+        let fetch = |operator: FilterOperator, pattern: &'static str| {
+            let pool = pool.clone();
+            let schema = schema.clone();
+            async move {
+                let filters = and_group(vec![condition("code", operator, Some(pattern))]);
+                DataOperations::fetch_paginated(
+                    &pool,
+                    &PageRequest {
+                        schema: schema.clone(),
+                        table: "tickets".to_string(),
+                        filters: Some(filters),
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+            }
+        };
+
+        let result = fetch(FilterOperator::Regex, r"^[A-Z]{3}-\d+$")
+            .await
+            .unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.rows[0]["id"], 1);
+
+        let result = fetch(FilterOperator::RegexInsensitive, r"^[A-Z]{3}-\d+$")
+            .await
+            .unwrap();
+        assert_eq!(result.total_count, 2);
+
+        let result = fetch(FilterOperator::Regex, "O'B").await.unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.rows[0]["id"], 4);
+
+        let err = fetch(FilterOperator::Regex, "(unclosed").await.unwrap_err();
+        assert!(
+            matches!(&err, DbViewerError::InvalidQuery(msg) if msg.starts_with("Invalid regular expression")),
+            "unexpected error: {err}"
+        );
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_empty_results_keep_columns() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {}.people (id int PRIMARY KEY, name text, tags text[])",
+                schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let expected = vec![
+            ("id".to_string(), "INT4".to_string()),
+            ("name".to_string(), "TEXT".to_string()),
+            ("tags".to_string(), "TEXT[]".to_string()),
+        ];
+        let names = |columns: &[ColumnMeta]| {
+            columns
+                .iter()
+                .map(|c| (c.name.clone(), c.data_type.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let result = DataOperations::fetch_paginated(
+            &pool,
+            &PageRequest {
+                schema: schema.clone(),
+                table: "people".to_string(),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(result.rows.is_empty());
+        assert_eq!(names(&result.columns), expected);
+
+        let order_by = vec![OrderSpec::Column("name".to_string())];
+        let filters = and_group(vec![condition("name", FilterOperator::Equals, Some("x"))]);
+        let result = DataOperations::fetch_paginated(
+            &pool,
+            &PageRequest {
+                schema: schema.clone(),
+                table: "people".to_string(),
+                order_by: Some(order_by),
+                filters: Some(filters),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(result.rows.is_empty());
+        assert_eq!(names(&result.columns), expected);
+
+        let result = DataOperations::execute_raw_query(
+            &mut pool.acquire().await.unwrap(),
+            None,
+            "test",
+            &format!("SELECT * FROM {}.people WHERE false", schema),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(result.rows.is_empty());
+        assert_eq!(names(&result.columns), expected);
+
+        drop_test_schema(&pool, &schema).await;
+    }
 }
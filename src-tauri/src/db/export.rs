@@ -35,15 +35,17 @@ pub struct ExportedProject {
     pub created_at: String,
 }
 
-fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
+/// Derive the file key from `password`, reporting failures with `error`
+/// (`Export` or `Import`)
+fn derive_key(password: &str, salt: &[u8], error: fn(String) -> DbViewerError) -> Result<[u8; 32]> {
     let params = argon2::Params::new(65536, 3, 4, Some(32))
-        .map_err(|e| DbViewerError::Export(format!("Argon2 params error: {}", e)))?;
+        .map_err(|e| error(format!("Argon2 params error: {}", e)))?;
     let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
 
     let mut key = [0u8; 32];
     argon2
         .hash_password_into(password.as_bytes(), salt, &mut key)
-        .map_err(|e| DbViewerError::Export(format!("Key derivation failed: {}", e)))?;
+        .map_err(|e| error(format!("Key derivation failed: {}", e)))?;
 
     Ok(key)
 }
@@ -86,7 +88,7 @@ pub fn encrypt_and_write(
     rand::thread_rng().fill_bytes(&mut nonce_bytes);
 
     // Derive encryption key
-    let key = derive_key(password, &salt)?;
+    let key = derive_key(password, &salt, DbViewerError::Export)?;
 
     // Encrypt
     let cipher = Aes256Gcm::new_from_slice(&key)
@@ -113,7 +115,7 @@ pub fn encrypt_and_write(
 /// Check if a file is encrypted (starts with TUSK magic bytes)
 pub fn is_file_encrypted(file_path: &str) -> Result<bool> {
     let data = std::fs::read(file_path)
-        .map_err(|e| DbViewerError::Export(format!("Failed to read file: {}", e)))?;
+        .map_err(|e| DbViewerError::Import(format!("Failed to read file: {}", e)))?;
 
     if data.len() < 4 {
         return Ok(false);
@@ -124,35 +126,33 @@ pub fn is_file_encrypted(file_path: &str) -> Result<bool> {
 
 pub fn read_plaintext(file_path: &str) -> Result<ExportPayload> {
     let data = std::fs::read(file_path)
-        .map_err(|e| DbViewerError::Export(format!("Failed to read file: {}", e)))?;
+        .map_err(|e| DbViewerError::Import(format!("Failed to read file: {}", e)))?;
 
     let payload: ExportPayload = serde_json::from_slice(&data)
-        .map_err(|e| DbViewerError::Export(format!("Not a valid Tusker export file: {}", e)))?;
+        .map_err(|e| DbViewerError::Import(format!("Not a valid Tusker export file: {}", e)))?;
 
     Ok(payload)
 }
 
 pub fn read_and_decrypt(file_path: &str, password: &str) -> Result<ExportPayload> {
     let data = std::fs::read(file_path)
-        .map_err(|e| DbViewerError::Export(format!("Failed to read file: {}", e)))?;
+        .map_err(|e| DbViewerError::Import(format!("Failed to read file: {}", e)))?;
 
-    if data.len() < HEADER_LEN {
-        return Err(DbViewerError::Export(
-            "Invalid file: too short".to_string(),
+    // Validate magic bytes, when there are enough to tell
+    if data.len() >= MAGIC.len() && &data[0..4] != MAGIC {
+        return Err(DbViewerError::Import(
+            "Not a valid Tusker export file".to_string(),
         ));
     }
 
-    // Validate magic bytes
-    if &data[0..4] != MAGIC {
-        return Err(DbViewerError::Export(
-            "Not a valid Tusker export file".to_string(),
-        ));
+    if data.len() < HEADER_LEN {
+        return Err(DbViewerError::Import("Invalid file: too short".to_string()));
     }
 
     // Check version
     let version = data[4];
     if version != VERSION {
-        return Err(DbViewerError::Export(format!(
+        return Err(DbViewerError::Import(format!(
             "Unsupported file version: {}",
             version
         )));
@@ -163,17 +163,18 @@ pub fn read_and_decrypt(file_path: &str, password: &str) -> Result<ExportPayload
     let ciphertext = &data[HEADER_LEN..];
 
     // Derive key
-    let key = derive_key(password, salt)?;
+    let key = derive_key(password, salt, DbViewerError::Import)?;
 
     // Decrypt
     let cipher = Aes256Gcm::new_from_slice(&key)
-        .map_err(|e| DbViewerError::Export(format!("Cipher init failed: {}", e)))?;
+        .map_err(|e| DbViewerError::Import(format!("Cipher init failed: {}", e)))?;
     let nonce = Nonce::from_slice(nonce_bytes);
     let plaintext = cipher
         .decrypt(nonce, ciphertext)
-        .map_err(|_| DbViewerError::Export("Incorrect password or corrupted file".to_string()))?;
+        .map_err(|_| DbViewerError::Import("Incorrect password or corrupted file".to_string()))?;
 
-    let payload: ExportPayload = serde_json::from_slice(&plaintext)?;
+    let payload: ExportPayload = serde_json::from_slice(&plaintext)
+        .map_err(|e| DbViewerError::Import(format!("Not a valid Tusker export file: {}", e)))?;
 
     Ok(payload)
 }
@@ -238,8 +239,9 @@ mod tests {
 
         let result = read_and_decrypt(path, "password");
         assert!(result.is_err());
-        let err = result.unwrap_err().to_string();
-        assert!(err.contains("Not a valid Tusker"));
+        let err = result.unwrap_err();
+        assert!(matches!(err, DbViewerError::Import(_)));
+        assert!(err.to_string().contains("Not a valid Tusker"));
     }
 
     #[test]
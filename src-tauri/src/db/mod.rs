@@ -1,24 +1,44 @@
 pub mod commit_store;
 pub mod connection;
+pub mod credentials;
 pub mod data;
 pub mod discovery;
 pub mod export;
+pub mod monitor;
+pub mod query_history;
 pub mod schema;
+pub mod ssh_tunnel;
+#[cfg(test)]
+mod test_support;
 
 pub use commit_store::{
-    Commit, CommitChange, CommitDetail, CommitStore, SaveCommitChange, SaveCommitRequest,
+    Commit, CommitChange, CommitDetail, CommitSearchHit, CommitStore, SaveCommitChange,
+    SaveCommitRequest,
 };
 pub use connection::{
-    ConnectionConfig, ConnectionInfo, ConnectionManager, CredentialStorage, SavedConnection,
-    SslMode,
+    ConnectionConfig, ConnectionInfo, ConnectionManager, CredentialStorage, PoolStats,
+    SavedConnection, SslMode,
 };
+pub use credentials::{CredentialBackend, EncryptedFileBackend, KeyringBackend};
 pub use data::{
-    BulkInsertRequest, ColumnMeta, DataOperations, DeleteRequest, FilterCondition, FilterOperator,
-    InsertRequest, MigrationOperations, MigrationRequest, MigrationResult, PaginatedResult,
-    QueryResult, UpdateRequest,
+    validate_filters, BulkDeleteRequest, BulkInsertProgress, BulkInsertRequest, CancellableConn,
+    ColumnMeta, ColumnStats, CommonValue, ConflictAction, ConflictStrategy, CountMode,
+    CursorDirection, DataOperations, DeleteRequest, DeleteResult, DistinctValues, FilterCondition,
+    FilterGroup, FilterLogic, FilterNode, FilterOperator, FilterValidationError, ImportCsvRequest,
+    ImportResult, InsertRequest, KeysetPage, KeysetRequest, MigrationOperations, MigrationRequest,
+    MigrationResult, MissingValue, OnConflict, OrderSpec, PageCursor, PageRequest, PaginatedResult,
+    PaginationMode, QueryResult, ReferencingRows, RowError, SampleMethod, TableSample,
+    TransactionRequest, TransactionResult, TruncatedCell, UpdateByFilterRequest, UpdateRequest,
+    UpdateResult, UpsertRequest,
 };
 pub use discovery::{AuthStatus, DiscoveredDatabase};
+pub use monitor::{ActivityFilter, ActivityInfo, ActivityMonitor};
+pub use query_history::{QueryHistoryEntry, QueryHistoryStore};
 pub use schema::{
-    ColumnInfo, ConstraintInfo, ConstraintType, ForeignKeyInfo, IndexInfo, SchemaInfo,
-    SchemaIntrospector, SchemaWithTables, TableColumnsInfo, TableInfo, TableType,
+    ColumnDiff, ColumnInfo, CompositeAttribute, CompositeType, ConstraintDiff, ConstraintInfo,
+    ConstraintType, DiffKind, DomainInfo, ForeignKeyInfo, FunctionInfo, FunctionKind, IndexDiff,
+    IndexInfo, IndexUsageStats, PgColumnStats, RlsPolicy, SchemaDiff, SchemaInfo,
+    SchemaIntrospector, SchemaWithTables, SequenceInfo, TableColumnsInfo, TableInfo, TableSizeInfo,
+    TableStats, TableType, TriggerInfo, ViewDefinition,
 };
+pub use ssh_tunnel::{SshAuth, SshTunnel, SshTunnelConfig};
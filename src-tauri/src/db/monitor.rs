@@ -0,0 +1,255 @@
+use crate::error::{DbViewerError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+
+/// A server process, from `pg_stat_activity`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityInfo {
+    pub pid: i32,
+    pub usename: Option<String>,
+    pub datname: Option<String>,
+    pub application_name: Option<String>,
+    pub client_addr: Option<String>,
+    /// `active`, `idle`, `idle in transaction`, ...
+    pub state: Option<String>,
+    /// The running query, or the last one when the backend is idle
+    pub query: Option<String>,
+    pub query_start: Option<DateTime<Utc>>,
+    /// Time since `query_start`, by the server's clock
+    pub query_duration_secs: Option<f64>,
+    pub wait_event_type: Option<String>,
+    pub wait_event: Option<String>,
+    /// Sitting in an open transaction without running anything, which holds
+    /// its locks until it commits or rolls back
+    pub idle_in_transaction: bool,
+}
+
+/// Which backends `ActivityMonitor::list_backends` returns. The default is
+/// every backend connected to the current database.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActivityFilter {
+    /// Backends of every database on the server, and those not connected
+    /// to a database
+    pub all_databases: bool,
+    /// Leave out idle backends
+    pub exclude_idle: bool,
+}
+
+pub struct ActivityMonitor;
+
+impl ActivityMonitor {
+    /// Backends connected to the current database, except the one running
+    /// this query, longest-running first
+    pub async fn list_activity(pool: &PgPool) -> Result<Vec<ActivityInfo>> {
+        Self::list_backends(pool, ActivityFilter::default()).await
+    }
+
+    /// Backends picked by `filter`, except the one running this query,
+    /// longest-running first
+    pub async fn list_backends(pool: &PgPool, filter: ActivityFilter) -> Result<Vec<ActivityInfo>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT pid, usename::text, datname::text, application_name,
+                   host(client_addr) AS client_addr, state, query, query_start,
+                   EXTRACT(EPOCH FROM clock_timestamp() - query_start)::float8
+                       AS query_duration_secs,
+                   wait_event_type, wait_event
+            FROM pg_stat_activity
+            WHERE ($1 OR datname = current_database())
+              AND (NOT $2 OR state <> 'idle')
+              AND pid <> pg_backend_pid()
+            ORDER BY query_start NULLS LAST, pid
+            "#,
+        )
+        .bind(filter.all_databases)
+        .bind(filter.exclude_idle)
+        .fetch_all(pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let state: Option<String> = row.try_get("state")?;
+                let idle_in_transaction = state
+                    .as_deref()
+                    .is_some_and(|s| s.starts_with("idle in transaction"));
+                Ok(ActivityInfo {
+                    pid: row.try_get("pid")?,
+                    usename: row.try_get("usename")?,
+                    datname: row.try_get("datname")?,
+                    application_name: row.try_get("application_name")?,
+                    client_addr: row.try_get("client_addr")?,
+                    state,
+                    query: row.try_get("query")?,
+                    query_start: row.try_get("query_start")?,
+                    query_duration_secs: row.try_get("query_duration_secs")?,
+                    wait_event_type: row.try_get("wait_event_type")?,
+                    wait_event: row.try_get("wait_event")?,
+                    idle_in_transaction,
+                })
+            })
+            .collect()
+    }
+
+    /// Cancel the current query of backend `pid`, or with `force` end its
+    /// session. Refuses to target the app's own backends: those sharing the
+    /// pool's `application_name`, and migrations in progress. Returns false
+    /// when no such backend exists.
+    pub async fn terminate_backend(pool: &PgPool, pid: i32, force: bool) -> Result<bool> {
+        // Check and signal on a pooled connection, whose session carries the
+        // name every connection of the pool is given
+        let mut conn = pool.acquire().await?;
+        let owned: Option<bool> = sqlx::query_scalar(
+            r#"
+            SELECT pid = pg_backend_pid()
+                OR coalesce(application_name, '')
+                    IN (current_setting('application_name'), 'tusker-migration')
+            FROM pg_stat_activity
+            WHERE pid = $1
+            "#,
+        )
+        .bind(pid)
+        .fetch_optional(&mut *conn)
+        .await?;
+        match owned {
+            None => return Ok(false),
+            Some(true) => {
+                return Err(DbViewerError::InvalidQuery(
+                    "Refusing to terminate one of the app's own connections".to_string(),
+                ))
+            }
+            Some(false) => {}
+        }
+
+        let sql = if force {
+            "SELECT pg_terminate_backend($1)"
+        } else {
+            "SELECT pg_cancel_backend($1)"
+        };
+        Ok(sqlx::query_scalar(sql)
+            .bind(pid)
+            .fetch_one(&mut *conn)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::test_pool;
+    use sqlx::postgres::PgPoolOptions;
+    use sqlx::{Connection, PgConnection};
+
+    #[tokio::test]
+    async fn test_list_activity() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let (idle_pid,): (i32,) = sqlx::query_as("SELECT pg_backend_pid()")
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+
+        let activity = ActivityMonitor::list_activity(&pool).await.unwrap();
+        let idle = activity.iter().find(|a| a.pid == idle_pid).unwrap();
+        assert!(idle.idle_in_transaction);
+        assert_eq!(idle.state.as_deref(), Some("idle in transaction"));
+        assert_eq!(idle.query.as_deref(), Some("SELECT pg_backend_pid()"));
+        assert!(idle.usename.is_some());
+
+        tx.rollback().await.unwrap();
+        let activity = ActivityMonitor::list_activity(&pool).await.unwrap();
+        assert!(
+            !activity
+                .iter()
+                .find(|a| a.pid == idle_pid)
+                .unwrap()
+                .idle_in_transaction
+        );
+        let busy = ActivityFilter {
+            all_databases: true,
+            exclude_idle: true,
+        };
+        let activity = ActivityMonitor::list_backends(&pool, busy).await.unwrap();
+        assert!(activity.iter().all(|a| a.pid != idle_pid));
+
+        // With a single connection, the backend listing activity is that one
+        let single = PgPoolOptions::new()
+            .max_connections(1)
+            .connect_with((*pool.connect_options()).clone())
+            .await
+            .unwrap();
+        let (own_pid,): (i32,) = sqlx::query_as("SELECT pg_backend_pid()")
+            .fetch_one(&single)
+            .await
+            .unwrap();
+        let activity = ActivityMonitor::list_activity(&single).await.unwrap();
+        assert!(activity.iter().all(|a| a.pid != own_pid));
+    }
+
+    #[tokio::test]
+    async fn test_terminate_backend() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        // Every connection of the pool is off limits, not just the one asking
+        let mut other = pool.acquire().await.unwrap();
+        let (pooled_pid,): (i32,) = sqlx::query_as("SELECT pg_backend_pid()")
+            .fetch_one(&mut *other)
+            .await
+            .unwrap();
+        let err = ActivityMonitor::terminate_backend(&pool, pooled_pid, true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DbViewerError::InvalidQuery(_)));
+        sqlx::query("SELECT 1").execute(&mut *other).await.unwrap();
+        drop(other);
+
+        let options = (*pool.connect_options())
+            .clone()
+            .application_name("tusker-test-victim");
+        let mut victim = PgConnection::connect_with(&options).await.unwrap();
+        let (pid,): (i32,) = sqlx::query_as("SELECT pg_backend_pid()")
+            .fetch_one(&mut victim)
+            .await
+            .unwrap();
+        let sleeper = tokio::spawn(async move {
+            let slept = sqlx::query("SELECT pg_sleep(30) /* terminate-backend-test */")
+                .execute(&mut victim)
+                .await;
+            (victim, slept)
+        });
+
+        let busy = ActivityFilter {
+            all_databases: true,
+            exclude_idle: true,
+        };
+        let mut found = None;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            let activity = ActivityMonitor::list_backends(&pool, busy).await.unwrap();
+            found = activity.into_iter().find(|a| a.pid == pid);
+            if found.is_some() {
+                break;
+            }
+        }
+        let found = found.unwrap();
+        assert_eq!(found.state.as_deref(), Some("active"));
+        assert!(found.query.unwrap().contains("terminate-backend-test"));
+        assert!(found.query_duration_secs.is_some_and(|d| d >= 0.0));
+
+        assert!(ActivityMonitor::terminate_backend(&pool, pid, false)
+            .await
+            .unwrap());
+        let (mut victim, slept) = sleeper.await.unwrap();
+        assert!(slept.is_err());
+        sqlx::query("SELECT 1").execute(&mut victim).await.unwrap();
+
+        assert!(ActivityMonitor::terminate_backend(&pool, pid, true)
+            .await
+            .unwrap());
+        assert!(sqlx::query("SELECT 1").execute(&mut victim).await.is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::db::data::escape_like_pattern;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryHistoryEntry {
+    pub id: i64,
+    pub connection_id: String,
+    pub sql: String,
+    pub executed_at: String,
+    pub duration_ms: u128,
+    pub rows_returned: u64,
+    pub error: Option<String>,
+}
+
+const SELECT_ENTRY: &str =
+    "SELECT id, connection_id, sql, executed_at, duration_ms, rows_returned, error
+     FROM query_history";
+
+/// Query history kept in a SQLite database at `path`
+#[derive(Debug, Clone)]
+pub struct QueryHistoryStore {
+    path: PathBuf,
+}
+
+impl QueryHistoryStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The store in the app data directory
+    pub fn in_app_data_dir() -> Result<Self, String> {
+        let data_dir =
+            dirs::data_dir().ok_or_else(|| "Could not find app data directory".to_string())?;
+        Ok(Self::new(
+            data_dir.join("com.tusker.app").join("query_history.db"),
+        ))
+    }
+
+    fn open(&self) -> Result<Connection, String> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        }
+        let conn = Connection::open(&self.path)
+            .map_err(|e| format!("Failed to open query history database: {}", e))?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS query_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                connection_id TEXT NOT NULL,
+                sql TEXT NOT NULL,
+                executed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                rows_returned INTEGER NOT NULL,
+                error TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_query_history_connection_id
+                ON query_history(connection_id, id);",
+        )
+        .map_err(|e| format!("Failed to initialize query history table: {}", e))?;
+
+        Ok(conn)
+    }
+
+    fn read_entry(row: &rusqlite::Row<'_>) -> rusqlite::Result<QueryHistoryEntry> {
+        Ok(QueryHistoryEntry {
+            id: row.get(0)?,
+            connection_id: row.get(1)?,
+            sql: row.get(2)?,
+            executed_at: row.get(3)?,
+            duration_ms: row.get::<_, i64>(4)?.max(0) as u128,
+            rows_returned: row.get::<_, i64>(5)?.max(0) as u64,
+            error: row.get(6)?,
+        })
+    }
+
+    pub fn record_query(
+        &self,
+        connection_id: &str,
+        sql: &str,
+        duration_ms: u128,
+        rows_returned: u64,
+        error: Option<&str>,
+    ) -> Result<QueryHistoryEntry, String> {
+        let conn = self.open()?;
+        let executed_at = chrono::Utc::now().to_rfc3339();
+
+        conn.execute(
+            "INSERT INTO query_history (connection_id, sql, executed_at, duration_ms, rows_returned, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                connection_id,
+                sql,
+                executed_at,
+                i64::try_from(duration_ms).unwrap_or(i64::MAX),
+                i64::try_from(rows_returned).unwrap_or(i64::MAX),
+                error
+            ],
+        )
+        .map_err(|e| format!("Failed to record query: {}", e))?;
+
+        Ok(QueryHistoryEntry {
+            id: conn.last_insert_rowid(),
+            connection_id: connection_id.to_string(),
+            sql: sql.to_string(),
+            executed_at,
+            duration_ms,
+            rows_returned,
+            error: error.map(str::to_string),
+        })
+    }
+
+    /// Most recent queries run against `connection_id`, newest first
+    pub fn get_history(
+        &self,
+        connection_id: &str,
+        limit: usize,
+    ) -> Result<Vec<QueryHistoryEntry>, String> {
+        let conn = self.open()?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "{} WHERE connection_id = ?1 ORDER BY id DESC LIMIT ?2",
+                SELECT_ENTRY
+            ))
+            .map_err(|e| format!("Failed to query history: {}", e))?;
+
+        let entries = stmt
+            .query_map(
+                params![connection_id, i64::try_from(limit).unwrap_or(i64::MAX)],
+                Self::read_entry,
+            )
+            .map_err(|e| format!("Failed to read history: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect history: {}", e))?;
+
+        Ok(entries)
+    }
+
+    /// Up to `limit` queries across all connections whose SQL contains `query`
+    /// (case-insensitive), newest first
+    pub fn search_history(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<QueryHistoryEntry>, String> {
+        let conn = self.open()?;
+
+        let pattern = format!("%{}%", escape_like_pattern(query));
+        let mut stmt = conn
+            .prepare(&format!(
+                "{} WHERE sql LIKE ?1 ESCAPE '\\' ORDER BY id DESC LIMIT ?2",
+                SELECT_ENTRY
+            ))
+            .map_err(|e| format!("Failed to search history: {}", e))?;
+
+        let entries = stmt
+            .query_map(
+                params![pattern, i64::try_from(limit).unwrap_or(i64::MAX)],
+                Self::read_entry,
+            )
+            .map_err(|e| format!("Failed to read history: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect history: {}", e))?;
+
+        Ok(entries)
+    }
+
+    pub fn clear_history(&self, connection_id: &str) -> Result<(), String> {
+        let conn = self.open()?;
+        conn.execute(
+            "DELETE FROM query_history WHERE connection_id = ?1",
+            params![connection_id],
+        )
+        .map_err(|e| format!("Failed to clear history: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_read_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = QueryHistoryStore::new(dir.path().join("query_history.db"));
+        let connection_id = uuid::Uuid::new_v4().to_string();
+        let marker = uuid::Uuid::new_v4().simple().to_string();
+
+        store
+            .record_query(&connection_id, "SELECT 1", 3, 1, None)
+            .unwrap();
+        let failed = store
+            .record_query(
+                &connection_id,
+                &format!("SELECT * FROM missing_{}", marker),
+                1,
+                0,
+                Some("relation does not exist"),
+            )
+            .unwrap();
+        store
+            .record_query(&connection_id, "SELECT 100%", 2, 1, None)
+            .unwrap();
+        store
+            .record_query("other", "SELECT 100% FROM other", 2, 1, None)
+            .unwrap();
+
+        let history = store.get_history(&connection_id, 2).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].sql, "SELECT 100%");
+        assert_eq!(history[1].id, failed.id);
+        assert_eq!(history[1].error.as_deref(), Some("relation does not exist"));
+
+        // Search is case-insensitive and spans connections
+        let found = store.search_history(&marker.to_uppercase(), 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].connection_id, connection_id);
+        let found = store.search_history("select", 10).unwrap();
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[0].connection_id, "other");
+
+        // LIKE wildcards in the search text match literally
+        let found = store.search_history("100%", 10).unwrap();
+        assert_eq!(found.len(), 2);
+        let found = store.search_history("100%", 1).unwrap();
+        assert_eq!(found[0].sql, "SELECT 100% FROM other");
+
+        store.clear_history(&connection_id).unwrap();
+        assert!(store.get_history(&connection_id, 10).unwrap().is_empty());
+        assert_eq!(store.get_history("other", 10).unwrap().len(), 1);
+    }
+}
@@ -1,6 +1,9 @@
-use crate::error::Result;
+use crate::error::{DbViewerError, Result};
+use chrono::{DateTime, Utc};
+use futures_util::future::try_join_all;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaInfo {
@@ -88,7 +91,7 @@ pub struct ConstraintInfo {
     pub definition: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ConstraintType {
     PrimaryKey,
@@ -111,6 +114,209 @@ impl From<String> for ConstraintType {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerInfo {
+    pub name: String,
+    /// Firing events joined with OR, e.g. `INSERT OR UPDATE`
+    pub event: String,
+    /// `INSERT`, `UPDATE`, `DELETE` and/or `TRUNCATE`
+    pub events: Vec<String>,
+    /// `BEFORE`, `AFTER` or `INSTEAD OF`
+    pub timing: String,
+    pub table: String,
+    /// The trigger function, schema-qualified when not on the search path
+    pub function_name: String,
+    pub enabled: bool,
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RlsPolicy {
+    pub name: String,
+    pub table: String,
+    pub schema: String,
+    /// `ALL`, `SELECT`, `INSERT`, `UPDATE` or `DELETE`
+    pub command: String,
+    /// False for restrictive policies
+    pub permissive: bool,
+    /// `public` when the policy applies to every role
+    pub roles: Vec<String>,
+    pub using_expr: Option<String>,
+    pub check_expr: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewDefinition {
+    pub schema: String,
+    pub name: String,
+    /// The view's `SELECT` statement
+    pub definition: String,
+    pub materialized: bool,
+    /// Whether a materialized view has data to read; `None` for plain views
+    pub populated: Option<bool>,
+    /// `LOCAL` or `CASCADED` for a view created `WITH CHECK OPTION`
+    pub check_option: Option<String>,
+    /// Whether rows can be inserted, updated and deleted through the view
+    pub is_updatable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceInfo {
+    pub schema: String,
+    pub name: String,
+    pub data_type: String,
+    pub start: i64,
+    pub increment: i64,
+    pub min_value: i64,
+    pub max_value: i64,
+    pub cycle: bool,
+    /// Last value handed out; `None` before the first `nextval` or without
+    /// privileges on the sequence
+    pub last_value: Option<i64>,
+    /// Owning column as `table.column`, for SERIAL and identity sequences
+    pub owned_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionKind {
+    Function,
+    Procedure,
+    Aggregate,
+    Window,
+}
+
+impl From<String> for FunctionKind {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "p" => FunctionKind::Procedure,
+            "a" => FunctionKind::Aggregate,
+            "w" => FunctionKind::Window,
+            _ => FunctionKind::Function,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionInfo {
+    pub schema: String,
+    pub name: String,
+    pub oid: u32,
+    pub kind: FunctionKind,
+    pub language: String,
+    pub argument_types: Vec<String>,
+    /// Empty for procedures
+    pub return_type: String,
+    /// `immutable`, `stable` or `volatile`
+    pub volatility: String,
+    pub security_definer: bool,
+    /// `CREATE FUNCTION` statement; `None` for aggregates, which have no
+    /// function body to print
+    pub definition: Option<String>,
+}
+
+/// A type made with `CREATE TYPE ... AS (...)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositeType {
+    pub schema: String,
+    pub name: String,
+    pub attributes: Vec<CompositeAttribute>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositeAttribute {
+    pub name: String,
+    pub data_type: String,
+    pub is_nullable: bool,
+}
+
+/// A type made with `CREATE DOMAIN`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainInfo {
+    pub schema: String,
+    pub name: String,
+    pub base_type: String,
+    pub not_null: bool,
+    pub default_value: Option<String>,
+    /// Each CHECK constraint as `CHECK (...)`, by constraint name
+    pub check_constraints: Vec<String>,
+}
+
+/// Activity counters for a table from `pg_stat_user_tables` and `pg_statio_user_tables`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableStats {
+    pub schema: String,
+    pub table: String,
+    pub seq_scans: i64,
+    pub idx_scans: i64,
+    pub n_live_tup: i64,
+    pub n_dead_tup: i64,
+    pub last_vacuum: Option<DateTime<Utc>>,
+    pub last_autovacuum: Option<DateTime<Utc>>,
+    pub last_analyze: Option<DateTime<Utc>>,
+    pub heap_blks_read: i64,
+    pub heap_blks_hit: i64,
+    /// Share of heap block reads served from shared buffers
+    pub cache_hit_ratio: f64,
+}
+
+/// How often an index is used, from `pg_stat_user_indexes`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexUsageStats {
+    pub schema: String,
+    pub table: String,
+    pub index: String,
+    pub idx_scan: i64,
+    pub idx_tup_read: i64,
+    pub idx_tup_fetch: i64,
+    pub size_bytes: i64,
+    /// Time of the last scan; only tracked from PostgreSQL 16
+    pub last_used: Option<DateTime<Utc>>,
+    /// Never scanned, on a table estimated at more than
+    /// `UNUSED_INDEX_MIN_ROWS` rows
+    pub is_unused: bool,
+}
+
+/// Tables at most this big are often read by sequential scan even with a
+/// usable index, so their unscanned indexes aren't flagged as unused
+pub const UNUSED_INDEX_MIN_ROWS: i64 = 1000;
+
+/// Disk usage of a table, split into the heap, its indexes and TOAST
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableSizeInfo {
+    pub schema: String,
+    pub table: String,
+    pub table_bytes: i64,
+    pub index_bytes: i64,
+    /// TOAST plus the free space and visibility maps
+    pub toast_bytes: i64,
+    pub total_bytes: i64,
+    /// Planner estimate; `None` until the table is first vacuumed or analyzed
+    pub row_estimate: Option<i64>,
+    /// Dead tuples not yet vacuumed away, a rough measure of bloat
+    pub dead_rows: i64,
+    /// `pg_size_pretty` of `table_bytes`
+    pub table_human: String,
+    /// `pg_size_pretty` of `total_bytes`
+    pub total_human: String,
+}
+
+/// Planner statistics for one column, as of the last `ANALYZE`. Every
+/// statistic is `None` when the column has not been analyzed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgColumnStats {
+    pub column: String,
+    /// Fraction of rows that are null
+    pub null_frac: Option<f32>,
+    /// Distinct values; negative values are a fraction of the row count,
+    /// e.g. -1 for a unique column
+    pub n_distinct: Option<f32>,
+    /// Average width in bytes of the non-null values
+    pub avg_width: Option<i32>,
+    /// Most frequent values as text, most frequent first
+    pub most_common_vals: Option<Vec<String>>,
+}
+
 pub struct SchemaIntrospector;
 
 impl SchemaIntrospector {
@@ -324,6 +530,300 @@ impl SchemaIntrospector {
         Ok(count.0)
     }
 
+    /// Get scan, tuple, vacuum and cache statistics for a table
+    pub async fn get_table_stats(pool: &PgPool, schema: &str, table: &str) -> Result<TableStats> {
+        type StatsRow = (
+            i64,
+            i64,
+            i64,
+            i64,
+            Option<DateTime<Utc>>,
+            Option<DateTime<Utc>>,
+            Option<DateTime<Utc>>,
+            i64,
+            i64,
+        );
+
+        let row = sqlx::query_as::<_, StatsRow>(
+            r#"
+            SELECT
+                COALESCE(s.seq_scan, 0),
+                COALESCE(s.idx_scan, 0),
+                s.n_live_tup,
+                s.n_dead_tup,
+                s.last_vacuum,
+                s.last_autovacuum,
+                s.last_analyze,
+                COALESCE(io.heap_blks_read, 0),
+                COALESCE(io.heap_blks_hit, 0)
+            FROM pg_stat_user_tables s
+            JOIN pg_statio_user_tables io ON io.relid = s.relid
+            WHERE s.schemaname = $1 AND s.relname = $2
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| DbViewerError::TableNotFound(format!("{}.{}", schema, table)))?;
+
+        let (
+            seq_scans,
+            idx_scans,
+            n_live_tup,
+            n_dead_tup,
+            last_vacuum,
+            last_autovacuum,
+            last_analyze,
+            heap_blks_read,
+            heap_blks_hit,
+        ) = row;
+
+        Ok(TableStats {
+            schema: schema.to_string(),
+            table: table.to_string(),
+            seq_scans,
+            idx_scans,
+            n_live_tup,
+            n_dead_tup,
+            last_vacuum,
+            last_autovacuum,
+            last_analyze,
+            heap_blks_read,
+            heap_blks_hit,
+            cache_hit_ratio: heap_blks_hit as f64 / (heap_blks_hit + heap_blks_read).max(1) as f64,
+        })
+    }
+
+    /// Get the heap, index and TOAST size of a table or materialized view
+    pub async fn get_table_size(pool: &PgPool, schema: &str, table: &str) -> Result<TableSizeInfo> {
+        Self::fetch_table_sizes(pool, schema, Some(table))
+            .await?
+            .pop()
+            .ok_or_else(|| DbViewerError::TableNotFound(format!("{}.{}", schema, table)))
+    }
+
+    /// Get the sizes of every table and materialized view in a schema,
+    /// largest first
+    pub async fn get_all_table_sizes(pool: &PgPool, schema: &str) -> Result<Vec<TableSizeInfo>> {
+        Self::fetch_table_sizes(pool, schema, None).await
+    }
+
+    async fn fetch_table_sizes(
+        pool: &PgPool,
+        schema: &str,
+        table: Option<&str>,
+    ) -> Result<Vec<TableSizeInfo>> {
+        type SizeRow = (String, i64, i64, i64, Option<i64>, i64, String, String);
+
+        let rows = sqlx::query_as::<_, SizeRow>(
+            r#"
+            SELECT
+                c.relname,
+                pg_relation_size(c.oid),
+                pg_indexes_size(c.oid),
+                pg_total_relation_size(c.oid),
+                CASE WHEN c.reltuples < 0 THEN NULL ELSE c.reltuples::bigint END,
+                COALESCE(s.n_dead_tup, 0),
+                pg_size_pretty(pg_relation_size(c.oid)),
+                pg_size_pretty(pg_total_relation_size(c.oid))
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            LEFT JOIN pg_stat_all_tables s ON s.relid = c.oid
+            WHERE n.nspname = $1
+              AND ($2::text IS NULL OR c.relname = $2)
+              AND c.relkind IN ('r', 'm', 'p')
+            ORDER BY pg_total_relation_size(c.oid) DESC, c.relname
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(
+                    table,
+                    table_bytes,
+                    index_bytes,
+                    total_bytes,
+                    row_estimate,
+                    dead_rows,
+                    table_human,
+                    total_human,
+                )| TableSizeInfo {
+                    schema: schema.to_string(),
+                    table,
+                    table_bytes,
+                    index_bytes,
+                    toast_bytes: total_bytes - table_bytes - index_bytes,
+                    total_bytes,
+                    row_estimate,
+                    dead_rows,
+                    table_human,
+                    total_human,
+                },
+            )
+            .collect())
+    }
+
+    /// Get the `pg_stats` entry of every column of a table, in column order
+    pub async fn get_column_stats(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<PgColumnStats>> {
+        let qualified_table = format!("{}.{}", quote_identifier(schema), quote_identifier(table));
+        let oid = sqlx::query_scalar::<_, Option<sqlx::postgres::types::Oid>>(
+            "SELECT to_regclass($1)::oid",
+        )
+        .bind(&qualified_table)
+        .fetch_one(pool)
+        .await?
+        .ok_or_else(|| DbViewerError::TableNotFound(format!("{}.{}", schema, table)))?;
+
+        type StatsRow = (
+            String,
+            Option<f32>,
+            Option<f32>,
+            Option<i32>,
+            Option<Vec<String>>,
+        );
+
+        // Partitioned tables only have stats covering their partitions
+        let rows = sqlx::query_as::<_, StatsRow>(
+            r#"
+            SELECT
+                a.attname,
+                s.null_frac,
+                s.n_distinct,
+                s.avg_width,
+                s.most_common_vals::text::text[]
+            FROM pg_attribute a
+            JOIN pg_class c ON c.oid = a.attrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            LEFT JOIN pg_stats s
+              ON s.schemaname = n.nspname
+             AND s.tablename = c.relname
+             AND s.attname = a.attname
+             AND s.inherited = (c.relkind = 'p')
+            WHERE a.attrelid = $1
+              AND a.attnum > 0
+              AND NOT a.attisdropped
+            ORDER BY a.attnum
+            "#,
+        )
+        .bind(oid)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(column, null_frac, n_distinct, avg_width, most_common_vals)| PgColumnStats {
+                    column,
+                    null_frac,
+                    n_distinct,
+                    avg_width,
+                    most_common_vals,
+                },
+            )
+            .collect())
+    }
+
+    /// Get usage counters for every index in a schema, least used first
+    pub async fn get_index_usage_stats(
+        pool: &PgPool,
+        schema: &str,
+    ) -> Result<Vec<IndexUsageStats>> {
+        type UsageRow = (
+            String,
+            String,
+            i64,
+            i64,
+            i64,
+            i64,
+            Option<DateTime<Utc>>,
+            bool,
+        );
+
+        // last_idx_scan is read through to_jsonb, as servers before 16 lack it
+        let rows = sqlx::query_as::<_, UsageRow>(
+            r#"
+            SELECT
+                s.relname,
+                s.indexrelname,
+                s.idx_scan,
+                s.idx_tup_read,
+                s.idx_tup_fetch,
+                pg_relation_size(s.indexrelid),
+                (to_jsonb(s) ->> 'last_idx_scan')::timestamptz,
+                s.idx_scan = 0 AND c.reltuples > $2
+            FROM pg_stat_user_indexes s
+            JOIN pg_class c ON c.oid = s.relid
+            WHERE s.schemaname = $1
+            ORDER BY s.idx_scan, pg_relation_size(s.indexrelid) DESC, s.indexrelname
+            "#,
+        )
+        .bind(schema)
+        .bind(UNUSED_INDEX_MIN_ROWS as f32)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(
+                    table,
+                    index,
+                    idx_scan,
+                    idx_tup_read,
+                    idx_tup_fetch,
+                    size_bytes,
+                    last_used,
+                    is_unused,
+                )| IndexUsageStats {
+                    schema: schema.to_string(),
+                    table,
+                    index,
+                    idx_scan,
+                    idx_tup_read,
+                    idx_tup_fetch,
+                    size_bytes,
+                    last_used,
+                    is_unused,
+                },
+            )
+            .collect())
+    }
+
+    /// Get the planner's row estimate for a table or materialized view, read
+    /// from `pg_class.reltuples` instead of scanning. Relations that have never
+    /// been vacuumed or analyzed have no estimate and are counted exactly.
+    pub async fn get_row_count_estimate(pool: &PgPool, schema: &str, table: &str) -> Result<i64> {
+        let estimate = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT c.reltuples::bigint
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1 AND c.relname = $2
+              AND c.relkind IN ('r', 'm', 'p', 'f')
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| DbViewerError::TableNotFound(format!("{}.{}", schema, table)))?;
+
+        if estimate < 0 {
+            return Self::get_row_count(pool, schema, table).await;
+        }
+        Ok(estimate)
+    }
+
     /// Get indexes for a table
     pub async fn get_indexes(pool: &PgPool, schema: &str, table: &str) -> Result<Vec<IndexInfo>> {
         let indexes = sqlx::query_as::<_, (String, bool, bool, String, Vec<String>)>(
@@ -411,70 +911,596 @@ impl SchemaIntrospector {
             })
             .collect())
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SchemaWithTables {
-    pub name: String,
-    pub owner: Option<String>,
-    pub tables: Vec<TableInfo>,
-}
+    /// Get user-defined triggers for a table
+    pub async fn get_triggers(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<TriggerInfo>> {
+        // tgtype bits: 2 = BEFORE, 4 = INSERT, 8 = DELETE, 16 = UPDATE,
+        // 32 = TRUNCATE, 64 = INSTEAD OF
+        type TriggerRow = (String, Vec<String>, String, String, String, bool, String);
 
-impl SchemaIntrospector {
-    /// Get all schemas with their tables in a single operation
-    pub async fn get_schemas_with_tables(pool: &PgPool) -> Result<Vec<SchemaWithTables>> {
-        // Run all three queries concurrently
-        let (schemas_result, tables_result, mat_views_result) = tokio::join!(
-            Self::get_schemas(pool),
-            // Fetch tables for ALL schemas at once using pg_catalog (faster than information_schema)
-            sqlx::query_as::<_, (String, String, String, Option<i64>, Option<String>)>(
-                r#"
-                SELECT
-                    n.nspname AS table_schema,
-                    c.relname AS table_name,
-                    CASE c.relkind
-                        WHEN 'r' THEN 'BASE TABLE'
-                        WHEN 'v' THEN 'VIEW'
-                        WHEN 'f' THEN 'FOREIGN TABLE'
-                        ELSE 'BASE TABLE'
-                    END AS table_type,
-                    c.reltuples::bigint AS estimated_row_count,
-                    obj_description(c.oid, 'pg_class') AS description
-                FROM pg_class c
-                JOIN pg_namespace n ON n.oid = c.relnamespace
-                WHERE n.nspname NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
-                  AND n.nspname NOT LIKE 'pg_temp_%'
-                  AND n.nspname NOT LIKE 'pg_toast_temp_%'
-                  AND c.relkind IN ('r', 'v', 'f')
-                ORDER BY n.nspname, c.relname
-                "#,
-            )
-            .fetch_all(pool),
-            // Materialized views
-            sqlx::query_as::<_, (String, String, Option<i64>, Option<String>)>(
-                r#"
-                SELECT
-                    n.nspname,
-                    c.relname,
-                    c.reltuples::bigint,
-                    obj_description(c.oid, 'pg_class')
-                FROM pg_class c
-                JOIN pg_namespace n ON n.oid = c.relnamespace
-                WHERE c.relkind = 'm'
-                  AND n.nspname NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
-                ORDER BY n.nspname, c.relname
-                "#,
+        let triggers = sqlx::query_as::<_, TriggerRow>(
+            r#"
+            SELECT
+                t.tgname,
+                ARRAY_REMOVE(ARRAY[
+                    CASE WHEN t.tgtype::int & 4 <> 0 THEN 'INSERT' END,
+                    CASE WHEN t.tgtype::int & 16 <> 0 THEN 'UPDATE' END,
+                    CASE WHEN t.tgtype::int & 8 <> 0 THEN 'DELETE' END,
+                    CASE WHEN t.tgtype::int & 32 <> 0 THEN 'TRUNCATE' END
+                ], NULL),
+                CASE
+                    WHEN t.tgtype::int & 64 <> 0 THEN 'INSTEAD OF'
+                    WHEN t.tgtype::int & 2 <> 0 THEN 'BEFORE'
+                    ELSE 'AFTER'
+                END,
+                c.relname,
+                p.oid::regproc::text,
+                t.tgenabled <> 'D',
+                pg_get_triggerdef(t.oid)
+            FROM pg_trigger t
+            JOIN pg_class c ON c.oid = t.tgrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            JOIN pg_proc p ON p.oid = t.tgfoid
+            WHERE n.nspname = $1
+              AND c.relname = $2
+              AND NOT t.tgisinternal
+            ORDER BY t.tgname
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(triggers
+            .into_iter()
+            .map(
+                |(name, events, timing, table, function_name, enabled, definition)| TriggerInfo {
+                    name,
+                    event: events.join(" OR "),
+                    events,
+                    timing,
+                    table,
+                    function_name,
+                    enabled,
+                    definition,
+                },
             )
-            .fetch_all(pool),
-        );
+            .collect())
+    }
 
-        let schemas = schemas_result?;
-        let all_tables = tables_result?;
-        let mat_views = mat_views_result.unwrap_or_default();
+    /// Get the `CREATE TRIGGER` statement for a trigger
+    pub async fn get_trigger_definition(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        name: &str,
+    ) -> Result<String> {
+        let definition = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT pg_get_triggerdef(t.oid)
+            FROM pg_trigger t
+            JOIN pg_class c ON c.oid = t.tgrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1
+              AND c.relname = $2
+              AND t.tgname = $3
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
 
-        // Group tables by schema
-        let mut tables_by_schema: std::collections::HashMap<String, Vec<TableInfo>> =
-            std::collections::HashMap::new();
+        definition.ok_or_else(|| {
+            DbViewerError::InvalidQuery(format!(
+                "Trigger {} not found on {}.{}",
+                name, schema, table
+            ))
+        })
+    }
+
+    /// Get the query behind a view or materialized view
+    pub async fn get_view_definition(
+        pool: &PgPool,
+        schema: &str,
+        name: &str,
+    ) -> Result<ViewDefinition> {
+        Self::fetch_views(pool, schema, Some(name))
+            .await?
+            .pop()
+            .ok_or_else(|| DbViewerError::TableNotFound(format!("{}.{}", schema, name)))
+    }
+
+    /// Get every view and materialized view in a schema along with its query
+    pub async fn get_views_with_definitions(
+        pool: &PgPool,
+        schema: &str,
+    ) -> Result<Vec<ViewDefinition>> {
+        Self::fetch_views(pool, schema, None).await
+    }
+
+    async fn fetch_views(
+        pool: &PgPool,
+        schema: &str,
+        name: Option<&str>,
+    ) -> Result<Vec<ViewDefinition>> {
+        type ViewRow = (String, String, bool, bool, Option<String>, bool);
+
+        // information_schema.views only covers plain views, so materialized
+        // views are never updatable
+        let rows = sqlx::query_as::<_, ViewRow>(
+            r#"
+            SELECT
+                c.relname,
+                pg_get_viewdef(c.oid, true),
+                c.relkind = 'm',
+                c.relispopulated,
+                NULLIF(v.check_option::text, 'NONE'),
+                COALESCE(v.is_updatable::text = 'YES', false)
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            LEFT JOIN information_schema.views v
+              ON v.table_schema = n.nspname AND v.table_name = c.relname
+            WHERE n.nspname = $1
+              AND ($2::text IS NULL OR c.relname = $2)
+              AND c.relkind IN ('v', 'm')
+            ORDER BY c.relname
+            "#,
+        )
+        .bind(schema)
+        .bind(name)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(name, definition, materialized, populated, check_option, is_updatable)| {
+                    ViewDefinition {
+                        schema: schema.to_string(),
+                        name,
+                        definition,
+                        materialized,
+                        populated: materialized.then_some(populated),
+                        check_option,
+                        is_updatable,
+                    }
+                },
+            )
+            .collect())
+    }
+
+    /// Get the row-level security policies on a table
+    pub async fn get_row_security_policies(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<RlsPolicy>> {
+        let policies = sqlx::query_as::<
+            _,
+            (
+                String,
+                String,
+                String,
+                String,
+                bool,
+                Vec<String>,
+                Option<String>,
+                Option<String>,
+            ),
+        >(
+            r#"
+            SELECT
+                p.polname::text,
+                c.relname::text,
+                n.nspname::text,
+                CASE p.polcmd
+                    WHEN 'r' THEN 'SELECT'
+                    WHEN 'a' THEN 'INSERT'
+                    WHEN 'w' THEN 'UPDATE'
+                    WHEN 'd' THEN 'DELETE'
+                    ELSE 'ALL'
+                END,
+                p.polpermissive,
+                ARRAY(
+                    SELECT CASE WHEN r = 0 THEN 'public' ELSE pg_get_userbyid(r)::text END
+                    FROM unnest(p.polroles) AS r
+                    ORDER BY 1
+                ),
+                pg_get_expr(p.polqual, p.polrelid),
+                pg_get_expr(p.polwithcheck, p.polrelid)
+            FROM pg_policy p
+            JOIN pg_class c ON c.oid = p.polrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1
+              AND c.relname = $2
+            ORDER BY p.polname
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(policies
+            .into_iter()
+            .map(
+                |(name, table, schema, command, permissive, roles, using_expr, check_expr)| {
+                    RlsPolicy {
+                        name,
+                        table,
+                        schema,
+                        command,
+                        permissive,
+                        roles,
+                        using_expr,
+                        check_expr,
+                    }
+                },
+            )
+            .collect())
+    }
+
+    /// Whether row-level security is enabled on a table
+    pub async fn get_rls_enabled(pool: &PgPool, schema: &str, table: &str) -> Result<bool> {
+        let enabled = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT c.relrowsecurity
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1
+              AND c.relname = $2
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_optional(pool)
+        .await?;
+
+        enabled.ok_or_else(|| DbViewerError::TableNotFound(format!("{}.{}", schema, table)))
+    }
+
+    /// Get sequences in a schema
+    pub async fn get_sequences(pool: &PgPool, schema: &str) -> Result<Vec<SequenceInfo>> {
+        type SequenceRow = (
+            String,
+            String,
+            i64,
+            i64,
+            i64,
+            i64,
+            bool,
+            Option<i64>,
+            Option<String>,
+        );
+
+        // pg_sequences reads last_value without advancing the sequence, and
+        // gives NULL instead of an error when it isn't readable
+        let sequences = sqlx::query_as::<_, SequenceRow>(
+            r#"
+            SELECT
+                c.relname,
+                format_type(s.seqtypid, NULL),
+                s.seqstart,
+                s.seqincrement,
+                s.seqmin,
+                s.seqmax,
+                s.seqcycle,
+                ps.last_value,
+                t.relname || '.' || a.attname
+            FROM pg_sequence s
+            JOIN pg_class c ON c.oid = s.seqrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            JOIN pg_sequences ps ON ps.schemaname = n.nspname AND ps.sequencename = c.relname
+            LEFT JOIN pg_depend d ON d.classid = 'pg_class'::regclass
+                AND d.objid = c.oid
+                AND d.refclassid = 'pg_class'::regclass
+                AND d.deptype IN ('a', 'i')
+            LEFT JOIN pg_class t ON t.oid = d.refobjid
+            LEFT JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+            WHERE n.nspname = $1
+            ORDER BY c.relname
+            "#,
+        )
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sequences
+            .into_iter()
+            .map(
+                |(
+                    name,
+                    data_type,
+                    start,
+                    increment,
+                    min_value,
+                    max_value,
+                    cycle,
+                    last_value,
+                    owned_by,
+                )| SequenceInfo {
+                    schema: schema.to_string(),
+                    name,
+                    data_type,
+                    start,
+                    increment,
+                    min_value,
+                    max_value,
+                    cycle,
+                    last_value,
+                    owned_by,
+                },
+            )
+            .collect())
+    }
+
+    /// Get user-defined functions, procedures and aggregates in a schema,
+    /// leaving out ones that belong to an extension
+    pub async fn get_functions(pool: &PgPool, schema: &str) -> Result<Vec<FunctionInfo>> {
+        let functions = sqlx::query_as::<
+            _,
+            (
+                String,
+                i64,
+                String,
+                String,
+                Vec<String>,
+                String,
+                String,
+                bool,
+                Option<String>,
+            ),
+        >(
+            r#"
+            SELECT
+                p.proname,
+                p.oid::int8,
+                p.prokind::text,
+                l.lanname,
+                ARRAY(
+                    SELECT format_type(a.type_oid, NULL)
+                    FROM unnest(p.proargtypes::oid[]) WITH ORDINALITY a(type_oid, n)
+                    ORDER BY a.n
+                ),
+                COALESCE(pg_get_function_result(p.oid), ''),
+                CASE p.provolatile
+                    WHEN 'i' THEN 'immutable'
+                    WHEN 's' THEN 'stable'
+                    ELSE 'volatile'
+                END,
+                p.prosecdef,
+                CASE WHEN p.prokind = 'a' THEN NULL ELSE pg_get_functiondef(p.oid) END
+            FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE n.nspname = $1
+              AND NOT EXISTS (
+                  SELECT 1 FROM pg_depend d
+                  WHERE d.classid = 'pg_proc'::regclass
+                    AND d.objid = p.oid
+                    AND d.deptype = 'e'
+              )
+            ORDER BY p.proname, p.oid
+            "#,
+        )
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(functions
+            .into_iter()
+            .map(
+                |(
+                    name,
+                    oid,
+                    kind,
+                    language,
+                    argument_types,
+                    return_type,
+                    volatility,
+                    security_definer,
+                    definition,
+                )| FunctionInfo {
+                    schema: schema.to_string(),
+                    name,
+                    oid: oid as u32,
+                    kind: FunctionKind::from(kind),
+                    language,
+                    argument_types,
+                    return_type,
+                    volatility,
+                    security_definer,
+                    definition,
+                },
+            )
+            .collect())
+    }
+
+    /// Get the composite types in a schema, with their attributes in order.
+    /// Row types that come with tables and views are left out.
+    pub async fn get_composite_types(pool: &PgPool, schema: &str) -> Result<Vec<CompositeType>> {
+        type AttributeRow = (String, Option<String>, Option<String>, Option<bool>);
+
+        // LEFT JOIN keeps types declared with no attributes
+        let rows = sqlx::query_as::<_, AttributeRow>(
+            r#"
+            SELECT
+                t.typname,
+                a.attname,
+                format_type(a.atttypid, a.atttypmod),
+                NOT a.attnotnull
+            FROM pg_type t
+            JOIN pg_namespace n ON n.oid = t.typnamespace
+            JOIN pg_class c ON c.oid = t.typrelid AND c.relkind = 'c'
+            LEFT JOIN pg_attribute a ON a.attrelid = c.oid
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+            WHERE n.nspname = $1 AND t.typtype = 'c'
+            ORDER BY t.typname, a.attnum
+            "#,
+        )
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+
+        let mut types: Vec<CompositeType> = Vec::new();
+        for (type_name, attribute, data_type, is_nullable) in rows {
+            if types.last().is_none_or(|t| t.name != type_name) {
+                types.push(CompositeType {
+                    schema: schema.to_string(),
+                    name: type_name,
+                    attributes: Vec::new(),
+                });
+            }
+            if let (Some(name), Some(data_type), Some(is_nullable)) =
+                (attribute, data_type, is_nullable)
+            {
+                if let Some(composite) = types.last_mut() {
+                    composite.attributes.push(CompositeAttribute {
+                        name,
+                        data_type,
+                        is_nullable,
+                    });
+                }
+            }
+        }
+        Ok(types)
+    }
+
+    /// Get the domains in a schema
+    pub async fn get_domains(pool: &PgPool, schema: &str) -> Result<Vec<DomainInfo>> {
+        type DomainRow = (String, String, bool, Option<String>, Vec<String>);
+
+        let domains = sqlx::query_as::<_, DomainRow>(
+            r#"
+            SELECT
+                t.typname,
+                format_type(t.typbasetype, t.typtypmod),
+                t.typnotnull,
+                t.typdefault,
+                COALESCE(
+                    array_agg(pg_get_constraintdef(con.oid) ORDER BY con.conname)
+                        FILTER (WHERE con.oid IS NOT NULL),
+                    '{}'
+                )
+            FROM pg_type t
+            JOIN pg_namespace n ON n.oid = t.typnamespace
+            LEFT JOIN pg_constraint con ON con.contypid = t.oid AND con.contype = 'c'
+            WHERE n.nspname = $1 AND t.typtype = 'd'
+            GROUP BY t.oid
+            ORDER BY t.typname
+            "#,
+        )
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(domains
+            .into_iter()
+            .map(
+                |(name, base_type, not_null, default_value, check_constraints)| DomainInfo {
+                    schema: schema.to_string(),
+                    name,
+                    base_type,
+                    not_null,
+                    default_value,
+                    check_constraints,
+                },
+            )
+            .collect())
+    }
+
+    /// Enable or disable a trigger
+    pub async fn set_trigger_enabled(
+        pool: &PgPool,
+        schema: &str,
+        table: &str,
+        name: &str,
+        enabled: bool,
+    ) -> Result<()> {
+        let query = format!(
+            "ALTER TABLE {}.{} {} TRIGGER {}",
+            quote_identifier(schema),
+            quote_identifier(table),
+            if enabled { "ENABLE" } else { "DISABLE" },
+            quote_identifier(name)
+        );
+
+        sqlx::query(&query).execute(pool).await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaWithTables {
+    pub name: String,
+    pub owner: Option<String>,
+    pub tables: Vec<TableInfo>,
+}
+
+impl SchemaIntrospector {
+    /// Get all schemas with their tables in a single operation
+    pub async fn get_schemas_with_tables(pool: &PgPool) -> Result<Vec<SchemaWithTables>> {
+        // Run all three queries concurrently
+        let (schemas_result, tables_result, mat_views_result) = tokio::join!(
+            Self::get_schemas(pool),
+            // Fetch tables for ALL schemas at once using pg_catalog (faster than information_schema)
+            sqlx::query_as::<_, (String, String, String, Option<i64>, Option<String>)>(
+                r#"
+                SELECT
+                    n.nspname AS table_schema,
+                    c.relname AS table_name,
+                    CASE c.relkind
+                        WHEN 'r' THEN 'BASE TABLE'
+                        WHEN 'v' THEN 'VIEW'
+                        WHEN 'f' THEN 'FOREIGN TABLE'
+                        ELSE 'BASE TABLE'
+                    END AS table_type,
+                    c.reltuples::bigint AS estimated_row_count,
+                    obj_description(c.oid, 'pg_class') AS description
+                FROM pg_class c
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE n.nspname NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
+                  AND n.nspname NOT LIKE 'pg_temp_%'
+                  AND n.nspname NOT LIKE 'pg_toast_temp_%'
+                  AND c.relkind IN ('r', 'v', 'f')
+                ORDER BY n.nspname, c.relname
+                "#,
+            )
+            .fetch_all(pool),
+            // Materialized views
+            sqlx::query_as::<_, (String, String, Option<i64>, Option<String>)>(
+                r#"
+                SELECT
+                    n.nspname,
+                    c.relname,
+                    c.reltuples::bigint,
+                    obj_description(c.oid, 'pg_class')
+                FROM pg_class c
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE c.relkind = 'm'
+                  AND n.nspname NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
+                ORDER BY n.nspname, c.relname
+                "#,
+            )
+            .fetch_all(pool),
+        );
+
+        let schemas = schemas_result?;
+        let all_tables = tables_result?;
+        let mat_views = mat_views_result.unwrap_or_default();
+
+        // Group tables by schema
+        let mut tables_by_schema: std::collections::HashMap<String, Vec<TableInfo>> =
+            std::collections::HashMap::new();
 
         for (schema, name, table_type, estimated_row_count, description) in all_tables {
             tables_by_schema
@@ -676,7 +1702,905 @@ impl SchemaIntrospector {
     }
 }
 
+// ============================================================================
+// Schema Comparison
+// ============================================================================
+
+/// How an object in the second database differs from the first
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    /// Present in the first database only
+    Missing,
+    /// Present in the second database only
+    Extra,
+    /// Present in both with different definitions
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDiff {
+    pub table: String,
+    pub column: String,
+    pub kind: DiffKind,
+    /// Names of the properties that differ, for `Changed`
+    pub differences: Vec<String>,
+    pub a: Option<ColumnInfo>,
+    pub b: Option<ColumnInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexDiff {
+    pub table: String,
+    pub name: String,
+    pub kind: DiffKind,
+    pub a: Option<IndexInfo>,
+    pub b: Option<IndexInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstraintDiff {
+    pub table: String,
+    pub name: String,
+    pub kind: DiffKind,
+    pub a: Option<ConstraintInfo>,
+    pub b: Option<ConstraintInfo>,
+}
+
+/// Differences in one schema between two databases, `a` and `b`. Missing
+/// objects exist only in `a`, extra ones only in `b`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub missing_tables: Vec<TableInfo>,
+    pub extra_tables: Vec<TableInfo>,
+    pub column_diffs: Vec<ColumnDiff>,
+    pub index_diffs: Vec<IndexDiff>,
+    pub constraint_diffs: Vec<ConstraintDiff>,
+}
+
+/// Everything compared for one schema, keyed by table name
+struct SchemaSnapshot {
+    tables: BTreeMap<String, TableInfo>,
+    columns: BTreeMap<String, Vec<ColumnInfo>>,
+    indexes: BTreeMap<String, Vec<IndexInfo>>,
+    constraints: BTreeMap<String, Vec<ConstraintInfo>>,
+}
+
+impl SchemaIntrospector {
+    /// Compare `schema` in two databases
+    pub async fn compare_schemas(
+        pool_a: &PgPool,
+        pool_b: &PgPool,
+        schema: &str,
+    ) -> Result<SchemaDiff> {
+        let (a, b) = tokio::try_join!(
+            Self::schema_snapshot(pool_a, schema),
+            Self::schema_snapshot(pool_b, schema)
+        )?;
+        Ok(diff_snapshots(&a, &b))
+    }
+
+    async fn schema_snapshot(pool: &PgPool, schema: &str) -> Result<SchemaSnapshot> {
+        let schema_names = [schema.to_string()];
+        let (schemas, columns) = tokio::try_join!(
+            Self::get_schemas_with_tables(pool),
+            Self::get_all_columns(pool, &schema_names)
+        )?;
+
+        let tables: BTreeMap<String, TableInfo> = schemas
+            .into_iter()
+            .filter(|s| s.name == schema)
+            .flat_map(|s| s.tables)
+            .map(|t| (t.name.clone(), t))
+            .collect();
+        let columns = columns.into_iter().map(|t| (t.table, t.columns)).collect();
+
+        let details = try_join_all(tables.keys().map(|table| async move {
+            let (indexes, constraints) = tokio::try_join!(
+                Self::get_indexes(pool, schema, table),
+                Self::get_constraints(pool, schema, table)
+            )?;
+            Ok::<_, DbViewerError>((table.clone(), indexes, constraints))
+        }))
+        .await?;
+        let mut indexes = BTreeMap::new();
+        let mut constraints = BTreeMap::new();
+        for (table, table_indexes, table_constraints) in details {
+            indexes.insert(table.clone(), table_indexes);
+            constraints.insert(table, table_constraints);
+        }
+
+        Ok(SchemaSnapshot {
+            tables,
+            columns,
+            indexes,
+            constraints,
+        })
+    }
+}
+
+/// Pair up items from both sides by name, returning the diff kind for each
+/// name whose items are missing, extra or not `same`
+fn diff_named<'a, T>(
+    a: &'a [T],
+    b: &'a [T],
+    name: impl Fn(&T) -> &str,
+    same: impl Fn(&T, &T) -> bool,
+) -> Vec<(DiffKind, Option<&'a T>, Option<&'a T>)> {
+    let names: BTreeSet<&str> = a.iter().chain(b).map(&name).collect();
+    names
+        .into_iter()
+        .filter_map(|n| {
+            let left = a.iter().find(|x| name(x) == n);
+            let right = b.iter().find(|x| name(x) == n);
+            match (left, right) {
+                (Some(l), Some(r)) if same(l, r) => None,
+                (Some(_), Some(_)) => Some((DiffKind::Changed, left, right)),
+                (Some(_), None) => Some((DiffKind::Missing, left, None)),
+                (None, _) => Some((DiffKind::Extra, None, right)),
+            }
+        })
+        .collect()
+}
+
+/// Names of the compared properties that differ between two columns
+fn column_differences(a: &ColumnInfo, b: &ColumnInfo) -> Vec<String> {
+    let checks = [
+        ("data_type", a.data_type != b.data_type),
+        ("is_nullable", a.is_nullable != b.is_nullable),
+        ("default_value", a.default_value != b.default_value),
+        ("is_primary_key", a.is_primary_key != b.is_primary_key),
+        ("is_foreign_key", a.is_foreign_key != b.is_foreign_key),
+    ];
+    checks
+        .into_iter()
+        .filter(|(_, differs)| *differs)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+fn diff_snapshots(a: &SchemaSnapshot, b: &SchemaSnapshot) -> SchemaDiff {
+    let missing_tables = a
+        .tables
+        .iter()
+        .filter(|(name, _)| !b.tables.contains_key(*name))
+        .map(|(_, t)| t.clone())
+        .collect();
+    let extra_tables = b
+        .tables
+        .iter()
+        .filter(|(name, _)| !a.tables.contains_key(*name))
+        .map(|(_, t)| t.clone())
+        .collect();
+
+    let mut column_diffs = Vec::new();
+    let mut index_diffs = Vec::new();
+    let mut constraint_diffs = Vec::new();
+    let common = a.tables.keys().filter(|t| b.tables.contains_key(*t));
+    for table in common {
+        let empty_columns = Vec::new();
+        let columns_a = a.columns.get(table).unwrap_or(&empty_columns);
+        let columns_b = b.columns.get(table).unwrap_or(&empty_columns);
+        for (kind, left, right) in diff_named(
+            columns_a,
+            columns_b,
+            |c| &c.name,
+            |l, r| column_differences(l, r).is_empty(),
+        ) {
+            column_diffs.push(ColumnDiff {
+                table: table.clone(),
+                column: left.or(right).map(|c| c.name.clone()).unwrap_or_default(),
+                kind,
+                differences: match (left, right) {
+                    (Some(l), Some(r)) => column_differences(l, r),
+                    _ => Vec::new(),
+                },
+                a: left.cloned(),
+                b: right.cloned(),
+            });
+        }
+
+        let empty_indexes = Vec::new();
+        for (kind, left, right) in diff_named(
+            a.indexes.get(table).unwrap_or(&empty_indexes),
+            b.indexes.get(table).unwrap_or(&empty_indexes),
+            |i| &i.name,
+            |l, r| {
+                (l.is_unique, l.is_primary, &l.columns, &l.index_type)
+                    == (r.is_unique, r.is_primary, &r.columns, &r.index_type)
+            },
+        ) {
+            index_diffs.push(IndexDiff {
+                table: table.clone(),
+                name: left.or(right).map(|i| i.name.clone()).unwrap_or_default(),
+                kind,
+                a: left.cloned(),
+                b: right.cloned(),
+            });
+        }
+
+        let empty_constraints = Vec::new();
+        for (kind, left, right) in diff_named(
+            a.constraints.get(table).unwrap_or(&empty_constraints),
+            b.constraints.get(table).unwrap_or(&empty_constraints),
+            |c| &c.name,
+            |l, r| {
+                (&l.constraint_type, &l.columns, &l.definition)
+                    == (&r.constraint_type, &r.columns, &r.definition)
+            },
+        ) {
+            constraint_diffs.push(ConstraintDiff {
+                table: table.clone(),
+                name: left.or(right).map(|c| c.name.clone()).unwrap_or_default(),
+                kind,
+                a: left.cloned(),
+                b: right.cloned(),
+            });
+        }
+    }
+
+    SchemaDiff {
+        missing_tables,
+        extra_tables,
+        column_diffs,
+        index_diffs,
+        constraint_diffs,
+    }
+}
+
 /// Quote an identifier to prevent SQL injection
 fn quote_identifier(identifier: &str) -> String {
     format!("\"{}\"", identifier.replace('"', "\"\""))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{create_test_schema, drop_test_schema, test_pool};
+    use sqlx::Executor;
+
+    #[tokio::test]
+    async fn test_triggers() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.docs (id int, touched timestamptz);
+                 CREATE FUNCTION {s}.touch() RETURNS trigger LANGUAGE plpgsql AS
+                    $$ BEGIN NEW.touched := now(); RETURN NEW; END $$;
+                 CREATE TRIGGER docs_touch BEFORE INSERT OR UPDATE ON {s}.docs
+                    FOR EACH ROW EXECUTE FUNCTION {s}.touch();",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let triggers = SchemaIntrospector::get_triggers(&pool, &schema, "docs")
+            .await
+            .unwrap();
+        assert_eq!(triggers.len(), 1);
+        let trigger = &triggers[0];
+        assert_eq!(trigger.name, "docs_touch");
+        assert_eq!(trigger.event, "INSERT OR UPDATE");
+        assert_eq!(trigger.events, ["INSERT", "UPDATE"]);
+        assert_eq!(trigger.function_name, format!("{}.touch", schema));
+        assert_eq!(trigger.timing, "BEFORE");
+        assert_eq!(trigger.table, "docs");
+        assert!(trigger.enabled);
+        assert!(trigger
+            .definition
+            .starts_with("CREATE TRIGGER docs_touch BEFORE"));
+
+        let definition =
+            SchemaIntrospector::get_trigger_definition(&pool, &schema, "docs", "docs_touch")
+                .await
+                .unwrap();
+        assert_eq!(definition, trigger.definition);
+        let missing =
+            SchemaIntrospector::get_trigger_definition(&pool, &schema, "docs", "nope").await;
+        assert!(missing.is_err());
+
+        SchemaIntrospector::set_trigger_enabled(&pool, &schema, "docs", "docs_touch", false)
+            .await
+            .unwrap();
+        let triggers = SchemaIntrospector::get_triggers(&pool, &schema, "docs")
+            .await
+            .unwrap();
+        assert!(!triggers[0].enabled);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_row_security_policies() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.notes (id int, owner text, body text);
+                 CREATE POLICY own_notes ON {s}.notes
+                    USING (owner = current_user)
+                    WITH CHECK (owner = current_user);
+                 CREATE POLICY no_deletes ON {s}.notes AS RESTRICTIVE FOR DELETE
+                    TO CURRENT_USER USING (false);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        assert!(
+            !SchemaIntrospector::get_rls_enabled(&pool, &schema, "notes")
+                .await
+                .unwrap()
+        );
+        pool.execute(format!("ALTER TABLE {}.notes ENABLE ROW LEVEL SECURITY", schema).as_str())
+            .await
+            .unwrap();
+        assert!(SchemaIntrospector::get_rls_enabled(&pool, &schema, "notes")
+            .await
+            .unwrap());
+        assert!(
+            SchemaIntrospector::get_rls_enabled(&pool, &schema, "missing")
+                .await
+                .is_err()
+        );
+
+        let policies = SchemaIntrospector::get_row_security_policies(&pool, &schema, "notes")
+            .await
+            .unwrap();
+        assert_eq!(policies.len(), 2);
+
+        let no_deletes = &policies[0];
+        assert_eq!(no_deletes.name, "no_deletes");
+        assert_eq!(no_deletes.command, "DELETE");
+        assert!(!no_deletes.permissive);
+        let (user,): (String,) = sqlx::query_as("SELECT current_user::text")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(no_deletes.roles, [user]);
+        assert_eq!(no_deletes.using_expr.as_deref(), Some("false"));
+        assert_eq!(no_deletes.check_expr, None);
+
+        let own_notes = &policies[1];
+        assert_eq!(own_notes.command, "ALL");
+        assert!(own_notes.permissive);
+        assert_eq!(own_notes.roles, ["public"]);
+        assert_eq!(own_notes.table, "notes");
+        assert_eq!(own_notes.schema, schema);
+        assert_eq!(
+            own_notes.using_expr.as_deref(),
+            Some("(owner = CURRENT_USER)")
+        );
+        assert_eq!(own_notes.check_expr, own_notes.using_expr);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_table_stats() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.events (id int PRIMARY KEY);
+                 INSERT INTO {s}.events SELECT generate_series(1, 10);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        // Counters are flushed asynchronously, so only their shape is checked here
+        let stats = SchemaIntrospector::get_table_stats(&pool, &schema, "events")
+            .await
+            .unwrap();
+        assert_eq!(stats.table, "events");
+        assert!(stats.seq_scans >= 0 && stats.idx_scans >= 0);
+        assert!(stats.heap_blks_read >= 0 && stats.heap_blks_hit >= 0);
+        assert!((0.0..=1.0).contains(&stats.cache_hit_ratio));
+        assert_eq!(stats.last_vacuum, None);
+
+        let missing = SchemaIntrospector::get_table_stats(&pool, &schema, "nope").await;
+        assert!(matches!(missing, Err(DbViewerError::TableNotFound(_))));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_table_sizes() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.docs (id int PRIMARY KEY, body text);
+                 ALTER TABLE {s}.docs ALTER COLUMN body SET STORAGE EXTERNAL;
+                 INSERT INTO {s}.docs SELECT i, repeat('x', 10000) FROM generate_series(1, 20) i;
+                 CREATE TABLE {s}.empty (id int);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let size = SchemaIntrospector::get_table_size(&pool, &schema, "docs")
+            .await
+            .unwrap();
+        assert!(size.table_bytes > 0 && size.index_bytes > 0);
+        // The bodies are stored out of line
+        assert!(size.toast_bytes > 20 * 10000);
+        assert_eq!(
+            size.total_bytes,
+            size.table_bytes + size.index_bytes + size.toast_bytes
+        );
+        assert!(size.total_human.ends_with("kB"));
+        assert_eq!(size.row_estimate, None);
+        assert!(size.dead_rows >= 0);
+
+        pool.execute(format!("ANALYZE {}.docs", schema).as_str())
+            .await
+            .unwrap();
+        let size = SchemaIntrospector::get_table_size(&pool, &schema, "docs")
+            .await
+            .unwrap();
+        assert_eq!(size.row_estimate, Some(20));
+
+        let all = SchemaIntrospector::get_all_table_sizes(&pool, &schema)
+            .await
+            .unwrap();
+        let names: Vec<&str> = all.iter().map(|s| s.table.as_str()).collect();
+        assert_eq!(names, ["docs", "empty"]);
+        assert_eq!(all[1].total_bytes, 0);
+
+        let missing = SchemaIntrospector::get_table_size(&pool, &schema, "nope").await;
+        assert!(matches!(missing, Err(DbViewerError::TableNotFound(_))));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_get_index_usage_stats() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.big (id int PRIMARY KEY, label text);
+                 CREATE INDEX big_label ON {s}.big (label);
+                 INSERT INTO {s}.big SELECT i, 'row ' || i FROM generate_series(1, 2000) i;
+                 CREATE TABLE {s}.small (id int PRIMARY KEY);
+                 INSERT INTO {s}.small VALUES (1);
+                 ANALYZE {s}.big;
+                 ANALYZE {s}.small;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let stats = SchemaIntrospector::get_index_usage_stats(&pool, &schema)
+            .await
+            .unwrap();
+        let mut names: Vec<&str> = stats.iter().map(|s| s.index.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["big_label", "big_pkey", "small_pkey"]);
+        for stat in &stats {
+            assert_eq!(stat.idx_scan, 0);
+            assert!(stat.size_bytes > 0);
+            assert_eq!(stat.is_unused, stat.table == "big", "{:?}", stat);
+        }
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_get_column_stats() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.people (id int, team text, note text);
+                 INSERT INTO {s}.people
+                   SELECT i, CASE WHEN i % 4 = 0 THEN 'red' ELSE 'blue' END, NULL
+                   FROM generate_series(1, 100) i;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        // Not analyzed yet: every column is listed, without stats
+        let stats = SchemaIntrospector::get_column_stats(&pool, &schema, "people")
+            .await
+            .unwrap();
+        let columns: Vec<&str> = stats.iter().map(|s| s.column.as_str()).collect();
+        assert_eq!(columns, ["id", "team", "note"]);
+        assert!(stats
+            .iter()
+            .all(|s| s.null_frac.is_none() && s.n_distinct.is_none()));
+
+        pool.execute(format!("ANALYZE {}.people", schema).as_str())
+            .await
+            .unwrap();
+        let stats = SchemaIntrospector::get_column_stats(&pool, &schema, "people")
+            .await
+            .unwrap();
+        assert_eq!(stats[0].n_distinct, Some(-1.0));
+        assert_eq!(stats[1].null_frac, Some(0.0));
+        assert_eq!(stats[1].n_distinct, Some(2.0));
+        assert_eq!(
+            stats[1].most_common_vals.as_deref(),
+            Some(&["blue".to_string(), "red".to_string()][..])
+        );
+        assert!(stats[1].avg_width.is_some());
+        assert_eq!(stats[2].null_frac, Some(1.0));
+
+        let missing = SchemaIntrospector::get_column_stats(&pool, &schema, "nope").await;
+        assert!(matches!(missing, Err(DbViewerError::TableNotFound(_))));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_get_view_definition() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.orders (id int PRIMARY KEY, total numeric);
+                 CREATE VIEW {s}.big_orders AS SELECT id FROM {s}.orders WHERE total > 100
+                   WITH CHECK OPTION;
+                 CREATE MATERIALIZED VIEW {s}.order_totals AS
+                   SELECT sum(total) AS total FROM {s}.orders WITH NO DATA;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let view = SchemaIntrospector::get_view_definition(&pool, &schema, "big_orders")
+            .await
+            .unwrap();
+        assert!(!view.materialized);
+        assert_eq!(view.populated, None);
+        assert!(view.definition.contains("total > 100"));
+        assert!(view.is_updatable);
+        assert_eq!(view.check_option.as_deref(), Some("CASCADED"));
+
+        let matview = SchemaIntrospector::get_view_definition(&pool, &schema, "order_totals")
+            .await
+            .unwrap();
+        assert!(matview.materialized);
+        assert_eq!(matview.populated, Some(false));
+        assert!(matview.definition.contains("sum("));
+        assert!(!matview.is_updatable);
+        assert_eq!(matview.check_option, None);
+
+        let views = SchemaIntrospector::get_views_with_definitions(&pool, &schema)
+            .await
+            .unwrap();
+        let names: Vec<_> = views.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["big_orders", "order_totals"]);
+        assert!(views.iter().all(|v| !v.definition.is_empty()));
+
+        pool.execute(format!("REFRESH MATERIALIZED VIEW {}.order_totals", schema).as_str())
+            .await
+            .unwrap();
+        let matview = SchemaIntrospector::get_view_definition(&pool, &schema, "order_totals")
+            .await
+            .unwrap();
+        assert_eq!(matview.populated, Some(true));
+
+        // Tables and missing relations are not views
+        for name in ["orders", "nope"] {
+            let missing = SchemaIntrospector::get_view_definition(&pool, &schema, name).await;
+            assert!(matches!(missing, Err(DbViewerError::TableNotFound(_))));
+        }
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_row_count_estimate() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.items (id int);
+                 INSERT INTO {s}.items SELECT generate_series(1, 1000);
+                 CREATE MATERIALIZED VIEW {s}.evens AS SELECT id FROM {s}.items WHERE id % 2 = 0;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        // Never analyzed: falls back to an exact count
+        let count = SchemaIntrospector::get_row_count_estimate(&pool, &schema, "items")
+            .await
+            .unwrap();
+        assert_eq!(count, 1000);
+
+        pool.execute(format!("ANALYZE {s}.items; ANALYZE {s}.evens;", s = schema).as_str())
+            .await
+            .unwrap();
+        let count = SchemaIntrospector::get_row_count_estimate(&pool, &schema, "items")
+            .await
+            .unwrap();
+        assert_eq!(count, 1000);
+        let count = SchemaIntrospector::get_row_count_estimate(&pool, &schema, "evens")
+            .await
+            .unwrap();
+        assert_eq!(count, 500);
+
+        let missing = SchemaIntrospector::get_row_count_estimate(&pool, &schema, "nope").await;
+        assert!(matches!(missing, Err(DbViewerError::TableNotFound(_))));
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_sequences() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {s}.orders (id serial PRIMARY KEY);
+                 CREATE SEQUENCE {s}.invoice_no AS int START 100 INCREMENT 5 CYCLE MAXVALUE 1000;
+                 INSERT INTO {s}.orders DEFAULT VALUES;
+                 INSERT INTO {s}.orders DEFAULT VALUES;",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let sequences = SchemaIntrospector::get_sequences(&pool, &schema)
+            .await
+            .unwrap();
+        assert_eq!(sequences.len(), 2);
+
+        let invoice = &sequences[0];
+        assert_eq!(invoice.name, "invoice_no");
+        assert_eq!(invoice.data_type, "integer");
+        assert_eq!((invoice.start, invoice.increment), (100, 5));
+        assert_eq!((invoice.min_value, invoice.max_value), (1, 1000));
+        assert!(invoice.cycle);
+        assert_eq!(invoice.owned_by, None);
+        assert_eq!(invoice.last_value, None);
+
+        let orders = &sequences[1];
+        assert_eq!(orders.name, "orders_id_seq");
+        assert_eq!(orders.last_value, Some(2));
+        assert_eq!(orders.owned_by.as_deref(), Some("orders.id"));
+
+        // Listing doesn't advance anything
+        let again = SchemaIntrospector::get_sequences(&pool, &schema)
+            .await
+            .unwrap();
+        assert_eq!(again[1].last_value, Some(2));
+        assert_eq!(again[0].last_value, None);
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_functions() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE FUNCTION {s}.add(a int, b numeric) RETURNS numeric
+                     LANGUAGE sql IMMUTABLE AS 'SELECT a + b';
+                 CREATE PROCEDURE {s}.touch() LANGUAGE plpgsql SECURITY DEFINER
+                     AS $$ BEGIN END $$;
+                 CREATE AGGREGATE {s}.total(int) (SFUNC = int4pl, STYPE = int);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let functions = SchemaIntrospector::get_functions(&pool, &schema)
+            .await
+            .unwrap();
+        let names: Vec<&str> = functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["add", "total", "touch"]);
+
+        let add = &functions[0];
+        assert!(matches!(add.kind, FunctionKind::Function));
+        assert_eq!(add.language, "sql");
+        assert_eq!(add.argument_types, ["integer", "numeric"]);
+        assert_eq!(add.return_type, "numeric");
+        assert_eq!(add.volatility, "immutable");
+        assert!(add
+            .definition
+            .as_deref()
+            .is_some_and(|d| d.contains("CREATE OR REPLACE FUNCTION")));
+
+        let total = &functions[1];
+        assert!(matches!(total.kind, FunctionKind::Aggregate));
+        assert_eq!(total.definition, None);
+
+        let touch = &functions[2];
+        assert!(matches!(touch.kind, FunctionKind::Procedure));
+        assert!(touch.security_definer);
+        assert_eq!(touch.return_type, "");
+        assert!(touch.argument_types.is_empty());
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_composite_types_and_domains() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TYPE {s}.address AS (street text, zip varchar(10));
+                 CREATE TYPE {s}.nothing AS ();
+                 CREATE DOMAIN {s}.percent AS numeric(5, 2) NOT NULL DEFAULT 0
+                     CONSTRAINT at_most_100 CHECK (VALUE <= 100)
+                     CONSTRAINT at_least_0 CHECK (VALUE >= 0);
+                 CREATE DOMAIN {s}.note AS text;
+                 CREATE TABLE {s}.shipments (id int, dest {s}.address);",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let types = SchemaIntrospector::get_composite_types(&pool, &schema)
+            .await
+            .unwrap();
+        let names: Vec<&str> = types.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["address", "nothing"]);
+        let attributes: Vec<(&str, &str, bool)> = types[0]
+            .attributes
+            .iter()
+            .map(|a| (a.name.as_str(), a.data_type.as_str(), a.is_nullable))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("street", "text", true),
+                ("zip", "character varying(10)", true)
+            ]
+        );
+        assert!(types[1].attributes.is_empty());
+
+        let domains = SchemaIntrospector::get_domains(&pool, &schema)
+            .await
+            .unwrap();
+        let names: Vec<&str> = domains.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["note", "percent"]);
+        let note = &domains[0];
+        assert_eq!(note.base_type, "text");
+        assert!(!note.not_null);
+        assert_eq!(note.default_value, None);
+        assert!(note.check_constraints.is_empty());
+        let percent = &domains[1];
+        assert_eq!(percent.base_type, "numeric(5,2)");
+        assert!(percent.not_null);
+        assert_eq!(percent.default_value.as_deref(), Some("0"));
+        assert_eq!(
+            percent.check_constraints,
+            [
+                "CHECK ((VALUE >= (0)::numeric))",
+                "CHECK ((VALUE <= (100)::numeric))"
+            ]
+        );
+
+        drop_test_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_compare_schemas() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let schema_a = create_test_schema(&pool).await;
+        let schema_b = create_test_schema(&pool).await;
+        pool.execute(
+            format!(
+                "CREATE TABLE {a}.users (
+                    id int PRIMARY KEY,
+                    email text NOT NULL,
+                    age int CONSTRAINT age_positive CHECK (age > 0)
+                 );
+                 CREATE INDEX users_email ON {a}.users (email);
+                 CREATE TABLE {a}.posts (id int);
+                 CREATE TABLE {b}.users (id int PRIMARY KEY, email varchar(100), nickname text);
+                 CREATE INDEX users_email ON {b}.users (email, id);
+                 CREATE TABLE {b}.comments (id int);",
+                a = schema_a,
+                b = schema_b
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        // Two schemas in one database stand in for one schema in two databases
+        let (a, b) = tokio::try_join!(
+            SchemaIntrospector::schema_snapshot(&pool, &schema_a),
+            SchemaIntrospector::schema_snapshot(&pool, &schema_b)
+        )
+        .unwrap();
+        let diff = diff_snapshots(&a, &b);
+
+        assert_eq!(diff.missing_tables.len(), 1);
+        assert_eq!(diff.missing_tables[0].name, "posts");
+        assert_eq!(diff.extra_tables.len(), 1);
+        assert_eq!(diff.extra_tables[0].name, "comments");
+
+        let columns: Vec<(&str, DiffKind, Vec<String>)> = diff
+            .column_diffs
+            .iter()
+            .map(|d| (d.column.as_str(), d.kind, d.differences.clone()))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("age", DiffKind::Missing, vec![]),
+                (
+                    "email",
+                    DiffKind::Changed,
+                    vec!["data_type".to_string(), "is_nullable".to_string()]
+                ),
+                ("nickname", DiffKind::Extra, vec![]),
+            ]
+        );
+
+        assert_eq!(diff.index_diffs.len(), 1);
+        assert_eq!(
+            (diff.index_diffs[0].name.as_str(), diff.index_diffs[0].kind),
+            ("users_email", DiffKind::Changed)
+        );
+        assert_eq!(diff.constraint_diffs.len(), 1);
+        assert_eq!(
+            (
+                diff.constraint_diffs[0].name.as_str(),
+                diff.constraint_diffs[0].kind
+            ),
+            ("age_positive", DiffKind::Missing)
+        );
+
+        drop_test_schema(&pool, &schema_a).await;
+        drop_test_schema(&pool, &schema_b).await;
+    }
+}
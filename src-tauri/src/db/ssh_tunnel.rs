@@ -0,0 +1,366 @@
+use crate::error::{DbViewerError, Result};
+#[cfg(feature = "ssh-tunnel")]
+use russh::client::{self, Handle};
+#[cfg(feature = "ssh-tunnel")]
+use russh::keys::{self, HashAlg, PrivateKeyWithHashAlg, PublicKeyOrCertificate};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+#[cfg(feature = "ssh-tunnel")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "ssh-tunnel")]
+use std::time::Duration;
+#[cfg(feature = "ssh-tunnel")]
+use tokio::net::TcpListener;
+#[cfg(feature = "ssh-tunnel")]
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshTunnelConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub auth: SshAuth,
+    /// SHA256 fingerprint of a host key the user confirmed for a host that
+    /// is not in known_hosts. It is added to known_hosts once seen.
+    #[serde(default)]
+    pub accepted_host_key: Option<String>,
+}
+
+/// The password and passphrase are never serialized, so they stay out of the
+/// saved connection list; `CredentialStorage` keeps them in the keyring.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SshAuth {
+    Password {
+        #[serde(default, skip_serializing)]
+        password: String,
+    },
+    PrivateKey {
+        path: PathBuf,
+        #[serde(default, skip_serializing)]
+        passphrase: Option<String>,
+    },
+}
+
+impl SshAuth {
+    /// The password or key passphrase, if there is one
+    pub fn secret(&self) -> Option<&str> {
+        match self {
+            SshAuth::Password { password } => Some(password),
+            SshAuth::PrivateKey { passphrase, .. } => passphrase.as_deref(),
+        }
+    }
+
+    pub fn set_secret(&mut self, secret: String) {
+        match self {
+            SshAuth::Password { password } => *password = secret,
+            SshAuth::PrivateKey { passphrase, .. } => *passphrase = Some(secret),
+        }
+    }
+}
+
+/// Accepts host keys recorded in `~/.ssh/known_hosts`, or the one the user
+/// accepted by fingerprint. Any other key is refused; an unknown one has its
+/// fingerprint left in `unknown_key` for the caller to show.
+#[cfg(feature = "ssh-tunnel")]
+struct TunnelHandler {
+    host: String,
+    port: u16,
+    accepted_host_key: Option<String>,
+    unknown_key: Arc<Mutex<Option<String>>>,
+}
+
+#[cfg(feature = "ssh-tunnel")]
+impl client::Handler for TunnelHandler {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &PublicKeyOrCertificate,
+    ) -> std::result::Result<bool, Self::Error> {
+        let PublicKeyOrCertificate::PublicKey { key, .. } = server_public_key else {
+            return Ok(false);
+        };
+        match keys::check_known_hosts(&self.host, self.port, key) {
+            Ok(true) => Ok(true),
+            Ok(false) => {
+                let fingerprint = key.fingerprint(HashAlg::Sha256).to_string();
+                if self.accepted_host_key.as_deref() != Some(fingerprint.as_str()) {
+                    *self.unknown_key.lock().unwrap() = Some(fingerprint);
+                    return Ok(false);
+                }
+                if let Err(e) = keys::known_hosts::learn_known_hosts(&self.host, self.port, key) {
+                    log::warn!("Could not add {} to known_hosts: {}", self.host, e);
+                }
+                Ok(true)
+            }
+            Err(keys::Error::KeyChanged { line }) => {
+                log::warn!(
+                    "SSH host key for {}:{} does not match known_hosts line {}",
+                    self.host,
+                    self.port,
+                    line
+                );
+                Ok(false)
+            }
+            Err(e) => {
+                log::warn!("Could not read known_hosts: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// A local TCP forward through an SSH server. Connections to `127.0.0.1:local_port`
+/// are relayed to the remote host and port as seen from the SSH server.
+#[cfg(feature = "ssh-tunnel")]
+pub struct SshTunnel {
+    local_port: u16,
+    session: Arc<Handle<TunnelHandler>>,
+    accept_task: JoinHandle<()>,
+}
+
+#[cfg(feature = "ssh-tunnel")]
+impl std::fmt::Debug for SshTunnel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SshTunnel")
+            .field("local_port", &self.local_port)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "ssh-tunnel")]
+impl SshTunnel {
+    /// Connect and authenticate to the SSH server, then forward a random local
+    /// port to `remote_host:remote_port`
+    pub async fn open(
+        config: &SshTunnelConfig,
+        remote_host: &str,
+        remote_port: u16,
+    ) -> Result<Self> {
+        let tunnel_err = |e: russh::Error| DbViewerError::SshTunnel(e.to_string());
+
+        let ssh_config = Arc::new(client::Config {
+            keepalive_interval: Some(Duration::from_secs(30)),
+            ..Default::default()
+        });
+        let unknown_key = Arc::new(Mutex::new(None));
+        let handler = TunnelHandler {
+            host: config.host.clone(),
+            port: config.port,
+            accepted_host_key: config.accepted_host_key.clone(),
+            unknown_key: unknown_key.clone(),
+        };
+        let connect = client::connect(ssh_config, (config.host.as_str(), config.port), handler);
+        let mut session = tokio::time::timeout(Duration::from_secs(10), connect)
+            .await
+            .map_err(|_| {
+                DbViewerError::SshTunnel(format!(
+                    "Timed out connecting to {}:{}",
+                    config.host, config.port
+                ))
+            })?
+            .map_err(|e| match unknown_key.lock().unwrap().take() {
+                Some(fingerprint) => DbViewerError::UnknownHostKey {
+                    host: config.host.clone(),
+                    fingerprint,
+                },
+                None => tunnel_err(e),
+            })?;
+
+        let auth = match &config.auth {
+            SshAuth::Password { password } => session
+                .authenticate_password(&config.username, password)
+                .await
+                .map_err(tunnel_err)?,
+            SshAuth::PrivateKey { path, passphrase } => {
+                let key = keys::load_secret_key(path, passphrase.as_deref()).map_err(|e| {
+                    DbViewerError::SshTunnel(format!(
+                        "Failed to load private key {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                let hash_alg = session
+                    .best_supported_rsa_hash()
+                    .await
+                    .map_err(tunnel_err)?
+                    .flatten();
+                session
+                    .authenticate_publickey(
+                        &config.username,
+                        PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg),
+                    )
+                    .await
+                    .map_err(tunnel_err)?
+            }
+        };
+        if !auth.success() {
+            return Err(DbViewerError::SshTunnel(format!(
+                "Authentication failed for {}@{}",
+                config.username, config.host
+            )));
+        }
+
+        let listener = TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .map_err(|e| DbViewerError::SshTunnel(format!("Failed to bind local port: {}", e)))?;
+        let local_port = listener
+            .local_addr()
+            .map_err(|e| DbViewerError::SshTunnel(e.to_string()))?
+            .port();
+
+        let session = Arc::new(session);
+        let accept_task = tokio::spawn(Self::forward(
+            listener,
+            session.clone(),
+            remote_host.to_string(),
+            remote_port,
+        ));
+
+        Ok(Self {
+            local_port,
+            session,
+            accept_task,
+        })
+    }
+
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    /// Stop accepting connections and end the SSH session
+    pub async fn close(self) {
+        self.accept_task.abort();
+        let _ = self
+            .session
+            .disconnect(russh::Disconnect::ByApplication, "", "en")
+            .await;
+    }
+
+    /// Relay each local connection over its own direct-tcpip channel
+    async fn forward(
+        listener: TcpListener,
+        session: Arc<Handle<TunnelHandler>>,
+        remote_host: String,
+        remote_port: u16,
+    ) {
+        while let Ok((mut socket, peer)) = listener.accept().await {
+            let session = session.clone();
+            let remote_host = remote_host.clone();
+            tokio::spawn(async move {
+                let channel = session
+                    .channel_open_direct_tcpip(
+                        remote_host,
+                        remote_port as u32,
+                        peer.ip().to_string(),
+                        peer.port() as u32,
+                    )
+                    .await;
+                match channel {
+                    Ok(channel) => {
+                        let mut stream = channel.into_stream();
+                        let _ = tokio::io::copy_bidirectional(&mut socket, &mut stream).await;
+                    }
+                    Err(e) => log::warn!("SSH tunnel channel failed: {}", e),
+                }
+            });
+        }
+    }
+}
+
+#[cfg(feature = "ssh-tunnel")]
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+/// Stand-in for builds without the `ssh-tunnel` feature; opening one fails
+#[cfg(not(feature = "ssh-tunnel"))]
+#[derive(Debug)]
+pub struct SshTunnel {
+    local_port: u16,
+}
+
+#[cfg(not(feature = "ssh-tunnel"))]
+impl SshTunnel {
+    pub async fn open(
+        config: &SshTunnelConfig,
+        _remote_host: &str,
+        _remote_port: u16,
+    ) -> Result<Self> {
+        Err(DbViewerError::SshTunnel(format!(
+            "Cannot connect through {}: this build has no SSH tunnel support",
+            config.host
+        )))
+    }
+
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    pub async fn close(self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_serialization() {
+        let config: SshTunnelConfig = serde_json::from_value(serde_json::json!({
+            "host": "bastion.example.com",
+            "port": 22,
+            "username": "deploy",
+            "auth": {"private_key": {"path": "/home/me/.ssh/id_ed25519", "passphrase": "s3cret"}}
+        }))
+        .unwrap();
+        assert!(matches!(
+            config.auth,
+            SshAuth::PrivateKey { ref path, passphrase: Some(ref p) }
+                if path.ends_with("id_ed25519") && p == "s3cret"
+        ));
+        assert_eq!(config.accepted_host_key, None);
+
+        // Secrets are read but never written back out
+        let saved = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            saved["auth"],
+            serde_json::json!({"private_key": {"path": "/home/me/.ssh/id_ed25519"}})
+        );
+        let auth = serde_json::to_value(SshAuth::Password {
+            password: "hunter2".to_string(),
+        })
+        .unwrap();
+        assert_eq!(auth, serde_json::json!({"password": {}}));
+    }
+
+    #[tokio::test]
+    async fn test_open_unreachable_host() {
+        // Grab a free port, then close it so nothing is listening there
+        let port = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = SshTunnelConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            username: "nobody".to_string(),
+            auth: SshAuth::Password {
+                password: String::new(),
+            },
+            accepted_host_key: None,
+        };
+
+        let err = SshTunnel::open(&config, "db.internal", 5432)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DbViewerError::SshTunnel(_)),
+            "unexpected error: {err}"
+        );
+    }
+}
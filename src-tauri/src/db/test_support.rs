@@ -0,0 +1,37 @@
+//! Helpers for tests that run against a live PostgreSQL server.
+
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Executor, PgPool};
+
+/// The URL in `TUSKER_TEST_DATABASE_URL`; tests that need a live server are
+/// skipped when it isn't set.
+pub fn test_url() -> Option<String> {
+    std::env::var("TUSKER_TEST_DATABASE_URL").ok()
+}
+
+/// Connect to the database in `TUSKER_TEST_DATABASE_URL`, if set
+pub async fn test_pool() -> Option<PgPool> {
+    let url = test_url()?;
+    Some(
+        PgPoolOptions::new()
+            .max_connections(4)
+            .connect(&url)
+            .await
+            .expect("failed to connect to TUSKER_TEST_DATABASE_URL"),
+    )
+}
+
+/// Create a throwaway schema for a test, returning its name
+pub async fn create_test_schema(pool: &PgPool) -> String {
+    let schema = format!("tusker_test_{}", uuid::Uuid::new_v4().simple());
+    pool.execute(format!("CREATE SCHEMA {}", schema).as_str())
+        .await
+        .unwrap();
+    schema
+}
+
+pub async fn drop_test_schema(pool: &PgPool, schema: &str) {
+    pool.execute(format!("DROP SCHEMA {} CASCADE", schema).as_str())
+        .await
+        .unwrap();
+}
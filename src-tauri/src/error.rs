@@ -24,6 +24,12 @@ pub enum DbViewerError {
     #[error("Invalid query: {0}")]
     InvalidQuery(String),
 
+    #[error("Query timed out after {0}")]
+    QueryTimeout(String),
+
+    #[error("Query cancelled")]
+    Cancelled,
+
     #[error("Table not found: {0}")]
     TableNotFound(String),
 
@@ -38,6 +44,20 @@ pub enum DbViewerError {
 
     #[error("Export error: {0}")]
     Export(String),
+
+    #[error("Import error: {0}")]
+    Import(String),
+
+    #[error("SSH tunnel error: {0}")]
+    SshTunnel(String),
+
+    /// The SSH server's key is not in known_hosts; connect again with
+    /// `accepted_host_key` set to `fingerprint` once the user confirms it
+    #[error("Unknown SSH host key for {host}: {fingerprint}")]
+    UnknownHostKey { host: String, fingerprint: String },
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 impl From<keyring::Error> for DbViewerError {
@@ -69,11 +89,20 @@ impl From<&DbViewerError> for ErrorResponse {
                 ("SERIALIZATION_ERROR".to_string(), Some(e.to_string()))
             }
             DbViewerError::InvalidQuery(_) => ("INVALID_QUERY".to_string(), None),
+            DbViewerError::QueryTimeout(_) => ("QUERY_TIMEOUT".to_string(), None),
+            DbViewerError::Cancelled => ("CANCELLED".to_string(), None),
             DbViewerError::TableNotFound(_) => ("TABLE_NOT_FOUND".to_string(), None),
             DbViewerError::SchemaNotFound(_) => ("SCHEMA_NOT_FOUND".to_string(), None),
             DbViewerError::Lock(_) => ("LOCK_ERROR".to_string(), None),
             DbViewerError::Configuration(_) => ("CONFIGURATION_ERROR".to_string(), None),
             DbViewerError::Export(_) => ("EXPORT_ERROR".to_string(), None),
+            DbViewerError::Import(_) => ("IMPORT_ERROR".to_string(), None),
+            DbViewerError::SshTunnel(_) => ("SSH_TUNNEL_ERROR".to_string(), None),
+            DbViewerError::UnknownHostKey { fingerprint, .. } => (
+                "SSH_HOST_KEY_UNKNOWN".to_string(),
+                Some(fingerprint.clone()),
+            ),
+            DbViewerError::Io(e) => ("IO_ERROR".to_string(), Some(e.to_string())),
         };
 
         ErrorResponse {
@@ -95,3 +124,53 @@ impl Serialize for DbViewerError {
 }
 
 pub type Result<T> = std::result::Result<T, DbViewerError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_error_shape() {
+        let cases = [
+            (
+                DbViewerError::Export("Failed to write file: disk full".to_string()),
+                serde_json::json!({
+                    "code": "EXPORT_ERROR",
+                    "message": "Export error: Failed to write file: disk full",
+                    "details": null,
+                }),
+            ),
+            (
+                DbViewerError::Import("Incorrect password or corrupted file".to_string()),
+                serde_json::json!({
+                    "code": "IMPORT_ERROR",
+                    "message": "Import error: Incorrect password or corrupted file",
+                    "details": null,
+                }),
+            ),
+            (
+                DbViewerError::UnknownHostKey {
+                    host: "bastion.example.com".to_string(),
+                    fingerprint: "SHA256:abc".to_string(),
+                },
+                serde_json::json!({
+                    "code": "SSH_HOST_KEY_UNKNOWN",
+                    "message": "Unknown SSH host key for bastion.example.com: SHA256:abc",
+                    "details": "SHA256:abc",
+                }),
+            ),
+            (
+                std::io::Error::new(std::io::ErrorKind::NotFound, "no such file").into(),
+                serde_json::json!({
+                    "code": "IO_ERROR",
+                    "message": "IO error: no such file",
+                    "details": "no such file",
+                }),
+            ),
+        ];
+
+        for (err, expected) in cases {
+            assert_eq!(serde_json::to_value(&err).unwrap(), expected);
+        }
+    }
+}
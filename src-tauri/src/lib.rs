@@ -54,10 +54,18 @@ pub fn run() {
             commands::connect_saved,
             commands::disconnect,
             commands::disconnect_all,
+            commands::reconnect,
+            commands::set_auto_reconnect,
             commands::test_connection,
+            commands::parse_connection_url,
+            commands::connect_url,
             commands::list_active_connections,
             commands::is_connected,
+            commands::get_pool_stats,
+            commands::set_max_connections,
             commands::ping_database,
+            commands::start_keepalive,
+            commands::stop_keepalive,
             // Saved connections commands
             commands::get_saved_connections,
             commands::save_connection,
@@ -74,24 +82,69 @@ pub fn run() {
             commands::get_row_count,
             commands::get_indexes,
             commands::get_constraints,
+            commands::get_row_security_policies,
+            commands::get_triggers,
+            commands::get_trigger_definition,
+            commands::get_view_definition,
+            commands::get_views_with_definitions,
+            commands::toggle_trigger,
+            commands::get_table_stats,
+            commands::get_table_size,
+            commands::get_all_table_sizes,
+            commands::get_table_column_stats,
+            commands::get_index_usage_stats,
+            commands::get_sequences,
+            commands::get_composite_types,
+            commands::get_domains,
+            commands::get_functions,
+            commands::compare_schemas,
             // Data commands
             commands::fetch_table_data,
+            commands::fetch_keyset,
+            commands::get_distinct_values,
+            commands::get_column_stats,
+            commands::reset_sequence,
+            commands::set_sequence_value,
             commands::insert_row,
+            commands::upsert_row,
             commands::bulk_insert,
+            commands::import_csv,
+            commands::duplicate_row,
             commands::update_row,
             commands::delete_row,
+            commands::bulk_delete,
+            commands::update_by_filter,
+            commands::count_matching_rows,
+            commands::truncate_table,
             commands::execute_query,
+            commands::cancel_query,
+            commands::explain_query,
             commands::execute_migration,
+            commands::execute_transaction,
+            commands::generate_rollback_sql,
             // Utility commands
             commands::get_database_info,
+            commands::list_activity,
+            commands::terminate_backend,
             // Commit history commands
             commands::save_commit,
             commands::get_commits,
             commands::get_commit_detail,
+            commands::revert_commit,
+            commands::search_commits,
+            // Query history commands
+            commands::get_query_history,
+            commands::search_query_history,
+            commands::clear_query_history,
             // Export/Import commands
             commands::export_connections,
             commands::import_connections,
             commands::check_export_file,
+            commands::export_table_csv,
+            commands::download_cell,
+            commands::fetch_full_cell,
+            commands::fetch_referenced_row,
+            commands::get_referencing_rows,
             // Discovery commands
             commands::discover_local_databases,
             commands::get_current_username,
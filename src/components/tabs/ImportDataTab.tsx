@@ -505,9 +505,7 @@ export function ImportDataTab({ tab }: ImportDataTabProps) {
 
         await invoke("bulk_insert", {
           connectionId,
-          schema,
-          table,
-          rows: batch,
+          request: { schema, table, rows: batch },
         });
 
         importedCount += batch.length;
@@ -5,6 +5,9 @@ import { useProjectStore } from "../stores/projectStore";
 import { useUIStore } from "../stores/uiStore";
 import { useChangesStore } from "../stores/changesStore";
 
+// Longer text, json and bytea cells come back cut short in the grid
+const MAX_CELL_BYTES = 16 * 1024;
+
 /**
  * Compatibility shim: derives the active connection ID from the currently active tab.
  * Prefer receiving connectionId as a prop where possible.
@@ -22,6 +25,7 @@ interface ConnectRequest {
   password: string;
   ssl_mode?: "disable" | "prefer" | "require";
   save_connection?: boolean;
+  read_only?: boolean;
 }
 
 interface ConnectResponse {
@@ -87,6 +91,7 @@ export function useConnect() {
         password,
         ssl_mode: project.connection.ssl ? "require" : "disable",
         save_connection: false,
+        read_only: project.settings.readOnly,
       };
 
       const result = await invoke<ConnectResponse>("connect", { request });
@@ -310,6 +315,7 @@ export function useTableData(
             order_by: orderByColumns,
             order_direction: orderDirections,
             filters: filters.length > 0 ? filters : undefined,
+            max_cell_bytes: MAX_CELL_BYTES,
           },
         }),
       ]);
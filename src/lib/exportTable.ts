@@ -3,11 +3,15 @@ import { save } from "@tauri-apps/plugin-dialog";
 import { writeTextFile } from "@tauri-apps/plugin-fs";
 import type { Row } from "../types";
 
-type ExportFormat = "csv" | "json";
+export type ExportFormat = "csv" | "json";
 
-interface PaginatedResult {
+interface PageCursor {
+  values: unknown[];
+}
+
+interface KeysetPage {
   rows: Row[];
-  total_count: number;
+  next_cursor: PageCursor | null;
 }
 
 export async function exportTable(
@@ -37,29 +41,8 @@ export async function exportTable(
       return;
     }
 
-    // Fetch all rows
-    const result = await invoke<PaginatedResult>("fetch_table_data", {
-      request: {
-        connection_id: connectionId,
-        schema,
-        table,
-        page: 1,
-        page_size: 100000, // Large number to get all rows
-      },
-    });
-
-    const rows = result.rows;
-
-    let content: string;
-    if (format === "csv") {
-      content = convertToCSV(rows);
-    } else {
-      content = JSON.stringify(rows, null, 2);
-    }
-
-    // Write file
-    await writeTextFile(filePath, content);
-    onSuccess(`Exported ${rows.length.toLocaleString()} rows to ${format.toUpperCase()}`);
+    const count = await exportTableToFile(connectionId, schema, table, format, filePath);
+    onSuccess(`Exported ${count.toLocaleString()} rows to ${format.toUpperCase()}`);
   } catch (err) {
     console.error("Export error:", err);
     const message = err instanceof Error
@@ -71,43 +54,57 @@ export async function exportTable(
   }
 }
 
-function convertToCSV(rows: Row[]): string {
-  if (rows.length === 0) return "";
-
-  const headers = Object.keys(rows[0]);
-  const csvRows: string[] = [];
-
-  // Header row
-  csvRows.push(headers.map(escapeCSVValue).join(","));
-
-  // Data rows
-  for (const row of rows) {
-    const values = headers.map((header) => {
-      const value = row[header];
-      return escapeCSVValue(value);
+/**
+ * Write a table to `filePath` and return the number of rows written. CSV is
+ * streamed to the file by the backend; JSON is read with keyset paging.
+ */
+export async function exportTableToFile(
+  connectionId: string,
+  schema: string,
+  table: string,
+  format: ExportFormat,
+  filePath: string
+): Promise<number> {
+  if (format === "csv") {
+    return invoke<number>("export_table_csv", {
+      connectionId,
+      schema,
+      table,
+      filePath,
     });
-    csvRows.push(values.join(","));
   }
 
-  return csvRows.join("\n");
+  const rows = await fetchAllRows(connectionId, schema, table);
+  await writeTextFile(filePath, JSON.stringify(rows, null, 2));
+  return rows.length;
 }
 
-function escapeCSVValue(value: unknown): string {
-  if (value === null || value === undefined) {
-    return "";
-  }
-
-  let stringValue: string;
-  if (typeof value === "object") {
-    stringValue = JSON.stringify(value);
-  } else {
-    stringValue = String(value);
-  }
-
-  // Escape quotes and wrap in quotes if contains comma, newline, or quote
-  if (stringValue.includes(",") || stringValue.includes("\n") || stringValue.includes('"')) {
-    return `"${stringValue.replace(/"/g, '""')}"`;
-  }
+// The backend caps page_size at this many rows
+const EXPORT_PAGE_SIZE = 1000;
 
-  return stringValue;
+/**
+ * Fetch every row of a table, one keyset page at a time. Pages follow the
+ * primary key, so no row is skipped or repeated and each read is a seek.
+ */
+export async function fetchAllRows(
+  connectionId: string,
+  schema: string,
+  table: string
+): Promise<Row[]> {
+  const rows: Row[] = [];
+  let cursor: PageCursor | null = null;
+  do {
+    const page: KeysetPage = await invoke<KeysetPage>("fetch_keyset", {
+      request: {
+        connection_id: connectionId,
+        schema,
+        table,
+        page_size: EXPORT_PAGE_SIZE,
+        cursor,
+      },
+    });
+    rows.push(...page.rows);
+    cursor = page.next_cursor;
+  } while (cursor);
+  return rows;
 }